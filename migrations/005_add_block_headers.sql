@@ -0,0 +1,17 @@
+-- Track indexed block headers per chain for reorg detection
+CREATE TABLE IF NOT EXISTS block_headers (
+    chain_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_hash VARCHAR(66) NOT NULL,
+    parent_hash VARCHAR(66) NOT NULL,
+    block_timestamp TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (chain_id, block_number)
+);
+
+-- Reorg detection walks headers backwards from the chain tip
+CREATE INDEX IF NOT EXISTS idx_block_headers_chain_block
+ON block_headers(chain_id, block_number DESC);
+
+COMMENT ON TABLE block_headers IS 'Recent block headers per chain, used to detect chain reorganizations';
+COMMENT ON COLUMN block_headers.parent_hash IS 'Hash of the parent block, compared against the stored header of block_number - 1';
@@ -0,0 +1,9 @@
+-- Track whether an event's block has been finalized on its chain
+ALTER TABLE events ADD COLUMN IF NOT EXISTS finalized BOOLEAN NOT NULL DEFAULT false;
+
+-- Finality upgrades only touch events that are not finalized yet
+CREATE INDEX IF NOT EXISTS idx_events_chain_unfinalized
+ON events(chain_id, block_number)
+WHERE finalized = false;
+
+COMMENT ON COLUMN events.finalized IS 'True once the block containing the event is at or below the chain''s finalized block';
@@ -0,0 +1,20 @@
+-- Block ranges that failed to sync and are waiting to be retried
+CREATE TABLE IF NOT EXISTS failed_blocks (
+    chain_id BIGINT NOT NULL,
+    from_block BIGINT NOT NULL,
+    to_block BIGINT NOT NULL,
+    attempts INTEGER DEFAULT 1 NOT NULL,
+    last_error TEXT NOT NULL,
+    next_retry_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (chain_id, from_block),
+    CHECK (to_block >= from_block)
+);
+
+-- The indexer polls for ranges that are due for a retry
+CREATE INDEX IF NOT EXISTS idx_failed_blocks_next_retry
+ON failed_blocks(chain_id, next_retry_at);
+
+COMMENT ON TABLE failed_blocks IS 'Block ranges skipped during catch-up; they are not synced until a retry succeeds';
+COMMENT ON COLUMN failed_blocks.next_retry_at IS 'Earliest time of the next retry (exponential backoff on attempts)';
@@ -0,0 +1,23 @@
+-- Dead-letter table for registry logs that could not be decoded
+CREATE TABLE IF NOT EXISTS unknown_logs (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_timestamp TIMESTAMPTZ NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    log_index INTEGER NOT NULL,
+    contract_address VARCHAR(42) NOT NULL,
+    registry VARCHAR(20) NOT NULL,
+    topics TEXT[] NOT NULL,
+    data TEXT NOT NULL,
+    error TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    UNIQUE (chain_id, transaction_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_unknown_logs_chain_block
+ON unknown_logs(chain_id, block_number);
+
+COMMENT ON TABLE unknown_logs IS 'Raw registry logs that failed to decode; re-decoded into events once decoder support lands';
+COMMENT ON COLUMN unknown_logs.topics IS 'Hex-encoded log topics, topic0 is the event signature hash';
+COMMENT ON COLUMN unknown_logs.data IS 'Hex-encoded (0x-prefixed) log data';
@@ -0,0 +1,6 @@
+-- Keep the raw log alongside each decoded event so event_data can be rebuilt offline
+ALTER TABLE events ADD COLUMN IF NOT EXISTS raw_topics TEXT[];
+ALTER TABLE events ADD COLUMN IF NOT EXISTS raw_data TEXT;
+
+COMMENT ON COLUMN events.raw_topics IS 'Hex-encoded log topics (NULL for events indexed before raw logs were stored)';
+COMMENT ON COLUMN events.raw_data IS 'Hex-encoded (0x-prefixed) log data';
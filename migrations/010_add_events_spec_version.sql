@@ -0,0 +1,13 @@
+-- ERC-8004 spec version of the registry deployment that emitted each event
+ALTER TABLE events ADD COLUMN IF NOT EXISTS spec_version TEXT;
+
+-- Registry events indexed so far were all decoded with the v1 ABI
+UPDATE events SET spec_version = 'v1'
+WHERE spec_version IS NULL
+  AND event_type IN (
+    'Registered', 'MetadataSet', 'UriUpdated',
+    'NewFeedback', 'FeedbackRevoked', 'ResponseAppended',
+    'ValidationRequest', 'ValidationResponse'
+  );
+
+COMMENT ON COLUMN events.spec_version IS 'ABI version of the registry deployment (NULL for events of custom contracts)';
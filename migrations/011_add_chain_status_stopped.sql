@@ -0,0 +1,6 @@
+-- Chains are marked as stopped when the indexer shuts down gracefully
+ALTER TABLE chain_sync_state DROP CONSTRAINT IF EXISTS chain_sync_state_status_check;
+ALTER TABLE chain_sync_state ADD CONSTRAINT chain_sync_state_status_check
+    CHECK (status IN ('active', 'syncing', 'catching_up', 'stalled', 'failed', 'stopped'));
+
+COMMENT ON COLUMN chain_sync_state.status IS 'Current status: active (up-to-date), syncing (normal), catching_up (behind), stalled (not progressing), failed (error), stopped (shut down)';
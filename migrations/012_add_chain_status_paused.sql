@@ -0,0 +1,6 @@
+-- Chains can be paused at runtime through the admin API
+ALTER TABLE chain_sync_state DROP CONSTRAINT IF EXISTS chain_sync_state_status_check;
+ALTER TABLE chain_sync_state ADD CONSTRAINT chain_sync_state_status_check
+    CHECK (status IN ('active', 'syncing', 'catching_up', 'stalled', 'failed', 'stopped', 'paused'));
+
+COMMENT ON COLUMN chain_sync_state.status IS 'Current status: active (up-to-date), syncing (normal), catching_up (behind), stalled (not progressing), failed (error), stopped (shut down), paused (admin)';
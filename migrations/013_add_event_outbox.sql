@@ -0,0 +1,24 @@
+-- Transactional outbox: event changes written in the same transaction as the events,
+-- delivered to sinks (e.g. webhooks) by the outbox dispatcher
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id BIGINT NOT NULL,
+    event_id BIGINT,
+    removed BOOLEAN NOT NULL DEFAULT FALSE,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_created_at ON event_outbox(created_at);
+
+-- Delivery cursor of each sink: the last outbox entry it acknowledged
+CREATE TABLE IF NOT EXISTS outbox_cursors (
+    sink VARCHAR(255) PRIMARY KEY,
+    last_outbox_id BIGINT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+COMMENT ON TABLE event_outbox IS 'Inserted and rolled back events, in commit order, for at-least-once delivery to sinks';
+COMMENT ON COLUMN event_outbox.event_id IS 'Id of the event in the events table (rolled back events are deleted from it)';
+COMMENT ON COLUMN event_outbox.payload IS 'Event as served by the API, with "removed": true for rolled back events';
+COMMENT ON TABLE outbox_cursors IS 'Per-sink delivery cursor; updated_at shows when the sink was last served';
@@ -0,0 +1,15 @@
+-- Admin commands for a chain's indexer, queued by any API instance and applied by the instance
+-- holding the chain's indexing lock
+CREATE TABLE IF NOT EXISTS indexer_commands (
+    id BIGSERIAL PRIMARY KEY,
+    chain_id BIGINT NOT NULL,
+    command JSONB NOT NULL,
+    requested_by VARCHAR(255),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    applied_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_indexer_commands_pending ON indexer_commands(chain_id, id) WHERE applied_at IS NULL;
+
+COMMENT ON TABLE indexer_commands IS 'Admin commands (pause, resume, restart, rewind, backfill) in request order';
+COMMENT ON COLUMN indexer_commands.applied_at IS 'When the lock holder took the command (NULL while pending)';
@@ -0,0 +1,13 @@
+-- Order outbox entries by the transaction that wrote them instead of serialising writers with a
+-- global lock: sinks only read entries of transactions older than every running one, so an entry
+-- can no longer commit behind a sink's cursor
+ALTER TABLE event_outbox ADD COLUMN IF NOT EXISTS xid XID8 NOT NULL DEFAULT pg_current_xact_id();
+CREATE INDEX IF NOT EXISTS idx_event_outbox_xid_id ON event_outbox(xid, id);
+
+-- Cursors become (last_xid, last_outbox_id); existing entries all carry this migration's
+-- transaction id, so existing cursors keep their position
+ALTER TABLE outbox_cursors ADD COLUMN IF NOT EXISTS last_xid XID8 NOT NULL DEFAULT '0';
+UPDATE outbox_cursors SET last_xid = pg_current_xact_id();
+
+COMMENT ON COLUMN event_outbox.xid IS 'Transaction that wrote the entry; entries are delivered in (xid, id) order';
+COMMENT ON COLUMN outbox_cursors.last_xid IS 'Transaction id of the last acknowledged entry';
@@ -0,0 +1,7 @@
+-- Sinks are claimed with a lease on their cursor instead of a transaction-scoped advisory lock, so
+-- no transaction stays open while a batch is delivered
+ALTER TABLE outbox_cursors ADD COLUMN IF NOT EXISTS lease_token UUID;
+ALTER TABLE outbox_cursors ADD COLUMN IF NOT EXISTS lease_until TIMESTAMPTZ;
+
+COMMENT ON COLUMN outbox_cursors.lease_token IS 'Claim of the instance delivering a batch (NULL when none)';
+COMMENT ON COLUMN outbox_cursors.lease_until IS 'When the claim expires if the batch is neither acknowledged nor released';
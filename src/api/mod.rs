@@ -1,11 +1,14 @@
 use crate::auth::{self, Claims, JwtConfig, LoginRequest, LoginResponse};
-use crate::models::{Event, EventQuery};
+use crate::indexer::control::{BackfillRequest, ChainControls, IndexerCommand, RewindRequest};
+use crate::indexer::decoder::{ChainDecoders, RegistryKind};
+use crate::models::{AgentOwnerQuery, Event, EventQuery, UnknownLogQuery};
+use crate::rpc::ChainProviders;
 use crate::stats::StatsTracker;
 use crate::storage::Storage;
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
-        Query, State, WebSocketUpgrade,
+        ws::{close_code, CloseFrame, Message, WebSocket},
+        Path, Query, State, WebSocketUpgrade,
     },
     http::{HeaderValue, Method, StatusCode},
     middleware,
@@ -15,8 +18,10 @@ use axum::{
 };
 use metrics_exporter_prometheus::PrometheusHandle;
 use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info, warn};
 
@@ -25,8 +30,17 @@ use tracing::{error, info, warn};
 pub struct AppState {
     pub storage: Storage,
     pub event_tx: broadcast::Sender<Event>,
+    pub decoders: ChainDecoders,
     pub metrics_handle: PrometheusHandle,
     pub stats_tracker: StatsTracker,
+    /// Control handles of the indexer supervisors (leadership of this instance)
+    pub controls: ChainControls,
+    /// RPC provider managers of the indexer supervisors
+    pub providers: ChainProviders,
+    /// Set while an unknown logs re-decoding job runs
+    pub redecode_running: Arc<AtomicBool>,
+    /// Cancelled on server shutdown; WebSocket clients are sent a close frame
+    pub shutdown: CancellationToken,
 }
 
 /// Configure CORS based on environment variables
@@ -64,19 +78,30 @@ fn configure_cors() -> CorsLayer {
 }
 
 /// Start the API server
+/// Once `shutdown` is cancelled the server stops accepting connections and drains in-flight requests
+#[allow(clippy::too_many_arguments)]
 pub async fn start_server(
     host: String,
     port: u16,
     storage: Storage,
     event_tx: broadcast::Sender<Event>,
+    decoders: ChainDecoders,
     metrics_handle: PrometheusHandle,
     stats_tracker: StatsTracker,
+    controls: ChainControls,
+    providers: ChainProviders,
+    shutdown: CancellationToken,
 ) -> anyhow::Result<()> {
     let state = Arc::new(AppState {
         storage,
         event_tx,
+        decoders,
         metrics_handle,
         stats_tracker,
+        controls,
+        providers,
+        redecode_running: Arc::new(AtomicBool::new(false)),
+        shutdown: shutdown.clone(),
     });
 
     // Initialize JWT config
@@ -96,7 +121,15 @@ pub async fn start_server(
         .route("/events", get(get_recent_activity))
         .route("/ws", get(websocket_handler))
         .route("/stats", get(get_stats))
+        .route("/agents/:chain_id/:agent_id/owner", get(get_agent_owner))
         .route("/chains/status", get(get_chains_status))
+        .route("/admin/unknown-logs", get(get_unknown_logs))
+        .route("/admin/unknown-logs/redecode", post(redecode_unknown_logs))
+        .route("/admin/chains/:chain_id/pause", post(pause_chain))
+        .route("/admin/chains/:chain_id/resume", post(resume_chain))
+        .route("/admin/chains/:chain_id/restart", post(restart_chain))
+        .route("/admin/chains/:chain_id/rewind", post(rewind_chain))
+        .route("/admin/chains/:chain_id/backfill", post(backfill_chain))
         .layer(middleware::from_fn(jwt_middleware));
 
     // Configure CORS
@@ -113,7 +146,11 @@ pub async fn start_server(
     info!("Starting API server on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await?;
+
+    info!("API server stopped");
 
     Ok(())
 }
@@ -163,7 +200,7 @@ async fn health_check_detailed(State(state): State<Arc<AppState>>) -> impl IntoR
     let mut checks = serde_json::Map::new();
 
     // Check database connectivity
-    match state.storage.get_enabled_chains().await {
+    match state.storage.get_chains().await {
         Ok(chains) => {
             // Check each chain status
             let mut chains_status = serde_json::Map::new();
@@ -173,10 +210,12 @@ async fn health_check_detailed(State(state): State<Arc<AppState>>) -> impl IntoR
             for chain in &chains {
                 let chain_status = chain.status.as_deref().unwrap_or("unknown");
 
-                if chain_status == "failed" {
+                // Disabled chains are listed with their last status but do not affect the
+                // overall health
+                if chain.enabled && chain_status == "failed" {
                     failed_count += 1;
                     overall_status = "degraded";
-                } else if chain_status == "stalled" {
+                } else if chain.enabled && chain_status == "stalled" {
                     stalled_count += 1;
                     if overall_status == "healthy" {
                         overall_status = "degraded";
@@ -187,6 +226,7 @@ async fn health_check_detailed(State(state): State<Arc<AppState>>) -> impl IntoR
                     chain.name.clone(),
                     json!({
                         "chain_id": chain.chain_id,
+                        "enabled": chain.enabled,
                         "status": chain_status,
                         "last_synced_block": chain.last_synced_block,
                         "last_sync_time": chain.last_sync_time,
@@ -336,7 +376,7 @@ async fn get_stats(
     warn!("User '{}' requested deprecated /stats endpoint", claims.sub);
 
     // Get multi-chain stats
-    let chains = state.storage.get_enabled_chains().await?;
+    let chains = state.storage.get_chains().await?;
     let (cache_size, cache_max) = state.storage.cache_stats();
 
     Ok(Json(json!({
@@ -360,6 +400,49 @@ async fn get_stats(
     })))
 }
 
+/// GET /agents/:chain_id/:agent_id/owner - Current owner of an agent identity NFT
+async fn get_agent_owner(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path((chain_id, agent_id)): Path<(u64, String)>,
+    Query(query): Query<AgentOwnerQuery>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    info!(
+        "User '{}' requested owner of agent {} on chain {}",
+        claims.sub, agent_id, chain_id
+    );
+
+    // Agent IDs restart with every identity registry deployment
+    let registry = query.registry.or_else(|| {
+        state
+            .decoders
+            .get(chain_id)
+            .latest_deployment(RegistryKind::Identity.as_str())
+            .map(|address| format!("{:?}", address))
+    });
+
+    match state
+        .storage
+        .get_agent_owner(chain_id, &agent_id, registry.as_deref())
+        .await?
+    {
+        Some(owner) => Ok((
+            StatusCode::OK,
+            Json(json!({
+                "success": true,
+                "owner": owner
+            })),
+        )),
+        None => Ok((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "success": false,
+                "error": format!("Agent {} not found on chain {}", agent_id, chain_id)
+            })),
+        )),
+    }
+}
+
 /// WebSocket handler
 async fn websocket_handler(
     claims: Claims,
@@ -389,10 +472,28 @@ async fn handle_websocket(socket: WebSocket, state: Arc<AppState>) {
     }
 
     // Spawn task to forward events to WebSocket
+    let shutdown = state.shutdown.clone();
     let mut send_task = tokio::spawn(async move {
-        while let Ok(event) = event_rx.recv().await {
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.cancelled() => {
+                    let close = CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server shutting down".into(),
+                    };
+                    let _ = sender.send(Message::Close(Some(close))).await;
+                    break;
+                }
+                event = event_rx.recv() => match event {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+            };
+
+            // Events rolled back by a reorg are sent with a distinct message type
+            let msg_type = if event.removed { "removed" } else { "event" };
             let msg = json!({
-                "type": "event",
+                "type": msg_type,
                 "data": event
             });
 
@@ -467,27 +568,28 @@ where
 use futures::stream::StreamExt;
 use futures::SinkExt;
 
-/// GET /chains - List all enabled chains with status
+/// GET /chains - List all chains with status (disabled ones included)
 async fn get_chains(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
-    // Get all enabled chains from database
-    let chains = state.storage.get_enabled_chains().await?;
+    let chains = state.storage.get_chains().await?;
 
-    // Calculate overall status
+    // Calculate overall status from the enabled chains
     let total_chains = chains.len();
+    let enabled_chains = chains.iter().filter(|c| c.enabled).count();
     let healthy_chains = chains
         .iter()
+        .filter(|c| c.enabled)
         .filter(|c| c.status.as_deref() == Some("active") || c.status.as_deref() == Some("syncing"))
         .count();
     let failed_chains = chains
         .iter()
-        .filter(|c| c.status.as_deref() == Some("failed"))
+        .filter(|c| c.enabled && c.status.as_deref() == Some("failed"))
         .count();
 
     let overall_status = if failed_chains > 0 {
         "degraded"
-    } else if healthy_chains == total_chains {
+    } else if healthy_chains == enabled_chains {
         "healthy"
     } else {
         "syncing"
@@ -496,6 +598,7 @@ async fn get_chains(
     Ok(Json(json!({
         "status": overall_status,
         "total_chains": total_chains,
+        "disabled_chains": total_chains - enabled_chains,
         "healthy_chains": healthy_chains,
         "failed_chains": failed_chains,
         "chains": chains
@@ -509,8 +612,15 @@ async fn get_chains_status(
 ) -> Result<Json<serde_json::Value>, ApiError> {
     info!("User '{}' requested chains status", claims.sub);
 
-    // Get all enabled chains from database
-    let chains = state.storage.get_enabled_chains().await?;
+    // Get all chains from database (disabled ones keep their last status)
+    let chains = state.storage.get_chains().await?;
+
+    // Instances currently indexing each chain
+    let lock_holders = state
+        .storage
+        .get_chain_lock_holders()
+        .await
+        .unwrap_or_default();
 
     let mut chain_statuses = vec![];
 
@@ -518,8 +628,18 @@ async fn get_chains_status(
         // Get current block from stats tracker
         let current_block = state.stats_tracker.get_current_block(chain.chain_id);
 
-        // Get indexer block from database
+        // Failed block ranges waiting for a retry
+        let failed_ranges = state
+            .storage
+            .get_failed_block_ranges(chain.chain_id)
+            .await
+            .unwrap_or_default();
+
+        // Get indexer block from database; blocks are only indexed up to the first failed range
         let indexer_block = chain.last_synced_block.unwrap_or(0);
+        let indexer_block = failed_ranges
+            .first()
+            .map_or(indexer_block, |range| indexer_block.min(range.from_block));
 
         // Calculate blocks behind
         let blocks_behind = if let Some(current) = current_block {
@@ -538,14 +658,28 @@ async fn get_chains_status(
             .await
             .unwrap_or_default();
 
+        let lock_holder = lock_holders.get(&chain.chain_id);
+        let this_instance = state
+            .controls
+            .get(chain.chain_id)
+            .is_some_and(|control| control.is_leader());
+
+        // Probe results of the RPC providers (only known to instances running the chain)
+        let providers = match state.providers.get(chain.chain_id) {
+            Some(provider_manager) => provider_manager.health_snapshot().await,
+            None => Vec::new(),
+        };
+
         chain_statuses.push(json!({
             "chain_id": chain.chain_id,
             "name": chain.name,
+            "enabled": chain.enabled,
             "status": chain.status.unwrap_or_else(|| "unknown".to_string()),
             "blocks": {
                 "current": current_block,
                 "indexed": indexer_block,
-                "behind": blocks_behind
+                "behind": blocks_behind,
+                "failed_ranges": failed_ranges
             },
             "polling": {
                 "rate_per_minute": format!("{:.2}", polling_rate)
@@ -554,6 +688,13 @@ async fn get_chains_status(
                 "total": chain.total_events_indexed.unwrap_or(0),
                 "by_type": event_counts
             },
+            "indexer_lock": {
+                "held": lock_holder.is_some(),
+                "instance_id": lock_holder.and_then(|holder| holder.instance_id.clone()),
+                "since": lock_holder.and_then(|holder| holder.since),
+                "this_instance": this_instance
+            },
+            "providers": providers,
             "last_sync_time": chain.last_sync_time
         }));
     }
@@ -565,6 +706,223 @@ async fn get_chains_status(
     })))
 }
 
+/// GET /admin/unknown-logs - List registry logs that could not be decoded
+async fn get_unknown_logs(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UnknownLogQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    info!("User '{}' requested unknown logs", claims.sub);
+
+    let total = state.storage.count_unknown_logs(query.chain_id).await?;
+    let logs = state.storage.get_unknown_logs(&query).await?;
+
+    Ok(Json(json!({
+        "success": true,
+        "count": logs.len(),
+        "total": total,
+        "unknown_logs": logs
+    })))
+}
+
+/// POST /admin/unknown-logs/redecode - Re-decode stored unknown logs with the current decoders
+/// Runs in the background (one job at a time); logs that now decode are moved into the events
+/// table, the rest stay in the table
+async fn redecode_unknown_logs(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UnknownLogQuery>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!("User '{}' requested unknown logs re-decoding", claims.sub);
+
+    if state.redecode_running.swap(true, Ordering::AcqRel) {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({
+                "success": false,
+                "error": "Unknown logs are already being re-decoded"
+            })),
+        );
+    }
+
+    let job_state = state.clone();
+    tokio::spawn(async move {
+        match redecode_job(&job_state, query.chain_id).await {
+            Ok((scanned, decoded, remaining)) => info!(
+                "Re-decoded {} of {} unknown logs ({} remaining)",
+                decoded, scanned, remaining
+            ),
+            Err(e) => error!("Failed to re-decode unknown logs: {}", e),
+        }
+        job_state.redecode_running.store(false, Ordering::Release);
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "chain_id": query.chain_id
+        })),
+    )
+}
+
+/// Re-decode unknown logs in batches until none are left or the server shuts down
+/// Returns the number of scanned, decoded and remaining logs
+async fn redecode_job(
+    state: &AppState,
+    chain_id: Option<u64>,
+) -> anyhow::Result<(usize, usize, i64)> {
+    const BATCH_SIZE: i64 = 500;
+
+    let mut scanned = 0;
+    let mut decoded = 0;
+    let mut after_id = 0;
+
+    while !state.shutdown.is_cancelled() {
+        let logs = state
+            .storage
+            .get_unknown_logs_after(chain_id, after_id, BATCH_SIZE)
+            .await?;
+        let Some(last) = logs.last() else {
+            break;
+        };
+        after_id = last.id.unwrap_or(after_id);
+        scanned += logs.len();
+
+        let resolved: Vec<(i64, Event)> = logs
+            .iter()
+            .filter_map(|log| {
+                let event = state
+                    .decoders
+                    .get(log.chain_id)
+                    .redecode_unknown_log(log)
+                    .ok()?;
+                Some((log.id?, event))
+            })
+            .collect();
+        decoded += resolved.len();
+
+        // Recovered events reach WebSocket clients through the outbox
+        state.storage.resolve_unknown_logs(resolved).await?;
+    }
+
+    let remaining = state.storage.count_unknown_logs(chain_id).await?;
+    Ok((scanned, decoded, remaining))
+}
+
+/// POST /admin/chains/:chain_id/pause - Stop a chain's indexer until it is resumed
+async fn pause_chain(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    send_indexer_command(&state, &claims, chain_id, IndexerCommand::Pause).await
+}
+
+/// POST /admin/chains/:chain_id/resume - Start a paused chain's indexer again
+async fn resume_chain(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    send_indexer_command(&state, &claims, chain_id, IndexerCommand::Resume).await
+}
+
+/// POST /admin/chains/:chain_id/restart - Restart a chain's indexer
+async fn restart_chain(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    send_indexer_command(&state, &claims, chain_id, IndexerCommand::Restart).await
+}
+
+/// POST /admin/chains/:chain_id/rewind - Move the sync cursor to `block` and restart from there
+async fn rewind_chain(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+    Json(request): Json<RewindRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let command = IndexerCommand::Rewind {
+        block: request.block,
+    };
+    send_indexer_command(&state, &claims, chain_id, command).await
+}
+
+/// POST /admin/chains/:chain_id/backfill - Re-index a block range alongside the live indexer
+async fn backfill_chain(
+    claims: Claims,
+    State(state): State<Arc<AppState>>,
+    Path(chain_id): Path<u64>,
+    Json(request): Json<BackfillRequest>,
+) -> (StatusCode, Json<serde_json::Value>) {
+    let command = IndexerCommand::Backfill {
+        from_block: request.from_block,
+        to_block: request.to_block,
+    };
+    send_indexer_command(&state, &claims, chain_id, command).await
+}
+
+/// Queue an admin command for a chain's indexer (applied asynchronously)
+/// Commands go through the database, so any instance accepts them and the instance holding the
+/// chain's indexing lock applies them
+async fn send_indexer_command(
+    state: &AppState,
+    claims: &Claims,
+    chain_id: u64,
+    command: IndexerCommand,
+) -> (StatusCode, Json<serde_json::Value>) {
+    info!(
+        "User '{}' requested {} on chain {}",
+        claims.sub, command, chain_id
+    );
+
+    let failure = |status: StatusCode, error: String| {
+        (status, Json(json!({ "success": false, "error": error })))
+    };
+    let not_indexed = || {
+        failure(
+            StatusCode::NOT_FOUND,
+            format!("Chain {} is not indexed", chain_id),
+        )
+    };
+
+    if let Err(e) = command.validate() {
+        return failure(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    if let IndexerCommand::Rewind { .. } = command {
+        let sync_state = match state.storage.get_chain_sync_state(chain_id).await {
+            Ok(Some(sync_state)) => sync_state,
+            Ok(None) => return not_indexed(),
+            Err(e) => return failure(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        };
+        if let Err(e) = command.validate_cursor(sync_state.last_synced_block) {
+            return failure(StatusCode::BAD_REQUEST, e.to_string());
+        }
+    }
+
+    let command_id = match state
+        .storage
+        .queue_indexer_command(chain_id, &command, &claims.sub)
+        .await
+    {
+        Ok(Some(command_id)) => command_id,
+        Ok(None) => return not_indexed(),
+        Err(e) => return failure(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "chain_id": chain_id,
+            "command_id": command_id,
+            "command": command.to_string()
+        })),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -587,6 +945,10 @@ mod tests {
                 owner: "0x5678".to_string(),
             }),
             created_at: Some(Utc::now()),
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
         }
     }
 
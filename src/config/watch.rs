@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+
+/// Detects changes to a configuration file
+/// The file's modification time is polled; on Unix, SIGHUP forces a reload as well
+pub struct ConfigWatcher {
+    path: PathBuf,
+    interval: Duration,
+    modified: Option<SystemTime>,
+    #[cfg(unix)]
+    hangup: Option<tokio::signal::unix::Signal>,
+}
+
+impl ConfigWatcher {
+    /// Watch `path`, checking its modification time every `interval`
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        let path = path.into();
+        let modified = modified_time(&path);
+
+        #[cfg(unix)]
+        let hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(signal) => Some(signal),
+            Err(e) => {
+                warn!(
+                    "Failed to install SIGHUP handler, relying on file polling: {}",
+                    e
+                );
+                None
+            }
+        };
+
+        Self {
+            path,
+            interval,
+            modified,
+            #[cfg(unix)]
+            hangup,
+        }
+    }
+
+    /// Wait until the file was modified (or SIGHUP was received)
+    pub async fn changed(&mut self) {
+        loop {
+            tokio::select! {
+                _ = sleep(self.interval) => {
+                    let modified = modified_time(&self.path);
+                    if modified != self.modified {
+                        self.modified = modified;
+                        info!("{} changed on disk", self.path.display());
+                        return;
+                    }
+                }
+                _ = self.hangup() => {
+                    self.modified = modified_time(&self.path);
+                    info!("SIGHUP received, reloading {}", self.path.display());
+                    return;
+                }
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    async fn hangup(&mut self) {
+        match self.hangup.as_mut() {
+            Some(signal) => {
+                signal.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn hangup(&mut self) {
+        std::future::pending().await
+    }
+}
+
+/// Modification time of a file (None if it cannot be read, e.g. while it is being replaced)
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[tokio::test]
+    async fn test_config_watcher_detects_changes() {
+        let path = env::temp_dir().join("api_8004_dev_test_watch_chains.yaml");
+        fs::write(&path, "chains: []").unwrap();
+
+        let mut watcher = ConfigWatcher::new(&path, Duration::from_millis(10));
+
+        // Unchanged file: no reload
+        let unchanged = tokio::time::timeout(Duration::from_millis(100), watcher.changed()).await;
+        assert!(unchanged.is_err());
+
+        // Ensure a different modification time even on coarse-grained file systems
+        let later = SystemTime::now() + Duration::from_secs(5);
+        let file = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_modified(later).unwrap();
+
+        let changed = tokio::time::timeout(Duration::from_secs(1), watcher.changed()).await;
+        assert!(changed.is_ok());
+
+        fs::remove_file(&path).ok();
+    }
+}
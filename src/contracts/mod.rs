@@ -7,6 +7,11 @@ sol! {
         event Registered(uint256 indexed agentId, string tokenURI, address indexed owner);
         event MetadataSet(uint256 indexed agentId, string indexed indexedKey, string key, bytes value);
         event UriUpdated(uint256 indexed agentId, string newUri, address indexed updatedBy);
+
+        // ERC-721 events (agent identities are NFTs; unchanged across spec versions)
+        event Transfer(address indexed from, address indexed to, uint256 indexed tokenId);
+        event Approval(address indexed owner, address indexed approved, uint256 indexed tokenId);
+        event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
     }
 }
 
@@ -57,4 +62,77 @@ sol! {
     }
 }
 
+// ERC-8004 v2 spec revision: string tags, signed feedback values with decimals,
+// feedback indexes and string validation tags
+
+// IdentityRegistry v2 events
+sol! {
+    #[sol(rpc)]
+    contract IdentityRegistryV2 {
+        event Registered(uint256 indexed agentId, string agentURI, address indexed owner);
+        event MetadataSet(
+            uint256 indexed agentId,
+            string indexed indexedMetadataKey,
+            string metadataKey,
+            bytes metadataValue
+        );
+        event URIUpdated(uint256 indexed agentId, string newURI, address indexed updatedBy);
+    }
+}
+
+// ReputationRegistry v2 events
+sol! {
+    #[sol(rpc)]
+    contract ReputationRegistryV2 {
+        event NewFeedback(
+            uint256 indexed agentId,
+            address indexed clientAddress,
+            uint64 feedbackIndex,
+            int128 value,
+            uint8 valueDecimals,
+            string indexed indexedTag1,
+            string tag1,
+            string tag2,
+            string endpoint,
+            string feedbackURI,
+            bytes32 feedbackHash
+        );
+        event FeedbackRevoked(
+            uint256 indexed agentId,
+            address indexed clientAddress,
+            uint64 indexed feedbackIndex
+        );
+        event ResponseAppended(
+            uint256 indexed agentId,
+            address indexed clientAddress,
+            uint64 feedbackIndex,
+            address indexed responder,
+            string responseURI,
+            bytes32 responseHash
+        );
+    }
+}
+
+// ValidationRegistry v2 events
+sol! {
+    #[sol(rpc)]
+    contract ValidationRegistryV2 {
+        event ValidationRequest(
+            address indexed validatorAddress,
+            uint256 indexed agentId,
+            string requestURI,
+            bytes32 indexed requestHash
+        );
+        event ValidationResponse(
+            address indexed validatorAddress,
+            uint256 indexed agentId,
+            bytes32 indexed requestHash,
+            uint8 response,
+            string responseURI,
+            bytes32 responseHash,
+            string tag
+        );
+    }
+}
+
 // Contract modules are available via the module name directly
@@ -0,0 +1,402 @@
+use crate::config::{ChainConfig, GlobalConfig, IndexerConfig};
+use crate::indexer::control::ChainControls;
+use crate::indexer::decoder::ChainDecoders;
+use crate::indexer::supervisor::{IndexerSupervisor, RestartPolicy};
+use crate::rpc::{verify_providers, ChainProviders, ProviderManager};
+use crate::stats::StatsTracker;
+use crate::storage::Storage;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// How a chain's configuration changed between two loads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChainChange {
+    /// New or re-enabled chain: start a supervisor
+    Added,
+    /// Removed or disabled chain: stop its supervisor
+    Removed,
+    /// Only the HTTP RPC providers or their selection strategy changed: swap them in the running
+    /// provider manager
+    Providers,
+    /// Anything else changed: restart the supervisor with the new configuration
+    Restart,
+}
+
+/// Compare two chain sets; unchanged chains are left out
+/// Changes to the global settings restart every chain
+pub fn diff_chains(
+    current: &[ChainConfig],
+    current_global: &GlobalConfig,
+    new: &[ChainConfig],
+    new_global: &GlobalConfig,
+) -> Vec<(u64, ChainChange)> {
+    let mut changes = Vec::new();
+
+    for chain in new {
+        let change = match current.iter().find(|c| c.chain_id == chain.chain_id) {
+            None => ChainChange::Added,
+            Some(_) if current_global != new_global => ChainChange::Restart,
+            Some(old) if old == chain => continue,
+            Some(old)
+                if without_providers(old) == without_providers(chain)
+                    && ws_urls(old) == ws_urls(chain) =>
+            {
+                ChainChange::Providers
+            }
+            Some(_) => ChainChange::Restart,
+        };
+        changes.push((chain.chain_id, change));
+    }
+
+    for chain in current {
+        if !new.iter().any(|c| c.chain_id == chain.chain_id) {
+            changes.push((chain.chain_id, ChainChange::Removed));
+        }
+    }
+
+    changes
+}
+
+/// Chain configuration with the RPC providers and their selection strategy left out
+fn without_providers(chain: &ChainConfig) -> ChainConfig {
+    ChainConfig {
+        rpc_providers: Vec::new(),
+        rpc_url: None,
+        provider_strategy: Default::default(),
+        ..chain.clone()
+    }
+}
+
+/// WebSocket endpoints in the order the head subscription uses them
+/// They are only read when an indexer starts, so changing them needs a restart
+fn ws_urls(chain: &ChainConfig) -> Vec<String> {
+    let mut providers = chain.get_providers();
+    providers.sort_by_key(|p| p.priority);
+    providers.into_iter().filter_map(|p| p.ws_url).collect()
+}
+
+/// Supervisor task of one chain
+struct RunningChain {
+    config: ChainConfig,
+    provider_manager: Arc<ProviderManager>,
+    /// Cancelled to stop this chain only
+    stop: CancellationToken,
+    handle: JoinHandle<()>,
+}
+
+/// Indexer supervisors of the configured chains
+/// `apply` starts, stops and updates them as chains.yaml changes
+pub struct ChainSupervisors {
+    storage: Storage,
+    stats_tracker: StatsTracker,
+    decoders: ChainDecoders,
+    controls: ChainControls,
+    providers: ChainProviders,
+    shutdown: CancellationToken,
+    instance_id: String,
+    global: GlobalConfig,
+    chains: HashMap<u64, RunningChain>,
+}
+
+impl ChainSupervisors {
+    pub fn new(
+        storage: Storage,
+        stats_tracker: StatsTracker,
+        shutdown: CancellationToken,
+        instance_id: String,
+    ) -> Self {
+        Self {
+            storage,
+            stats_tracker,
+            decoders: ChainDecoders::default(),
+            controls: ChainControls::default(),
+            providers: ChainProviders::default(),
+            shutdown,
+            instance_id,
+            global: GlobalConfig::default(),
+            chains: HashMap::new(),
+        }
+    }
+
+    /// Decoder registries of the running chains (kept up to date on reload)
+    pub fn decoders(&self) -> ChainDecoders {
+        self.decoders.clone()
+    }
+
+    /// Control handles of the running chains (kept up to date on reload)
+    pub fn controls(&self) -> ChainControls {
+        self.controls.clone()
+    }
+
+    /// Provider managers of the running chains (kept up to date on reload)
+    pub fn providers(&self) -> ChainProviders {
+        self.providers.clone()
+    }
+
+    /// Bring the running supervisors in line with the configuration
+    /// Chains whose supervisor gave up (e.g. max retries reached) are restarted as well
+    pub async fn apply(&mut self, chains: &[ChainConfig], global: &GlobalConfig) {
+        let current: Vec<ChainConfig> = self.chains.values().map(|c| c.config.clone()).collect();
+        let mut changes = diff_chains(&current, &self.global, chains, global);
+        self.global = global.clone();
+
+        for (chain_id, running) in &self.chains {
+            if running.handle.is_finished() && !changes.iter().any(|(id, _)| id == chain_id) {
+                changes.push((*chain_id, ChainChange::Restart));
+            }
+        }
+
+        for (chain_id, change) in changes {
+            let chain = chains.iter().find(|c| c.chain_id == chain_id);
+
+            match (change, chain) {
+                (ChainChange::Removed, _) => self.stop_chain(chain_id).await,
+                (ChainChange::Added, Some(chain)) => self.start_chain(chain),
+                (ChainChange::Restart, Some(chain)) => {
+                    self.stop_chain(chain_id).await;
+                    self.start_chain(chain);
+                }
+                (ChainChange::Providers, Some(chain)) => self.replace_providers(chain).await,
+                (_, None) => {}
+            }
+        }
+    }
+
+    /// Start the supervisor of a chain
+    /// Invalid chain configurations are logged and skipped (retried on the next reload)
+    fn start_chain(&mut self, chain: &ChainConfig) {
+        // Convert ChainConfig to IndexerConfig
+        let indexer_config = match IndexerConfig::from_chain_config(chain, &self.global) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                error!(
+                    "❌ Failed to create indexer config for {}: {}",
+                    chain.name, e
+                );
+                return;
+            }
+        };
+        let decoders = indexer_config.decoders.clone();
+
+        // Create supervisor with exponential backoff restart policy
+        let supervisor = match IndexerSupervisor::new(
+            indexer_config,
+            self.storage.clone(),
+            RestartPolicy::Exponential {
+                max_retries: self.global.max_indexer_retries,
+                base_delay_ms: self.global.retry_base_delay_ms,
+                max_delay_ms: self.global.retry_max_delay_ms,
+            },
+            self.stats_tracker.clone(),
+            self.instance_id.clone(),
+        ) {
+            Ok(supervisor) => supervisor,
+            Err(e) => {
+                error!("❌ Failed to create supervisor for {}: {}", chain.name, e);
+                return;
+            }
+        };
+
+        self.decoders.insert(chain.chain_id, decoders);
+        self.controls.insert(chain.chain_id, supervisor.control());
+
+        let provider_manager = supervisor.provider_manager();
+        self.providers
+            .insert(chain.chain_id, provider_manager.clone());
+        let stop = self.shutdown.child_token();
+        let supervisor_stop = stop.clone();
+        let chain_name = chain.name.clone();
+
+        // Spawn supervisor in its own task
+        let handle = tokio::spawn(async move {
+            info!("🚀 Starting supervisor for {}", chain_name);
+            match supervisor.start(supervisor_stop).await {
+                Ok(()) => {
+                    info!("✅ Supervisor {} exited cleanly", chain_name);
+                }
+                Err(e) => {
+                    error!("❌ Supervisor {} failed: {}", chain_name, e);
+                }
+            }
+        });
+
+        self.chains.insert(
+            chain.chain_id,
+            RunningChain {
+                config: chain.clone(),
+                provider_manager,
+                stop,
+                handle,
+            },
+        );
+    }
+
+    /// Stop the supervisor of a chain and wait until it marked the chain as stopped
+    async fn stop_chain(&mut self, chain_id: u64) {
+        let Some(running) = self.chains.remove(&chain_id) else {
+            return;
+        };
+
+        info!("🛑 Stopping supervisor for {}", running.config.name);
+        self.controls.remove(chain_id);
+        self.providers.remove(chain_id);
+        self.decoders.remove(chain_id);
+
+        running.stop.cancel();
+        if let Err(e) = running.handle.await {
+            error!("❌ Supervisor {} panicked: {}", running.config.name, e);
+        }
+    }
+
+    /// Swap the RPC providers and strategy of a running chain without restarting it
+    async fn replace_providers(&mut self, chain: &ChainConfig) {
+        let Some(running) = self.chains.get_mut(&chain.chain_id) else {
+            return;
+        };
+
+        running
+            .provider_manager
+            .set_strategy(chain.provider_strategy)
+            .await;
+
+        match running
+            .provider_manager
+            .replace_providers(chain.get_providers())
+            .await
+        {
+            Ok(()) => {
+                info!("🔁 Updated RPC providers for {}", chain.name);
+                running.config = chain.clone();
+
+                // New providers stay quarantined until their chain ID is confirmed, also when
+                // background probing is disabled
+                let provider_manager = running.provider_manager.clone();
+                let name = chain.name.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = verify_providers(&provider_manager, &name, None).await {
+                        error!("[{}] {}", name, e);
+                    }
+                });
+            }
+            Err(e) => warn!(
+                "⚠️  Keeping current RPC providers for {}: {}",
+                chain.name, e
+            ),
+        }
+    }
+
+    /// Number of chains with a supervisor
+    pub fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+
+    /// Wait for every supervisor to exit (after the shutdown token was cancelled)
+    pub async fn join(self) {
+        for (_, running) in self.chains {
+            if let Err(e) = running.handle.await {
+                error!("❌ Supervisor {} panicked: {}", running.config.name, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ContractAddresses, ProviderStrategy, RpcProvider};
+
+    fn chain(chain_id: u64, rpc_url: &str) -> ChainConfig {
+        ChainConfig {
+            name: format!("Chain {}", chain_id),
+            chain_id,
+            enabled: true,
+            rpc_providers: vec![RpcProvider {
+                url: rpc_url.to_string(),
+                weight: 30,
+                priority: 1,
+                max_requests_per_minute: 100,
+                cooldown_on_error_ms: 60000,
+                ws_url: None,
+            }],
+            rpc_url: None,
+            contracts: ContractAddresses {
+                identity_registry: "0x8004a6090Cd10A7288092483047B097295Fb8847".to_string(),
+                reputation_registry: "0x8004B8FD1A363aa02fDC07635C0c5F94f6Af5B7E".to_string(),
+                validation_registry: "0x8004CB39f29c09145F24Ad9dDe2A108C1A2cdfC5".to_string(),
+                deployments: vec![],
+            },
+            starting_block: "latest".to_string(),
+            poll_interval_ms: 12000,
+            batch_size: 1,
+            adaptive_polling: true,
+            max_block_span: 1000,
+            confirmations: 0,
+            block_tag: Default::default(),
+            provider_strategy: Default::default(),
+            log_verification: None,
+            event_abis: vec![],
+        }
+    }
+
+    #[test]
+    fn test_diff_chains() {
+        let global = GlobalConfig::default();
+        let current = vec![
+            chain(1, "http://a"),
+            chain(2, "http://b"),
+            chain(3, "http://c"),
+        ];
+
+        let mut rotated = chain(1, "http://a2");
+        rotated.rpc_providers[0].weight = 10;
+        let mut slower = chain(2, "http://b");
+        slower.poll_interval_ms = 30000;
+        let new = vec![rotated, slower, chain(4, "http://d")];
+
+        assert_eq!(
+            diff_chains(&current, &global, &new, &global),
+            vec![
+                (1, ChainChange::Providers),
+                (2, ChainChange::Restart),
+                (4, ChainChange::Added),
+                (3, ChainChange::Removed),
+            ]
+        );
+
+        // Unchanged chains are left out
+        assert!(diff_chains(&current, &global, &current, &global).is_empty());
+
+        // The provider selection strategy is swapped in place too
+        let mut sticky = chain(1, "http://a");
+        sticky.provider_strategy = ProviderStrategy::Sticky;
+        assert_eq!(
+            diff_chains(&current[..1], &global, &[sticky], &global),
+            vec![(1, ChainChange::Providers)]
+        );
+
+        // A new WebSocket endpoint is only picked up by a restart
+        let mut ws = chain(1, "http://a");
+        ws.rpc_providers[0].ws_url = Some("wss://a".to_string());
+        assert_eq!(
+            diff_chains(&current[..1], &global, &[ws], &global),
+            vec![(1, ChainChange::Restart)]
+        );
+
+        // Global settings apply to every chain
+        let new_global = GlobalConfig {
+            max_indexer_retries: 10,
+            ..GlobalConfig::default()
+        };
+        assert_eq!(
+            diff_chains(&current[..1], &global, &current[..1], &new_global),
+            vec![(1, ChainChange::Restart)]
+        );
+    }
+}
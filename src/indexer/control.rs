@@ -0,0 +1,213 @@
+use anyhow::{anyhow, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Maximum number of commands queued for a supervisor
+const CONTROL_QUEUE_SIZE: usize = 16;
+
+/// Command sent from the API to a chain's supervisor
+/// Queued in the database so any instance can accept it; the lock holder applies it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexerCommand {
+    /// Stop the indexer until it is resumed
+    Pause,
+    /// Start a paused indexer again
+    Resume,
+    /// Stop the indexer and start it again right away
+    Restart,
+    /// Move the sync cursor to `block` and restart the indexer from there
+    Rewind { block: u64 },
+    /// Re-index `from_block..=to_block` in the background without moving the cursor
+    Backfill { from_block: u64, to_block: u64 },
+}
+
+impl IndexerCommand {
+    /// Whether a running indexer has to stop before the command is applied
+    pub fn stops_indexer(&self) -> bool {
+        matches!(
+            self,
+            IndexerCommand::Pause | IndexerCommand::Restart | IndexerCommand::Rewind { .. }
+        )
+    }
+
+    /// Reject commands that can never be applied
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            IndexerCommand::Backfill {
+                from_block,
+                to_block,
+            } if from_block > to_block => Err(anyhow!(
+                "Backfill range ends at block {} before it starts at block {}",
+                to_block,
+                from_block
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject rewinds ahead of the chain's sync cursor (they would skip blocks)
+    pub fn validate_cursor(&self, last_synced_block: u64) -> Result<()> {
+        match self {
+            IndexerCommand::Rewind { block } if *block > last_synced_block => Err(anyhow!(
+                "Cannot rewind to block {} ahead of the last synced block {}",
+                block,
+                last_synced_block
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for IndexerCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexerCommand::Pause => write!(f, "pause"),
+            IndexerCommand::Resume => write!(f, "resume"),
+            IndexerCommand::Restart => write!(f, "restart"),
+            IndexerCommand::Rewind { block } => write!(f, "rewind to block {}", block),
+            IndexerCommand::Backfill {
+                from_block,
+                to_block,
+            } => write!(f, "backfill blocks {}-{}", from_block, to_block),
+        }
+    }
+}
+
+/// Body of the rewind admin endpoint
+#[derive(Debug, Deserialize)]
+pub struct RewindRequest {
+    pub block: u64,
+}
+
+/// Body of the backfill admin endpoint
+#[derive(Debug, Deserialize)]
+pub struct BackfillRequest {
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+/// Sending side of a supervisor's control channel
+#[derive(Debug, Clone)]
+pub struct IndexerControl {
+    tx: mpsc::Sender<IndexerCommand>,
+    /// Set while this instance holds the chain's indexing lock
+    leader: Arc<AtomicBool>,
+}
+
+impl IndexerControl {
+    /// Create a control handle and the receiver its supervisor reads commands from
+    pub fn channel() -> (Self, mpsc::Receiver<IndexerCommand>) {
+        let (tx, rx) = mpsc::channel(CONTROL_QUEUE_SIZE);
+        let leader = Arc::new(AtomicBool::new(false));
+        (Self { tx, leader }, rx)
+    }
+
+    /// Whether this instance indexes the chain (standby instances leave it to the lock holder)
+    pub fn is_leader(&self) -> bool {
+        self.leader.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_leader(&self, leader: bool) {
+        self.leader.store(leader, Ordering::Relaxed);
+    }
+
+    /// Queue a command, waiting while the supervisor's queue is full
+    pub async fn send(&self, command: IndexerCommand) -> Result<()> {
+        self.tx
+            .send(command)
+            .await
+            .map_err(|_| anyhow!("Indexer is not running"))
+    }
+}
+
+/// Control handles of the supervised chains
+/// Clones share the handles, so supervisors started or stopped on config reload are seen by the API
+#[derive(Debug, Clone, Default)]
+pub struct ChainControls {
+    chains: Arc<DashMap<u64, IndexerControl>>,
+}
+
+impl ChainControls {
+    pub fn insert(&self, chain_id: u64, control: IndexerControl) {
+        self.chains.insert(chain_id, control);
+    }
+
+    pub fn remove(&self, chain_id: u64) {
+        self.chains.remove(&chain_id);
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<IndexerControl> {
+        self.chains.get(&chain_id).map(|control| control.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_properties() {
+        assert!(IndexerCommand::Pause.stops_indexer());
+        assert!(IndexerCommand::Rewind { block: 100 }.stops_indexer());
+        assert!(!IndexerCommand::Resume.stops_indexer());
+
+        let backfill = IndexerCommand::Backfill {
+            from_block: 10,
+            to_block: 20,
+        };
+        assert!(!backfill.stops_indexer());
+        assert!(backfill.validate().is_ok());
+        assert!(IndexerCommand::Backfill {
+            from_block: 20,
+            to_block: 10
+        }
+        .validate()
+        .is_err());
+        assert_eq!(backfill.to_string(), "backfill blocks 10-20");
+        assert_eq!(
+            IndexerCommand::Rewind { block: 100 }.to_string(),
+            "rewind to block 100"
+        );
+
+        // Rewinds may not move the cursor forward
+        let rewind = IndexerCommand::Rewind { block: 100 };
+        assert!(rewind.validate_cursor(100).is_ok());
+        assert!(rewind.validate_cursor(150).is_ok());
+        assert!(rewind.validate_cursor(99).is_err());
+        assert!(backfill.validate_cursor(0).is_ok());
+
+        // Commands are stored as tagged JSON
+        let json = serde_json::to_value(&backfill).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "backfill", "from_block": 10, "to_block": 20})
+        );
+        assert_eq!(
+            serde_json::from_value::<IndexerCommand>(json).unwrap(),
+            backfill
+        );
+        assert_eq!(
+            serde_json::from_value::<IndexerCommand>(serde_json::json!({"type": "pause"})).unwrap(),
+            IndexerCommand::Pause
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indexer_control_send() {
+        let (control, mut rx) = IndexerControl::channel();
+        assert!(!control.is_leader());
+        control.set_leader(true);
+        assert!(control.clone().is_leader());
+
+        control.send(IndexerCommand::Restart).await.unwrap();
+        assert_eq!(rx.recv().await, Some(IndexerCommand::Restart));
+
+        drop(rx);
+        assert!(control.send(IndexerCommand::Pause).await.is_err());
+    }
+}
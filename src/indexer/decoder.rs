@@ -0,0 +1,1391 @@
+use crate::config::{ChainConfig, EventAbiConfig};
+use crate::contracts::{
+    IdentityRegistry, IdentityRegistryV2, ReputationRegistry, ReputationRegistryV2,
+    ValidationRegistry, ValidationRegistryV2,
+};
+use crate::models::{
+    ApprovalData, ApprovalForAllData, Event, EventData, EventType, FeedbackRevokedData,
+    MetadataSetData, NewFeedbackData, RawLog, RegisteredData, ResponseAppendedData, TransferData,
+    UnknownLog, UriUpdatedData, ValidationRequestData, ValidationResponseData,
+};
+use alloy::{
+    dyn_abi::{DynSolValue, EventExt},
+    json_abi::{self, JsonAbi},
+    primitives::{Address, Bytes, Log as PrimitiveLog, LogData, B256},
+    rpc::types::Log,
+    sol_types::SolEvent,
+};
+use anyhow::{anyhow, Context, Result};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::warn;
+
+/// ERC-8004 registry contract that emitted a log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryKind {
+    Identity,
+    Reputation,
+    Validation,
+}
+
+impl RegistryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RegistryKind::Identity => "identity",
+            RegistryKind::Reputation => "reputation",
+            RegistryKind::Validation => "validation",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "identity" => Some(RegistryKind::Identity),
+            "reputation" => Some(RegistryKind::Reputation),
+            "validation" => Some(RegistryKind::Validation),
+            _ => None,
+        }
+    }
+
+    /// Registry that emits a given built-in event type (None for ABI-defined events)
+    pub fn for_event_type(event_type: &EventType) -> Option<Self> {
+        match event_type {
+            EventType::Registered
+            | EventType::MetadataSet
+            | EventType::UriUpdated
+            | EventType::Transfer
+            | EventType::Approval
+            | EventType::ApprovalForAll => Some(RegistryKind::Identity),
+            EventType::NewFeedback | EventType::FeedbackRevoked | EventType::ResponseAppended => {
+                Some(RegistryKind::Reputation)
+            }
+            EventType::ValidationRequest | EventType::ValidationResponse => {
+                Some(RegistryKind::Validation)
+            }
+            EventType::Custom(_) => None,
+        }
+    }
+}
+
+/// Position of a log on chain, shared by every event decoded from it
+#[derive(Debug, Clone)]
+pub struct LogContext {
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub block_timestamp: chrono::DateTime<chrono::Utc>,
+    pub transaction_hash: String,
+    pub log_index: u32,
+    pub contract_address: String,
+}
+
+impl LogContext {
+    /// Build the context of a mined RPC log
+    pub fn from_rpc_log(
+        chain_id: u64,
+        log: &Log,
+        block_number: u64,
+        block_timestamp: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        Self {
+            chain_id,
+            block_number,
+            block_timestamp,
+            transaction_hash: format!("{:?}", log.transaction_hash.unwrap_or_default()),
+            log_index: log.log_index.unwrap_or_default() as u32,
+            contract_address: format!("{:?}", log.address()),
+        }
+    }
+
+    /// Context of an already stored event
+    pub fn from_event(event: &Event) -> Self {
+        Self {
+            chain_id: event.chain_id,
+            block_number: event.block_number,
+            block_timestamp: event.block_timestamp,
+            transaction_hash: event.transaction_hash.clone(),
+            log_index: event.log_index,
+            contract_address: event.contract_address.clone(),
+        }
+    }
+
+    /// Context of a stored dead-letter log
+    pub fn from_unknown_log(unknown: &UnknownLog) -> Self {
+        Self {
+            chain_id: unknown.chain_id,
+            block_number: unknown.block_number,
+            block_timestamp: unknown.block_timestamp,
+            transaction_hash: unknown.transaction_hash.clone(),
+            log_index: unknown.log_index,
+            contract_address: unknown.contract_address.clone(),
+        }
+    }
+
+    fn event(&self, event_type: EventType, event_data: EventData) -> Event {
+        Event {
+            id: None,
+            chain_id: self.chain_id,
+            block_number: self.block_number,
+            block_timestamp: self.block_timestamp,
+            transaction_hash: self.transaction_hash.clone(),
+            log_index: self.log_index,
+            contract_address: self.contract_address.clone(),
+            event_type,
+            event_data,
+            created_at: None,
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
+        }
+    }
+}
+
+/// Decoder for a single event signature
+pub trait EventDecoder: Send + Sync {
+    /// Event signature hash (topic0) this decoder handles
+    fn topic0(&self) -> B256;
+
+    /// Decode a log whose topic0 matches into its event type and data
+    fn decode(&self, log: &PrimitiveLog) -> Result<(EventType, EventData)>;
+}
+
+/// Decoder for an event declared with `sol!` in `contracts/mod.rs`
+struct SolEventDecoder<E> {
+    event_type: EventType,
+    convert: fn(E) -> EventData,
+}
+
+impl<E: SolEvent> EventDecoder for SolEventDecoder<E> {
+    fn topic0(&self) -> B256 {
+        E::SIGNATURE_HASH
+    }
+
+    fn decode(&self, log: &PrimitiveLog) -> Result<(EventType, EventData)> {
+        let decoded = E::decode_log(log, true)
+            .with_context(|| format!("Failed to decode {}", self.event_type.as_str()))?;
+        Ok((self.event_type.clone(), (self.convert)(decoded.data)))
+    }
+}
+
+fn sol_decoder<E: SolEvent + 'static>(
+    event_type: EventType,
+    convert: fn(E) -> EventData,
+) -> Arc<dyn EventDecoder> {
+    Arc::new(SolEventDecoder {
+        event_type,
+        convert,
+    })
+}
+
+/// Decoder for an event loaded from a JSON ABI
+/// Event data is a JSON object keyed by parameter name
+pub struct AbiEventDecoder {
+    event: json_abi::Event,
+}
+
+impl AbiEventDecoder {
+    /// None for anonymous events, which have no topic0 to match on
+    pub fn new(event: json_abi::Event) -> Option<Self> {
+        (!event.anonymous).then_some(Self { event })
+    }
+}
+
+impl EventDecoder for AbiEventDecoder {
+    fn topic0(&self) -> B256 {
+        self.event.selector()
+    }
+
+    fn decode(&self, log: &PrimitiveLog) -> Result<(EventType, EventData)> {
+        let decoded = self
+            .event
+            .decode_log(&log.data, true)
+            .with_context(|| format!("Failed to decode {}", self.event.signature()))?;
+
+        let mut indexed = decoded.indexed.into_iter();
+        let mut body = decoded.body.into_iter();
+        let mut fields = serde_json::Map::new();
+
+        for (i, param) in self.event.inputs.iter().enumerate() {
+            let value = if param.indexed {
+                indexed.next()
+            } else {
+                body.next()
+            }
+            .ok_or_else(|| anyhow!("Missing value for parameter {}", i))?;
+
+            let name = if param.name.is_empty() {
+                format!("arg{}", i)
+            } else {
+                param.name.clone()
+            };
+            fields.insert(name, dyn_value_to_json(value));
+        }
+
+        Ok((
+            EventType::Custom(self.event.name.clone()),
+            EventData::Custom(fields),
+        ))
+    }
+}
+
+/// Convert a decoded ABI value to JSON, using the same encodings as the built-in events
+/// (integers as decimal strings, addresses and bytes as 0x-prefixed hex)
+fn dyn_value_to_json(value: DynSolValue) -> serde_json::Value {
+    use serde_json::Value;
+
+    match value {
+        DynSolValue::Bool(b) => Value::Bool(b),
+        DynSolValue::Int(i, _) => Value::String(i.to_string()),
+        DynSolValue::Uint(u, _) => Value::String(u.to_string()),
+        DynSolValue::FixedBytes(word, size) => {
+            Value::String(format!("0x{}", hex::encode(&word[..size])))
+        }
+        DynSolValue::Address(address) => Value::String(format!("{:?}", address)),
+        DynSolValue::Function(function) => Value::String(format!("{:?}", function)),
+        DynSolValue::Bytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+        DynSolValue::String(s) => Value::String(s),
+        DynSolValue::Array(values)
+        | DynSolValue::FixedArray(values)
+        | DynSolValue::Tuple(values) => {
+            Value::Array(values.into_iter().map(dyn_value_to_json).collect())
+        }
+    }
+}
+
+/// ABI version of registry contracts configured without an explicit deployment
+pub const DEFAULT_ABI_VERSION: &str = "v1";
+
+/// Contract indexed over a block range with the decoders of a role and ABI version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deployment {
+    pub role: String,
+    pub abi_version: String,
+    /// First indexed block (inclusive)
+    pub from_block: u64,
+    /// Last indexed block (inclusive, None while the deployment is active)
+    pub to_block: Option<u64>,
+}
+
+impl Deployment {
+    /// Deployment indexed over all blocks with the default ABI version
+    pub fn new(role: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            abi_version: DEFAULT_ABI_VERSION.to_string(),
+            from_block: 0,
+            to_block: None,
+        }
+    }
+
+    pub fn is_active_at(&self, block: u64) -> bool {
+        self.overlaps(block, block)
+    }
+
+    /// Whether the deployment is active anywhere in `from..=to`
+    fn overlaps(&self, from: u64, to: u64) -> bool {
+        self.from_block <= to && self.to_block.is_none_or(|end| end >= from)
+    }
+}
+
+/// Decoders for one event signature, keyed by role, ABI version and topic0
+/// Decoders without a version (loaded from JSON ABIs) apply to every version of the role
+type DecoderKey = (String, Option<String>, B256);
+
+/// Event decoders and the contract deployments they apply to
+/// Built-in roles are the three ERC-8004 registries (with one decoder set per ABI version);
+/// ABI files can add events and roles
+#[derive(Clone)]
+pub struct DecoderRegistry {
+    contracts: HashMap<Address, Vec<Deployment>>,
+    decoders: HashMap<DecoderKey, Arc<dyn EventDecoder>>,
+}
+
+impl fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecoderRegistry")
+            .field("contracts", &self.contracts)
+            .field("decoders", &self.decoders.len())
+            .finish()
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl DecoderRegistry {
+    /// Registry with the built-in ERC-8004 event decoders (v1 and v2) and no contracts
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            contracts: HashMap::new(),
+            decoders: HashMap::new(),
+        };
+        registry.register_v1_decoders();
+        registry.register_v2_decoders();
+        for abi_version in ["v1", "v2"] {
+            registry.register_erc721_decoders(abi_version);
+        }
+        registry
+    }
+
+    /// Original ERC-8004 registry events (`contracts::{IdentityRegistry, ...}`)
+    fn register_v1_decoders(&mut self) {
+        let v1 = Some("v1");
+
+        let identity = RegistryKind::Identity.as_str();
+        self.register(
+            identity,
+            v1,
+            sol_decoder(EventType::Registered, |e: IdentityRegistry::Registered| {
+                EventData::Registered(RegisteredData {
+                    agent_id: e.agentId.to_string(),
+                    token_uri: e.tokenURI,
+                    owner: format!("{:?}", e.owner),
+                })
+            }),
+        );
+        self.register(
+            identity,
+            v1,
+            sol_decoder(
+                EventType::MetadataSet,
+                |e: IdentityRegistry::MetadataSet| {
+                    EventData::MetadataSet(MetadataSetData {
+                        agent_id: e.agentId.to_string(),
+                        indexed_key: format!("{:?}", e.indexedKey),
+                        key: e.key,
+                        value: format!("0x{}", hex::encode(&e.value)),
+                    })
+                },
+            ),
+        );
+        self.register(
+            identity,
+            v1,
+            sol_decoder(EventType::UriUpdated, |e: IdentityRegistry::UriUpdated| {
+                EventData::UriUpdated(UriUpdatedData {
+                    agent_id: e.agentId.to_string(),
+                    new_uri: e.newUri,
+                    updated_by: format!("{:?}", e.updatedBy),
+                })
+            }),
+        );
+
+        let reputation = RegistryKind::Reputation.as_str();
+        self.register(
+            reputation,
+            v1,
+            sol_decoder(
+                EventType::NewFeedback,
+                |e: ReputationRegistry::NewFeedback| {
+                    EventData::NewFeedback(NewFeedbackData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.client),
+                        score: e.score,
+                        tag1: format!("{:?}", e.tag1),
+                        tag2: format!("{:?}", e.tag2),
+                        feedback_uri: e.feedbackURI,
+                        feedback_hash: format!("{:?}", e.feedbackHash),
+                        feedback_index: None,
+                        value: None,
+                        value_decimals: None,
+                        endpoint: None,
+                    })
+                },
+            ),
+        );
+        self.register(
+            reputation,
+            v1,
+            sol_decoder(
+                EventType::FeedbackRevoked,
+                |e: ReputationRegistry::FeedbackRevoked| {
+                    EventData::FeedbackRevoked(FeedbackRevokedData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.client),
+                        feedback_index: e.feedbackIndex.to_string(),
+                    })
+                },
+            ),
+        );
+        self.register(
+            reputation,
+            v1,
+            sol_decoder(
+                EventType::ResponseAppended,
+                |e: ReputationRegistry::ResponseAppended| {
+                    EventData::ResponseAppended(ResponseAppendedData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.client),
+                        feedback_index: e.feedbackIndex.to_string(),
+                        responder: format!("{:?}", e.responder),
+                        response_uri: e.responseURI,
+                        response_hash: format!("{:?}", e.responseHash),
+                    })
+                },
+            ),
+        );
+
+        let validation = RegistryKind::Validation.as_str();
+        self.register(
+            validation,
+            v1,
+            sol_decoder(
+                EventType::ValidationRequest,
+                |e: ValidationRegistry::ValidationRequest| {
+                    EventData::ValidationRequest(ValidationRequestData {
+                        validator_address: format!("{:?}", e.validatorAddress),
+                        agent_id: e.agentId.to_string(),
+                        request_uri: e.requestUri,
+                        request_hash: format!("{:?}", e.requestHash),
+                    })
+                },
+            ),
+        );
+        self.register(
+            validation,
+            v1,
+            sol_decoder(
+                EventType::ValidationResponse,
+                |e: ValidationRegistry::ValidationResponse| {
+                    EventData::ValidationResponse(ValidationResponseData {
+                        validator_address: format!("{:?}", e.validatorAddress),
+                        agent_id: e.agentId.to_string(),
+                        request_hash: format!("{:?}", e.requestHash),
+                        response: e.response,
+                        response_uri: e.responseUri,
+                        response_hash: format!("{:?}", e.responseHash),
+                        tag: format!("{:?}", e.tag),
+                    })
+                },
+            ),
+        );
+    }
+
+    /// ERC-721 events of the identity registry (the same in every spec version)
+    fn register_erc721_decoders(&mut self, abi_version: &str) {
+        let identity = RegistryKind::Identity.as_str();
+        let version = Some(abi_version);
+
+        self.register(
+            identity,
+            version,
+            sol_decoder(EventType::Transfer, |e: IdentityRegistry::Transfer| {
+                EventData::Transfer(TransferData {
+                    agent_id: e.tokenId.to_string(),
+                    from: format!("{:?}", e.from),
+                    to: format!("{:?}", e.to),
+                })
+            }),
+        );
+        self.register(
+            identity,
+            version,
+            sol_decoder(EventType::Approval, |e: IdentityRegistry::Approval| {
+                EventData::Approval(ApprovalData {
+                    agent_id: e.tokenId.to_string(),
+                    owner: format!("{:?}", e.owner),
+                    approved: format!("{:?}", e.approved),
+                })
+            }),
+        );
+        self.register(
+            identity,
+            version,
+            sol_decoder(
+                EventType::ApprovalForAll,
+                |e: IdentityRegistry::ApprovalForAll| {
+                    EventData::ApprovalForAll(ApprovalForAllData {
+                        owner: format!("{:?}", e.owner),
+                        operator: format!("{:?}", e.operator),
+                        approved: e.approved,
+                    })
+                },
+            ),
+        );
+    }
+
+    /// v2 spec revision events (`contracts::{IdentityRegistryV2, ...}`), normalised into the
+    /// same event types and data as v1
+    fn register_v2_decoders(&mut self) {
+        let v2 = Some("v2");
+
+        let identity = RegistryKind::Identity.as_str();
+        self.register(
+            identity,
+            v2,
+            sol_decoder(
+                EventType::Registered,
+                |e: IdentityRegistryV2::Registered| {
+                    EventData::Registered(RegisteredData {
+                        agent_id: e.agentId.to_string(),
+                        token_uri: e.agentURI,
+                        owner: format!("{:?}", e.owner),
+                    })
+                },
+            ),
+        );
+        self.register(
+            identity,
+            v2,
+            sol_decoder(
+                EventType::MetadataSet,
+                |e: IdentityRegistryV2::MetadataSet| {
+                    EventData::MetadataSet(MetadataSetData {
+                        agent_id: e.agentId.to_string(),
+                        indexed_key: format!("{:?}", e.indexedMetadataKey),
+                        key: e.metadataKey,
+                        value: format!("0x{}", hex::encode(&e.metadataValue)),
+                    })
+                },
+            ),
+        );
+        self.register(
+            identity,
+            v2,
+            sol_decoder(
+                EventType::UriUpdated,
+                |e: IdentityRegistryV2::URIUpdated| {
+                    EventData::UriUpdated(UriUpdatedData {
+                        agent_id: e.agentId.to_string(),
+                        new_uri: e.newURI,
+                        updated_by: format!("{:?}", e.updatedBy),
+                    })
+                },
+            ),
+        );
+
+        let reputation = RegistryKind::Reputation.as_str();
+        self.register(
+            reputation,
+            v2,
+            sol_decoder(
+                EventType::NewFeedback,
+                |e: ReputationRegistryV2::NewFeedback| {
+                    EventData::NewFeedback(NewFeedbackData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.clientAddress),
+                        score: feedback_score(e.value, e.valueDecimals),
+                        tag1: e.tag1,
+                        tag2: e.tag2,
+                        feedback_uri: e.feedbackURI,
+                        feedback_hash: format!("{:?}", e.feedbackHash),
+                        feedback_index: Some(e.feedbackIndex.to_string()),
+                        value: Some(e.value.to_string()),
+                        value_decimals: Some(e.valueDecimals),
+                        endpoint: Some(e.endpoint),
+                    })
+                },
+            ),
+        );
+        self.register(
+            reputation,
+            v2,
+            sol_decoder(
+                EventType::FeedbackRevoked,
+                |e: ReputationRegistryV2::FeedbackRevoked| {
+                    EventData::FeedbackRevoked(FeedbackRevokedData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.clientAddress),
+                        feedback_index: e.feedbackIndex.to_string(),
+                    })
+                },
+            ),
+        );
+        self.register(
+            reputation,
+            v2,
+            sol_decoder(
+                EventType::ResponseAppended,
+                |e: ReputationRegistryV2::ResponseAppended| {
+                    EventData::ResponseAppended(ResponseAppendedData {
+                        agent_id: e.agentId.to_string(),
+                        client: format!("{:?}", e.clientAddress),
+                        feedback_index: e.feedbackIndex.to_string(),
+                        responder: format!("{:?}", e.responder),
+                        response_uri: e.responseURI,
+                        response_hash: format!("{:?}", e.responseHash),
+                    })
+                },
+            ),
+        );
+
+        let validation = RegistryKind::Validation.as_str();
+        self.register(
+            validation,
+            v2,
+            sol_decoder(
+                EventType::ValidationRequest,
+                |e: ValidationRegistryV2::ValidationRequest| {
+                    EventData::ValidationRequest(ValidationRequestData {
+                        validator_address: format!("{:?}", e.validatorAddress),
+                        agent_id: e.agentId.to_string(),
+                        request_uri: e.requestURI,
+                        request_hash: format!("{:?}", e.requestHash),
+                    })
+                },
+            ),
+        );
+        self.register(
+            validation,
+            v2,
+            sol_decoder(
+                EventType::ValidationResponse,
+                |e: ValidationRegistryV2::ValidationResponse| {
+                    EventData::ValidationResponse(ValidationResponseData {
+                        validator_address: format!("{:?}", e.validatorAddress),
+                        agent_id: e.agentId.to_string(),
+                        request_hash: format!("{:?}", e.requestHash),
+                        response: e.response,
+                        response_uri: e.responseURI,
+                        response_hash: format!("{:?}", e.responseHash),
+                        tag: e.tag,
+                    })
+                },
+            ),
+        );
+    }
+
+    /// Built-in decoders plus the chain's registry deployments and configured event ABIs
+    /// Registry addresses in `contracts` without an entry in `contracts.deployments` are
+    /// indexed over all blocks with the default ABI version
+    pub fn from_chain_config(chain: &ChainConfig) -> Result<Self> {
+        let mut registry = Self::builtin();
+
+        for deployment in &chain.contracts.deployments {
+            let kind = RegistryKind::parse(&deployment.registry).with_context(|| {
+                format!(
+                    "Unknown registry '{}' (expected identity, reputation or validation)",
+                    deployment.registry
+                )
+            })?;
+            let address = Address::from_str(&deployment.address)
+                .with_context(|| format!("Invalid deployment address {}", deployment.address))?;
+
+            registry.add_deployment(
+                address,
+                Deployment {
+                    role: kind.as_str().to_string(),
+                    abi_version: deployment.abi_version.clone(),
+                    from_block: deployment.from_block,
+                    to_block: deployment.to_block,
+                },
+            )?;
+        }
+
+        for (kind, address) in [
+            (RegistryKind::Identity, &chain.contracts.identity_registry),
+            (
+                RegistryKind::Reputation,
+                &chain.contracts.reputation_registry,
+            ),
+            (
+                RegistryKind::Validation,
+                &chain.contracts.validation_registry,
+            ),
+        ] {
+            let address = Address::from_str(address)
+                .with_context(|| format!("Invalid {}_registry address", kind.as_str()))?;
+            registry.add_contract(address, kind.as_str());
+        }
+
+        for abi in &chain.event_abis {
+            registry
+                .load_abi_config(abi)
+                .with_context(|| format!("Failed to load event ABI {}", abi.abi))?;
+        }
+
+        Ok(registry)
+    }
+
+    /// Register a decoder for a role and ABI version (None for every version)
+    /// The first decoder registered for a topic0 wins
+    pub fn register(
+        &mut self,
+        role: &str,
+        abi_version: Option<&str>,
+        decoder: Arc<dyn EventDecoder>,
+    ) -> bool {
+        let key = (
+            role.to_string(),
+            abi_version.map(str::to_string),
+            decoder.topic0(),
+        );
+        if self.decoders.contains_key(&key) {
+            return false;
+        }
+        self.decoders.insert(key, decoder);
+        true
+    }
+
+    /// Index logs of a contract over all blocks with the default ABI version of a role
+    /// Contracts that already have deployments are left unchanged
+    pub fn add_contract(&mut self, address: Address, role: &str) {
+        self.contracts
+            .entry(address)
+            .or_insert_with(|| vec![Deployment::new(role)]);
+    }
+
+    /// Index logs of a contract over a block range with a role's decoders for an ABI version
+    pub fn add_deployment(&mut self, address: Address, deployment: Deployment) -> Result<()> {
+        if deployment
+            .to_block
+            .is_some_and(|end| end < deployment.from_block)
+        {
+            return Err(anyhow!(
+                "Deployment {:?} ends (block {:?}) before it starts (block {})",
+                address,
+                deployment.to_block,
+                deployment.from_block
+            ));
+        }
+
+        if RegistryKind::parse(&deployment.role).is_some()
+            && !self.has_abi_version(&deployment.role, &deployment.abi_version)
+        {
+            return Err(anyhow!(
+                "Unsupported {} ABI version '{}'",
+                deployment.role,
+                deployment.abi_version
+            ));
+        }
+
+        let deployments = self.contracts.entry(address).or_default();
+        if let Some(existing) = deployments.iter().find(|d| {
+            d.overlaps(
+                deployment.from_block,
+                deployment.to_block.unwrap_or(u64::MAX),
+            )
+        }) {
+            return Err(anyhow!(
+                "Deployment {:?} from block {} overlaps its {} deployment from block {}",
+                address,
+                deployment.from_block,
+                existing.abi_version,
+                existing.from_block
+            ));
+        }
+
+        deployments.push(deployment);
+        Ok(())
+    }
+
+    /// Whether built-in decoders exist for a role's ABI version
+    fn has_abi_version(&self, role: &str, abi_version: &str) -> bool {
+        self.decoders
+            .keys()
+            .any(|(r, v, _)| r == role && v.as_deref() == Some(abi_version))
+    }
+
+    /// Register every non-anonymous event of a JSON ABI for a role (all ABI versions)
+    /// Returns the number of events added (events already decoded for the role are skipped)
+    pub fn register_abi(&mut self, role: &str, abi: &JsonAbi) -> usize {
+        let mut added = 0;
+
+        for event in abi.events() {
+            let Some(decoder) = AbiEventDecoder::new(event.clone()) else {
+                warn!("Skipping anonymous event {} for role {}", event.name, role);
+                continue;
+            };
+
+            let builtin = self
+                .decoders
+                .keys()
+                .any(|(r, _, topic0)| r == role && *topic0 == decoder.topic0());
+            if !builtin && self.register(role, None, Arc::new(decoder)) {
+                added += 1;
+            } else {
+                warn!(
+                    "Event {} is already decoded for role {}, ignoring ABI definition",
+                    event.signature(),
+                    role
+                );
+            }
+        }
+
+        added
+    }
+
+    /// Load an event ABI referenced from chains.yaml
+    fn load_abi_config(&mut self, config: &EventAbiConfig) -> Result<usize> {
+        match &config.address {
+            Some(address) => {
+                let address = Address::from_str(address).context("Invalid contract address")?;
+                self.add_contract(address, &config.role);
+            }
+            None if RegistryKind::parse(&config.role).is_none() => {
+                return Err(anyhow!(
+                    "Contract role '{}' is not a built-in registry and needs an address",
+                    config.role
+                ));
+            }
+            None => {}
+        }
+
+        let abi = load_json_abi(&config.abi)?;
+        Ok(self.register_abi(&config.role, &abi))
+    }
+
+    /// Deployment of a contract active at a block (None for unrelated or inactive contracts)
+    pub fn deployment(&self, address: Address, block: u64) -> Option<&Deployment> {
+        self.contracts
+            .get(&address)?
+            .iter()
+            .find(|d| d.is_active_at(block))
+    }
+
+    /// All configured deployments, ordered by role and first block
+    pub fn deployments(&self) -> Vec<(Address, &Deployment)> {
+        let mut deployments: Vec<_> = self
+            .contracts
+            .iter()
+            .flat_map(|(address, deployments)| deployments.iter().map(|d| (*address, d)))
+            .collect();
+        deployments
+            .sort_by(|(a, x), (b, y)| (&x.role, x.from_block, a).cmp(&(&y.role, y.from_block, b)));
+        deployments
+    }
+
+    /// Address of the newest deployment of a role (the active one, if any)
+    pub fn latest_deployment(&self, role: &str) -> Option<Address> {
+        self.deployments()
+            .into_iter()
+            .filter(|(_, d)| d.role == role)
+            .max_by_key(|(_, d)| (d.to_block.is_none(), d.to_block, d.from_block))
+            .map(|(address, _)| address)
+    }
+
+    /// Addresses of the contracts with a deployment active anywhere in `from..=to`
+    pub fn addresses_active_in(&self, from: u64, to: u64) -> Vec<Address> {
+        self.contracts
+            .iter()
+            .filter(|(_, deployments)| deployments.iter().any(|d| d.overlaps(from, to)))
+            .map(|(address, _)| *address)
+            .collect()
+    }
+
+    /// Role and ABI version whose decoders produced a stored event
+    /// Uses the deployment active at the event's block first, then the registry of
+    /// built-in event types with the event's stored spec version
+    pub fn event_deployment(&self, event: &Event) -> Option<(String, String)> {
+        let deployment = Address::from_str(&event.contract_address)
+            .ok()
+            .and_then(|address| self.deployment(address, event.block_number));
+        if let Some(deployment) = deployment {
+            return Some((deployment.role.clone(), deployment.abi_version.clone()));
+        }
+
+        RegistryKind::for_event_type(&event.event_type).map(|kind| {
+            (
+                kind.as_str().to_string(),
+                event
+                    .spec_version
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_ABI_VERSION.to_string()),
+            )
+        })
+    }
+
+    /// Decode a log emitted by a contract with the given role and ABI version
+    /// Registry events are tagged with the ABI version as spec version; the raw log is
+    /// attached so the event can be re-decoded later
+    pub fn decode(
+        &self,
+        role: &str,
+        abi_version: &str,
+        ctx: &LogContext,
+        log: &PrimitiveLog,
+    ) -> Result<Event> {
+        let topic0 = *log
+            .topics()
+            .first()
+            .ok_or_else(|| anyhow!("Log has no topics"))?;
+        let decoder = self
+            .decoders
+            .get(&(role.to_string(), Some(abi_version.to_string()), topic0))
+            .or_else(|| self.decoders.get(&(role.to_string(), None, topic0)))
+            .ok_or_else(|| {
+                anyhow!(
+                    "No {} {} decoder for topic0 {:?}",
+                    role,
+                    abi_version,
+                    topic0
+                )
+            })?;
+
+        let (event_type, event_data) = decoder.decode(log)?;
+        let mut event = ctx.event(event_type, event_data);
+        event.raw = Some(raw_log(log));
+        if RegistryKind::parse(role).is_some() {
+            event.spec_version = Some(abi_version.to_string());
+        }
+        Ok(event)
+    }
+
+    /// Decode a hex-encoded raw log without any RPC access
+    pub fn decode_raw(
+        &self,
+        role: &str,
+        abi_version: &str,
+        ctx: &LogContext,
+        raw: &RawLog,
+    ) -> Result<Event> {
+        let topics = raw
+            .topics
+            .iter()
+            .map(|t| B256::from_str(t).with_context(|| format!("Invalid topic {}", t)))
+            .collect::<Result<Vec<_>>>()?;
+        let data = Bytes::from_str(&raw.data).context("Invalid log data")?;
+        let address = Address::from_str(&ctx.contract_address).context("Invalid address")?;
+
+        let log = PrimitiveLog {
+            address,
+            data: LogData::new_unchecked(topics, data),
+        };
+
+        self.decode(role, abi_version, ctx, &log)
+    }
+
+    /// Decode a stored dead-letter log again (e.g. after decoder support was added)
+    /// Uses the ABI version of the contract's deployment at the log's block
+    pub fn redecode_unknown_log(&self, unknown: &UnknownLog) -> Result<Event> {
+        let raw = RawLog {
+            topics: unknown.topics.clone(),
+            data: unknown.data.clone(),
+        };
+        let abi_version = Address::from_str(&unknown.contract_address)
+            .ok()
+            .and_then(|address| self.deployment(address, unknown.block_number))
+            .filter(|deployment| deployment.role == unknown.registry)
+            .map_or(DEFAULT_ABI_VERSION, |deployment| &deployment.abi_version);
+
+        self.decode_raw(
+            &unknown.registry,
+            abi_version,
+            &LogContext::from_unknown_log(unknown),
+            &raw,
+        )
+    }
+}
+
+/// Derive a 0-100 score from a v2 feedback value (`value / 10^decimals`, clamped)
+fn feedback_score(value: i128, decimals: u8) -> u8 {
+    let whole = 10i128
+        .checked_pow(decimals as u32)
+        .map_or(0, |scale| value / scale);
+    whole.clamp(0, 100) as u8
+}
+
+/// Decoder registries of the configured chains
+/// Chains without one (e.g. removed from chains.yaml) use the built-in decoders.
+/// Clones share the registries, so chains added or removed on config reload are seen everywhere
+#[derive(Debug, Clone, Default)]
+pub struct ChainDecoders {
+    chains: Arc<DashMap<u64, Arc<DecoderRegistry>>>,
+    builtin: Arc<DecoderRegistry>,
+}
+
+impl ChainDecoders {
+    /// Build the registries of all configured chains
+    pub fn from_chains(chains: &[ChainConfig]) -> Result<Self> {
+        let decoders = Self::default();
+        for chain in chains {
+            let registry = DecoderRegistry::from_chain_config(chain)
+                .with_context(|| format!("Invalid decoder configuration for {}", chain.name))?;
+            decoders.insert(chain.chain_id, Arc::new(registry));
+        }
+        Ok(decoders)
+    }
+
+    pub fn insert(&self, chain_id: u64, registry: Arc<DecoderRegistry>) {
+        self.chains.insert(chain_id, registry);
+    }
+
+    pub fn remove(&self, chain_id: u64) {
+        self.chains.remove(&chain_id);
+    }
+
+    pub fn get(&self, chain_id: u64) -> Arc<DecoderRegistry> {
+        self.chains
+            .get(&chain_id)
+            .map_or_else(|| self.builtin.clone(), |registry| registry.clone())
+    }
+}
+
+/// Read a JSON ABI file (a plain ABI array or a build artifact with an `abi` field)
+pub fn load_json_abi(path: &str) -> Result<JsonAbi> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let value: serde_json::Value =
+        serde_json::from_str(&content).with_context(|| format!("Invalid JSON in {}", path))?;
+    let abi = match value {
+        serde_json::Value::Object(mut artifact) if artifact.contains_key("abi") => {
+            artifact.remove("abi").unwrap_or_default()
+        }
+        value => value,
+    };
+    serde_json::from_value(abi).with_context(|| format!("Invalid ABI in {}", path))
+}
+
+/// Convert RPC Log to Primitive Log for event decoding
+pub fn convert_log(log: &Log) -> PrimitiveLog {
+    PrimitiveLog {
+        address: log.address(),
+        data: LogData::new_unchecked(log.topics().to_vec(), log.data().data.clone()),
+    }
+}
+
+/// Hex-encode the topics and data of a log
+pub fn raw_log(log: &PrimitiveLog) -> RawLog {
+    RawLog {
+        topics: log.topics().iter().map(|t| format!("{:?}", t)).collect(),
+        data: format!("0x{}", hex::encode(&log.data.data)),
+    }
+}
+
+/// Build a dead-letter record for a contract log that could not be decoded
+pub fn unknown_log(
+    role: &str,
+    ctx: &LogContext,
+    log: &PrimitiveLog,
+    error: &anyhow::Error,
+) -> UnknownLog {
+    let raw = raw_log(log);
+    UnknownLog {
+        id: None,
+        chain_id: ctx.chain_id,
+        block_number: ctx.block_number,
+        block_timestamp: ctx.block_timestamp,
+        transaction_hash: ctx.transaction_hash.clone(),
+        log_index: ctx.log_index,
+        contract_address: ctx.contract_address.clone(),
+        registry: role.to_string(),
+        topics: raw.topics,
+        data: raw.data,
+        error: format!("{:#}", error),
+        created_at: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::U256;
+
+    const IDENTITY_ADDRESS: &str = "0x8004a6090cd10a7288092483047b097295fb8847";
+
+    fn test_context() -> LogContext {
+        LogContext {
+            chain_id: 11155111,
+            block_number: 1000,
+            block_timestamp: chrono::Utc::now(),
+            transaction_hash: format!("0x{:064x}", 1),
+            log_index: 3,
+            contract_address: IDENTITY_ADDRESS.to_string(),
+        }
+    }
+
+    fn registered_log() -> PrimitiveLog {
+        let event = IdentityRegistry::Registered {
+            agentId: U256::from(42),
+            tokenURI: "ipfs://agent".to_string(),
+            owner: Address::repeat_byte(0x11),
+        };
+        PrimitiveLog {
+            address: Address::from_str(IDENTITY_ADDRESS).unwrap(),
+            data: event.encode_log_data(),
+        }
+    }
+
+    #[test]
+    fn test_decode_registered_event() {
+        let event = DecoderRegistry::builtin()
+            .decode("identity", "v1", &test_context(), &registered_log())
+            .unwrap();
+
+        assert_eq!(event.event_type, EventType::Registered);
+        assert_eq!(event.log_index, 3);
+        match event.event_data {
+            EventData::Registered(data) => {
+                assert_eq!(data.agent_id, "42");
+                assert_eq!(data.token_uri, "ipfs://agent");
+            }
+            _ => panic!("Expected Registered event data"),
+        }
+    }
+
+    #[test]
+    fn test_decode_transfer_event() {
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let log = PrimitiveLog {
+            address: Address::from_str(IDENTITY_ADDRESS).unwrap(),
+            data: IdentityRegistry::Transfer {
+                from,
+                to,
+                tokenId: U256::from(42),
+            }
+            .encode_log_data(),
+        };
+
+        // ERC-721 events decode with every identity registry version
+        let registry = DecoderRegistry::builtin();
+        for abi_version in ["v1", "v2"] {
+            let event = registry
+                .decode("identity", abi_version, &test_context(), &log)
+                .unwrap();
+            assert_eq!(event.event_type, EventType::Transfer);
+            match event.event_data {
+                EventData::Transfer(data) => {
+                    assert_eq!(data.agent_id, "42");
+                    assert_eq!(data.from, format!("{:?}", from));
+                    assert_eq!(data.to, format!("{:?}", to));
+                }
+                _ => panic!("Expected Transfer event data"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_log_roundtrip() {
+        let registry = DecoderRegistry::builtin();
+        let ctx = test_context();
+        let log = registered_log();
+
+        // An identity log decoded as the wrong registry ends up in the dead-letter table
+        let error = registry.decode("reputation", "v1", &ctx, &log).unwrap_err();
+        let mut unknown = unknown_log("reputation", &ctx, &log, &error);
+        assert_eq!(unknown.registry, "reputation");
+        assert_eq!(unknown.topics.len(), log.topics().len());
+        assert!(registry.redecode_unknown_log(&unknown).is_err());
+
+        // Once the right decoder is used, the raw log decodes to the original event
+        unknown.registry = RegistryKind::Identity.as_str().to_string();
+        let event = registry.redecode_unknown_log(&unknown).unwrap();
+        assert_eq!(event.event_type, EventType::Registered);
+        assert_eq!(event.transaction_hash, ctx.transaction_hash);
+    }
+
+    #[test]
+    fn test_decode_raw_matches_decoded_event() {
+        let registry = DecoderRegistry::builtin();
+        let event = registry
+            .decode("identity", "v1", &test_context(), &registered_log())
+            .unwrap();
+        let raw = event
+            .raw
+            .clone()
+            .expect("decoded events carry their raw log");
+
+        assert_eq!(event.spec_version.as_deref(), Some("v1"));
+        let (role, abi_version) = registry.event_deployment(&event).unwrap();
+        assert_eq!((role.as_str(), abi_version.as_str()), ("identity", "v1"));
+
+        let redecoded = registry
+            .decode_raw(&role, &abi_version, &LogContext::from_event(&event), &raw)
+            .unwrap();
+        assert_eq!(redecoded.event_type, event.event_type);
+        assert_eq!(
+            serde_json::to_value(&redecoded.event_data).unwrap(),
+            serde_json::to_value(&event.event_data).unwrap()
+        );
+        assert_eq!(redecoded.raw, Some(raw));
+    }
+
+    #[test]
+    fn test_registry_kind_parse() {
+        for kind in [
+            RegistryKind::Identity,
+            RegistryKind::Reputation,
+            RegistryKind::Validation,
+        ] {
+            assert_eq!(RegistryKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(RegistryKind::parse("erc721"), None);
+    }
+
+    #[test]
+    fn test_abi_event_decoder() {
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[
+                {
+                    "type": "event",
+                    "name": "Delegated",
+                    "anonymous": false,
+                    "inputs": [
+                        {"name": "from", "type": "address", "indexed": true},
+                        {"name": "to", "type": "address", "indexed": true},
+                        {"name": "tokenId", "type": "uint256", "indexed": true}
+                    ]
+                },
+                {
+                    "type": "event",
+                    "name": "Registered",
+                    "anonymous": false,
+                    "inputs": [
+                        {"name": "agentId", "type": "uint256", "indexed": true},
+                        {"name": "tokenURI", "type": "string", "indexed": false},
+                        {"name": "owner", "type": "address", "indexed": true}
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        // Registered already has a typed built-in decoder, so only Delegated is added
+        let mut registry = DecoderRegistry::builtin();
+        assert_eq!(registry.register_abi("identity", &abi), 1);
+
+        let from = Address::repeat_byte(0x11);
+        let to = Address::repeat_byte(0x22);
+        let log = PrimitiveLog {
+            address: Address::from_str(IDENTITY_ADDRESS).unwrap(),
+            data: LogData::new_unchecked(
+                vec![
+                    abi.events["Delegated"][0].selector(),
+                    from.into_word(),
+                    to.into_word(),
+                    B256::from(U256::from(7)),
+                ],
+                Bytes::new(),
+            ),
+        };
+
+        let event = registry
+            .decode("identity", "v1", &test_context(), &log)
+            .unwrap();
+        assert_eq!(event.event_type, EventType::Custom("Delegated".to_string()));
+        let data = serde_json::to_value(&event.event_data).unwrap();
+        assert_eq!(data["from"], format!("{:?}", from));
+        assert_eq!(data["to"], format!("{:?}", to));
+        assert_eq!(data["tokenId"], "7");
+
+        // Only the role the ABI was registered for decodes the event
+        assert!(registry
+            .decode("reputation", "v1", &test_context(), &log)
+            .is_err());
+
+        let event = registry
+            .decode("identity", "v1", &test_context(), &registered_log())
+            .unwrap();
+        assert_eq!(event.event_type, EventType::Registered);
+    }
+
+    #[test]
+    fn test_contract_roles() {
+        let mut registry = DecoderRegistry::builtin();
+        let identity = Address::from_str(IDENTITY_ADDRESS).unwrap();
+        let custom = Address::repeat_byte(0x33);
+        registry.add_contract(identity, "identity");
+        registry.add_contract(custom, "token");
+
+        let role = |address| registry.deployment(address, 0).map(|d| d.role.clone());
+        assert_eq!(role(identity).as_deref(), Some("identity"));
+        assert_eq!(role(custom).as_deref(), Some("token"));
+        assert_eq!(role(Address::ZERO), None);
+        assert_eq!(registry.addresses_active_in(0, u64::MAX).len(), 2);
+    }
+
+    #[test]
+    fn test_decode_v2_feedback() {
+        let event = ReputationRegistryV2::NewFeedback {
+            agentId: U256::from(42),
+            clientAddress: Address::repeat_byte(0x11),
+            feedbackIndex: 3,
+            value: 8750,
+            valueDecimals: 2,
+            indexedTag1: B256::ZERO,
+            tag1: "uptime".to_string(),
+            tag2: "latency".to_string(),
+            endpoint: "https://agent.example/a2a".to_string(),
+            feedbackURI: "ipfs://feedback".to_string(),
+            feedbackHash: B256::repeat_byte(0xaa),
+        };
+        let log = PrimitiveLog {
+            address: Address::repeat_byte(0x22),
+            data: event.encode_log_data(),
+        };
+
+        let registry = DecoderRegistry::builtin();
+        assert!(registry
+            .decode("reputation", "v1", &test_context(), &log)
+            .is_err());
+
+        let event = registry
+            .decode("reputation", "v2", &test_context(), &log)
+            .unwrap();
+        assert_eq!(event.event_type, EventType::NewFeedback);
+        assert_eq!(event.spec_version.as_deref(), Some("v2"));
+        match event.event_data {
+            EventData::NewFeedback(data) => {
+                assert_eq!(data.agent_id, "42");
+                assert_eq!(data.score, 87);
+                assert_eq!(data.tag1, "uptime");
+                assert_eq!(data.feedback_index.as_deref(), Some("3"));
+                assert_eq!(data.value.as_deref(), Some("8750"));
+                assert_eq!(data.value_decimals, Some(2));
+                assert_eq!(data.endpoint.as_deref(), Some("https://agent.example/a2a"));
+            }
+            _ => panic!("Expected NewFeedback event data"),
+        }
+    }
+
+    #[test]
+    fn test_deployment_block_ranges() {
+        let mut registry = DecoderRegistry::builtin();
+        let old = Address::repeat_byte(0x11);
+        let new = Address::repeat_byte(0x22);
+        let deployment = |abi_version: &str, from_block, to_block| Deployment {
+            role: "identity".to_string(),
+            abi_version: abi_version.to_string(),
+            from_block,
+            to_block,
+        };
+
+        registry
+            .add_deployment(old, deployment("v1", 0, Some(999)))
+            .unwrap();
+        registry
+            .add_deployment(new, deployment("v2", 1000, None))
+            .unwrap();
+
+        assert_eq!(registry.deployment(old, 999).unwrap().abi_version, "v1");
+        assert!(registry.deployment(old, 1000).is_none());
+        assert!(registry.deployment(new, 999).is_none());
+        assert_eq!(registry.deployment(new, 5000).unwrap().abi_version, "v2");
+
+        assert_eq!(registry.addresses_active_in(0, 500), vec![old]);
+        assert_eq!(registry.addresses_active_in(1000, 2000), vec![new]);
+        assert_eq!(registry.addresses_active_in(900, 1100).len(), 2);
+        assert_eq!(registry.latest_deployment("identity"), Some(new));
+        assert_eq!(registry.latest_deployment("reputation"), None);
+
+        // A legacy registry address does not replace the configured deployments
+        registry.add_contract(old, "identity");
+        assert!(registry.deployment(old, 1000).is_none());
+
+        let error = registry
+            .add_deployment(new, deployment("v1", 2000, None))
+            .unwrap_err();
+        assert!(error.to_string().contains("overlaps"));
+        assert!(registry
+            .add_deployment(Address::ZERO, deployment("v9", 0, None))
+            .is_err());
+        assert!(registry
+            .add_deployment(Address::ZERO, deployment("v1", 10, Some(5)))
+            .is_err());
+    }
+
+    #[test]
+    fn test_feedback_score() {
+        assert_eq!(feedback_score(87, 0), 87);
+        assert_eq!(feedback_score(8750, 2), 87);
+        assert_eq!(feedback_score(-5, 0), 0);
+        assert_eq!(feedback_score(250, 0), 100);
+        assert_eq!(feedback_score(1, 255), 0);
+    }
+}
@@ -1,53 +1,116 @@
+pub mod chains;
+pub mod control;
+pub mod decoder;
+pub mod reindex;
+pub mod subscription;
 pub mod supervisor;
+pub mod verification;
 
-use crate::config::IndexerConfig;
-use crate::contracts::{IdentityRegistry, ReputationRegistry, ValidationRegistry};
-use crate::models::{
-    Event, EventData, EventType, FeedbackRevokedData, MetadataSetData, NewFeedbackData,
-    RegisteredData, ResponseAppendedData, UriUpdatedData, ValidationRequestData,
-    ValidationResponseData,
-};
-use crate::rpc::ProviderManager;
+use crate::config::{HeadBlockTag, IndexerConfig};
+use crate::rpc::{ProviderError, ProviderManager};
 use crate::stats::StatsTracker;
-use crate::storage::Storage;
+use crate::storage::{BlockHeader, Storage};
 use alloy::{
-    primitives::{Log as PrimitiveLog, LogData},
+    eips::BlockNumberOrTag,
     providers::{Provider, ProviderBuilder, RootProvider},
     rpc::types::{BlockTransactionsKind, Filter, Log},
-    sol_types::SolEvent,
     transports::http::{Client, Http},
 };
 use anyhow::{Context, Result};
+use decoder::{Deployment, LogContext, RegistryKind};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use subscription::HeadSubscription;
 use tokio::sync::RwLock;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
-/// Event indexer that fetches events block by block with adaptive polling
+/// Result of checking the chain against stored block headers
+enum ReorgCheck {
+    /// Stored headers match the chain; carries the canonical header of the checked block
+    Canonical(BlockHeader),
+    /// A reorg was detected and storage was rolled back to this common ancestor
+    RolledBack(u64),
+}
+
+/// Logs of a block range fetched ahead of being committed to storage
+struct FetchedRange {
+    logs: Vec<Log>,
+    block_timestamps: HashMap<u64, chrono::DateTime<chrono::Utc>>,
+    headers: Vec<BlockHeader>,
+}
+
+/// Progress of a catch-up range
+struct RangeSync {
+    /// Last block committed without gaps
+    last_block: u64,
+    events: usize,
+}
+
+/// How often the finalized block is refreshed when following `latest` or `safe`
+const FINALITY_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the retry queue is checked for failed block ranges that are due
+const FAILED_RANGE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum number of failed block ranges retried per check
+const FAILED_RANGE_RETRY_BATCH: i64 = 5;
+
+/// Backoff before retrying a failed block range, doubling per attempt (30s up to 1h)
+fn failed_range_retry_delay(attempts: u32) -> Duration {
+    const BASE: Duration = Duration::from_secs(30);
+    const MAX: Duration = Duration::from_secs(3600);
+
+    BASE.saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)))
+        .min(MAX)
+}
+
+/// Sleep for `duration`, returning early once `shutdown` is cancelled
+pub(crate) async fn sleep_unless_cancelled(shutdown: &CancellationToken, duration: Duration) {
+    tokio::select! {
+        _ = shutdown.cancelled() => {}
+        _ = sleep(duration) => {}
+    }
+}
+
+/// Split `from..=to` into consecutive inclusive chunks of at most `span` blocks
+fn split_block_range(from: u64, to: u64, span: u64) -> Vec<(u64, u64)> {
+    let mut chunks = Vec::new();
+    let mut chunk_start = from;
+    while chunk_start <= to {
+        let chunk_end = chunk_start.saturating_add(span - 1).min(to);
+        chunks.push((chunk_start, chunk_end));
+        if chunk_end == u64::MAX {
+            break;
+        }
+        chunk_start = chunk_end + 1;
+    }
+    chunks
+}
+
+/// Event indexer that fetches events with range-based eth_getLogs and adaptive polling
 pub struct Indexer {
     config: IndexerConfig,
     provider: Arc<RwLock<RootProvider<Http<Client>>>>,
     provider_manager: Arc<ProviderManager>,
     current_rpc_url: Arc<RwLock<String>>,
     storage: Storage,
-    event_tx: broadcast::Sender<Event>,
     stats_tracker: StatsTracker,
+    /// Highest block known to be finalized (0 = unknown)
+    finalized_block: AtomicU64,
 }
 
 impl Indexer {
+    /// The provider manager is shared with the supervisor so providers can be swapped at runtime
     pub async fn new(
         config: IndexerConfig,
+        provider_manager: Arc<ProviderManager>,
         storage: Storage,
-        event_tx: broadcast::Sender<Event>,
         stats_tracker: StatsTracker,
     ) -> Result<Self> {
-        // Create provider manager
-        let provider_manager = Arc::new(ProviderManager::new(
-            config.rpc_providers.clone(),
-            config.name.clone(),
-        )?);
-
         // Get initial RPC URL
         let initial_url = provider_manager.get_current_provider().await?;
 
@@ -61,8 +124,8 @@ impl Indexer {
             provider_manager,
             current_rpc_url: Arc::new(RwLock::new(initial_url)),
             storage,
-            event_tx,
             stats_tracker,
+            finalized_block: AtomicU64::new(0),
         })
     }
 
@@ -96,8 +159,18 @@ impl Indexer {
         Ok(())
     }
 
+    /// The current provider together with the URL it was built from, so request outcomes are
+    /// recorded for the provider that actually served them
+    async fn current_provider(&self) -> (RootProvider<Http<Client>>, String) {
+        let provider = self.provider.read().await;
+        let url = self.current_rpc_url.read().await.clone();
+        (provider.clone(), url)
+    }
+
     /// Start the indexer loop with adaptive polling
-    pub async fn start(&self) -> Result<()> {
+    /// Returns Ok once `shutdown` is cancelled; a block or range being committed at that point
+    /// is finished first, so the cursor always matches the stored events
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<()> {
         info!("[{}] Starting ERC-8004 event indexer", self.config.name);
         info!("[{}] Chain ID: {}", self.config.name, self.config.chain_id);
         info!("[{}] Monitoring contracts:", self.config.name);
@@ -113,50 +186,48 @@ impl Indexer {
             "[{}]   ValidationRegistry: {}",
             self.config.name, self.config.validation_registry
         );
+        for (address, deployment) in self.config.decoders.deployments() {
+            if RegistryKind::parse(&deployment.role).is_some()
+                && *deployment == Deployment::new(&deployment.role)
+            {
+                continue;
+            }
+            info!(
+                "[{}]   {} contract: {} ({} from block {} to {})",
+                self.config.name,
+                deployment.role,
+                address,
+                deployment.abi_version,
+                deployment.from_block,
+                deployment
+                    .to_block
+                    .map_or_else(|| "latest".to_string(), |b| b.to_string())
+            );
+        }
 
         // Get starting block (per-chain)
-        // IMPORTANT: Resume from last_synced_block - 1 to ensure no events are missed on crash
+        // The cursor is committed together with the events, so it always points at the next
+        // block to sync
         let mut current_block = match self
             .storage
             .get_last_synced_block_for_chain(self.config.chain_id)
             .await
         {
-            Ok(block) if block > 1 => {
-                let resume_block = block.saturating_sub(1);
-                info!("[{}] Resuming from block {} (last synced: {}, replaying last block to ensure no missed events)",
-                    self.config.name, resume_block, block);
-                resume_block
+            Ok(block) if block > 0 => {
+                info!("[{}] Resuming from block {}", self.config.name, block);
+                block
             }
             _ => {
                 let block = if self.config.starting_block == 0 {
                     // Refresh provider before first call
                     self.refresh_provider_if_needed().await?;
 
-                    let result = tokio::time::timeout(Duration::from_secs(30), async {
-                        let provider = self.provider.read().await;
-                        provider.get_block_number().await
-                    })
-                    .await;
-
-                    match result {
-                        Ok(Ok(block_num)) => {
-                            self.provider_manager.mark_success().await;
-                            block_num
-                        }
-                        Ok(Err(e)) => {
-                            self.provider_manager
-                                .mark_error(&format!("get_block_number failed: {}", e))
-                                .await;
+                    match self.fetch_head_block(self.config.block_tag).await {
+                        Ok(head) => head.saturating_sub(self.config.confirmations),
+                        Err(e) => {
                             self.refresh_provider_if_needed().await?;
                             return Err(e).context("Failed to get current block number");
                         }
-                        Err(_) => {
-                            self.provider_manager
-                                .mark_error("get_block_number timeout")
-                                .await;
-                            self.refresh_provider_if_needed().await?;
-                            return Err(anyhow::anyhow!("Timeout getting current block number"));
-                        }
                     }
                 } else {
                     self.config.starting_block
@@ -166,9 +237,22 @@ impl Indexer {
             }
         };
 
+        // New heads from a WebSocket subscription wake the loop as soon as a block arrives.
+        // Blocks are always synced from the cursor, so a gap left while the subscription was
+        // down is backfilled by the regular catch-up modes.
+        let mut ws_providers = self.config.rpc_providers.clone();
+        ws_providers.sort_by_key(|p| p.priority);
+        let mut heads = HeadSubscription::spawn(
+            &self.config.name,
+            self.config.chain_id,
+            ws_providers.into_iter().filter_map(|p| p.ws_url).collect(),
+        );
+
         let mut poll_interval = self.config.poll_interval;
+        let mut last_finality_refresh: Option<Instant> = None;
+        let mut last_failed_range_check: Option<Instant> = None;
 
-        loop {
+        while !shutdown.is_cancelled() {
             // Refresh provider if needed (rotation or recovery)
             if let Err(e) = self.refresh_provider_if_needed().await {
                 warn!("[{}] Failed to refresh provider: {}", self.config.name, e);
@@ -177,40 +261,27 @@ impl Indexer {
             // Record polling event for stats
             self.stats_tracker.record_poll(self.config.chain_id);
 
-            // Get latest block to calculate lag (with 30s timeout)
-            let latest_block = match tokio::time::timeout(Duration::from_secs(30), async {
-                let provider = self.provider.read().await;
-                provider.get_block_number().await
-            })
-            .await
-            {
-                Ok(Ok(block)) => {
-                    self.provider_manager.mark_success().await;
-                    // Update current block for stats
-                    self.stats_tracker
-                        .update_current_block(self.config.chain_id, block);
-                    block
-                }
-                Ok(Err(e)) => {
+            // Get the followed head (latest/safe/finalized) to calculate lag
+            let head_block = match self.fetch_head_block(self.config.block_tag).await {
+                Ok(block) => block,
+                Err(e) => {
                     error!("[{}] Failed to get latest block: {}", self.config.name, e);
-                    self.provider_manager
-                        .mark_error(&format!("get_block_number failed: {}", e))
-                        .await;
-                    self.refresh_provider_if_needed().await.ok(); // Try to recover
-                    sleep(Duration::from_secs(5)).await;
-                    continue;
-                }
-                Err(_) => {
-                    error!("[{}] Timeout getting latest block (>30s)", self.config.name);
-                    self.provider_manager
-                        .mark_error("get_block_number timeout")
-                        .await;
                     self.refresh_provider_if_needed().await.ok(); // Try to recover
-                    sleep(Duration::from_secs(5)).await;
+                    sleep_unless_cancelled(&shutdown, Duration::from_secs(5)).await;
                     continue;
                 }
             };
 
+            // Stay `confirmations` blocks behind the head
+            let latest_block = head_block.saturating_sub(self.config.confirmations);
+
+            // Update current block for stats
+            self.stats_tracker
+                .update_current_block(self.config.chain_id, latest_block);
+
+            self.refresh_finalized_block(head_block, &mut last_finality_refresh)
+                .await;
+
             let blocks_behind = latest_block.saturating_sub(current_block);
 
             // Adaptive polling: adjust speed based on how far behind we are
@@ -218,6 +289,34 @@ impl Indexer {
                 poll_interval = self.calculate_adaptive_interval(blocks_behind);
             }
 
+            // Retry failed block ranges that are due
+            if last_failed_range_check.is_none_or(|t| t.elapsed() >= FAILED_RANGE_CHECK_INTERVAL) {
+                last_failed_range_check = Some(Instant::now());
+                self.retry_failed_ranges().await;
+            }
+
+            // Detect chain reorganizations before indexing past the stored chain tip
+            let start_header = if blocks_behind > 0 {
+                match self.check_for_reorg(current_block).await {
+                    Ok(ReorgCheck::Canonical(header)) => Some(header),
+                    Ok(ReorgCheck::RolledBack(ancestor)) => {
+                        current_block = ancestor + 1;
+                        continue;
+                    }
+                    Err(e) => {
+                        error!(
+                            "[{}] Reorg check failed at block {}: {}",
+                            self.config.name, current_block, e
+                        );
+                        warn!("[{}] Retrying in 5 seconds...", self.config.name);
+                        sleep_unless_cancelled(&shutdown, Duration::from_secs(5)).await;
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
             // Determine sync strategy based on blocks behind
             match blocks_behind {
                 0 => {
@@ -226,11 +325,13 @@ impl Indexer {
                         "[{}] Caught up at block {}",
                         self.config.name, current_block
                     );
-                    sleep(poll_interval).await;
+                    self.wait_for_next_block(&mut heads, current_block, poll_interval, &shutdown)
+                        .await;
                 }
                 1..=10 => {
                     // Near real-time - process one by one
-                    match self.sync_block(current_block).await {
+                    // The reorg check already fetched this block's header
+                    match self.sync_block(current_block, start_header.as_ref()).await {
                         Ok(events_found) => {
                             if events_found > 0 {
                                 info!(
@@ -240,24 +341,16 @@ impl Indexer {
                             } else {
                                 debug!("[{}] Block {}: No events", self.config.name, current_block);
                             }
-                            current_block += 1;
 
-                            // Update last synced block
-                            if let Err(e) = self
-                                .storage
-                                .update_last_synced_block_for_chain(
-                                    self.config.chain_id,
-                                    current_block,
-                                )
-                                .await
-                            {
-                                warn!(
-                                    "[{}] Failed to update last synced block: {}",
-                                    self.config.name, e
-                                );
-                            }
+                            current_block += 1;
 
-                            sleep(poll_interval).await;
+                            self.wait_for_next_block(
+                                &mut heads,
+                                current_block,
+                                poll_interval,
+                                &shutdown,
+                            )
+                            .await;
                         }
                         Err(e) => {
                             error!(
@@ -265,7 +358,7 @@ impl Indexer {
                                 self.config.name, current_block, e
                             );
                             warn!("[{}] Retrying in 5 seconds...", self.config.name);
-                            sleep(Duration::from_secs(5)).await;
+                            sleep_unless_cancelled(&shutdown, Duration::from_secs(5)).await;
                         }
                     }
                 }
@@ -277,30 +370,23 @@ impl Indexer {
                     );
                     let batch_end = (current_block + self.config.batch_size).min(latest_block);
 
-                    match self.sync_block_range(current_block, batch_end).await {
-                        Ok(total_events) => {
+                    match self
+                        .sync_block_range(current_block, batch_end, &shutdown)
+                        .await
+                    {
+                        Ok(RangeSync {
+                            last_block: synced_to,
+                            events,
+                        }) => {
                             info!(
                                 "[{}] Synced blocks {}-{}: {} events",
-                                self.config.name, current_block, batch_end, total_events
+                                self.config.name, current_block, synced_to, events
                             );
-                            current_block = batch_end + 1;
-
-                            if let Err(e) = self
-                                .storage
-                                .update_last_synced_block_for_chain(
-                                    self.config.chain_id,
-                                    current_block,
-                                )
-                                .await
-                            {
-                                warn!(
-                                    "[{}] Failed to update last synced block: {}",
-                                    self.config.name, e
-                                );
-                            }
+                            current_block = synced_to + 1;
 
                             // Small delay to avoid overwhelming RPC
-                            sleep(Duration::from_millis(50)).await;
+                            sleep_unless_cancelled(&shutdown, self.config.batch_processing_delay)
+                                .await;
                         }
                         Err(e) => {
                             error!(
@@ -308,7 +394,7 @@ impl Indexer {
                                 self.config.name, current_block, batch_end, e
                             );
                             warn!("[{}] Retrying in 5 seconds...", self.config.name);
-                            sleep(Duration::from_secs(5)).await;
+                            sleep_unless_cancelled(&shutdown, Duration::from_secs(5)).await;
                         }
                     }
                 }
@@ -318,29 +404,28 @@ impl Indexer {
                         "[{}] {} blocks behind, aggressive catch-up mode",
                         self.config.name, blocks_behind
                     );
-                    let batch_end = (current_block + 100).min(latest_block);
-
-                    match self.sync_block_range(current_block, batch_end).await {
-                        Ok(total_events) => {
+                    // Fetch up to `max_parallel_blocks` log ranges concurrently
+                    let batch_span = self
+                        .config
+                        .max_block_span
+                        .saturating_mul(self.config.max_parallel_blocks as u64);
+                    let batch_end = current_block
+                        .saturating_add(batch_span - 1)
+                        .min(latest_block);
+
+                    match self
+                        .sync_block_range(current_block, batch_end, &shutdown)
+                        .await
+                    {
+                        Ok(RangeSync {
+                            last_block: synced_to,
+                            events,
+                        }) => {
                             info!(
                                 "[{}] Synced blocks {}-{}: {} events",
-                                self.config.name, current_block, batch_end, total_events
+                                self.config.name, current_block, synced_to, events
                             );
-                            current_block = batch_end + 1;
-
-                            if let Err(e) = self
-                                .storage
-                                .update_last_synced_block_for_chain(
-                                    self.config.chain_id,
-                                    current_block,
-                                )
-                                .await
-                            {
-                                warn!(
-                                    "[{}] Failed to update last synced block: {}",
-                                    self.config.name, e
-                                );
-                            }
+                            current_block = synced_to + 1;
 
                             // No delay - max speed
                         }
@@ -350,12 +435,48 @@ impl Indexer {
                                 self.config.name, current_block, batch_end, e
                             );
                             warn!("[{}] Retrying in 5 seconds...", self.config.name);
-                            sleep(Duration::from_secs(5)).await;
+                            sleep_unless_cancelled(&shutdown, Duration::from_secs(5)).await;
                         }
                     }
                 }
             }
         }
+
+        info!(
+            "[{}] Indexer stopped at block {}",
+            self.config.name, current_block
+        );
+        Ok(())
+    }
+
+    /// Wait before checking the head again
+    /// With a connected head subscription this returns as soon as a head arrives that makes
+    /// `current_block` indexable (for `safe`/`finalized` any new head re-checks the tag); otherwise
+    /// it sleeps `interval`. Returns early on shutdown
+    async fn wait_for_next_block(
+        &self,
+        heads: &mut Option<HeadSubscription>,
+        current_block: u64,
+        interval: Duration,
+        shutdown: &CancellationToken,
+    ) {
+        let Some(heads) = heads.as_mut() else {
+            sleep_unless_cancelled(shutdown, interval).await;
+            return;
+        };
+
+        let target = match self.config.block_tag {
+            HeadBlockTag::Latest => current_block
+                .saturating_add(self.config.confirmations)
+                .saturating_add(1),
+            HeadBlockTag::Safe | HeadBlockTag::Finalized => heads
+                .latest()
+                .map_or(u64::MAX, |head| head.saturating_add(1)),
+        };
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = heads.wait_for_head(target, interval) => {}
+        }
     }
 
     /// Calculate adaptive polling interval based on how far behind we are
@@ -369,395 +490,693 @@ impl Indexer {
     }
 
     /// Sync a range of blocks (for catch-up)
-    async fn sync_block_range(&self, from: u64, to: u64) -> Result<usize> {
-        let mut total_events = 0;
+    /// Logs are fetched with one eth_getLogs request per `max_block_span` blocks, up to
+    /// `max_parallel_blocks` requests in flight. Chunks are committed in block order and
+    /// syncing stops at the first failed chunk, so the returned block never skips a gap.
+    /// On shutdown it stops after the chunk being committed
+    async fn sync_block_range(
+        &self,
+        from: u64,
+        to: u64,
+        shutdown: &CancellationToken,
+    ) -> Result<RangeSync> {
+        let chunks = split_block_range(from, to, self.config.max_block_span);
+
+        let delay = self.config.batch_processing_delay;
+        let fetches = stream::iter(chunks.into_iter().enumerate())
+            .then(|(index, chunk)| async move {
+                // Space out request starts to avoid RPC rate limits
+                if index > 0 && !delay.is_zero() {
+                    sleep(delay).await;
+                }
+                chunk
+            })
+            .map(|(chunk_start, chunk_end)| async move {
+                let fetched = self
+                    .fetch_range_with_checkpoint(chunk_start, chunk_end)
+                    .await;
+                (chunk_start, chunk_end, fetched)
+            })
+            .buffered(self.config.max_parallel_blocks);
+        let mut fetches = std::pin::pin!(fetches);
+
+        let mut last_block = None;
+        let mut events = 0;
+        let mut failed_chunks: Vec<(u64, u64, anyhow::Error)> = Vec::new();
+
+        while let Some((chunk_start, chunk_end, fetched)) = fetches.next().await {
+            let result = match fetched {
+                Ok(fetched) => {
+                    // A later chunk was fetched, so earlier failures are specific to their
+                    // blocks: queue them for retry before the cursor moves past them
+                    if let Err(e) = self.queue_failed_chunks(&failed_chunks).await {
+                        warn!(
+                            "[{}] Failed to queue failed blocks for retry: {}",
+                            self.config.name, e
+                        );
+                        break;
+                    }
+                    failed_chunks.clear();
 
-        for block_num in from..=to {
-            match self.sync_block(block_num).await {
-                Ok(events) => total_events += events,
+                    self.commit_range(fetched, Some(chunk_end + 1)).await
+                }
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(chunk_events) => {
+                    events += chunk_events;
+                    last_block = Some(chunk_end);
+                    if shutdown.is_cancelled() {
+                        break;
+                    }
+                }
                 Err(e) => {
                     warn!(
-                        "[{}] Failed to sync block {} in range: {}",
-                        self.config.name, block_num, e
+                        "[{}] Failed to sync blocks {}-{}: {}",
+                        self.config.name, chunk_start, chunk_end, e
                     );
-                    // Continue with next block instead of failing entire range
+                    failed_chunks.push((chunk_start, chunk_end, e));
                 }
             }
+        }
 
-            // Small delay to avoid RPC rate limits
-            sleep(Duration::from_millis(50)).await;
+        // Trailing failures are not queued; the range stops before them and they are
+        // refetched next round. Remaining in-flight fetches were dropped with the stream
+        match last_block {
+            Some(last_block) => Ok(RangeSync { last_block, events }),
+            None => match failed_chunks.into_iter().next() {
+                Some((chunk_start, chunk_end, e)) => Err(e).context(format!(
+                    "Failed to sync blocks {}-{}",
+                    chunk_start, chunk_end
+                )),
+                None => Err(anyhow::anyhow!("Empty block range {}-{}", from, to)),
+            },
         }
+    }
 
-        Ok(total_events)
+    /// Persist failed chunks in the retry queue
+    async fn queue_failed_chunks(&self, failed_chunks: &[(u64, u64, anyhow::Error)]) -> Result<()> {
+        for (from, to, error) in failed_chunks {
+            self.storage
+                .record_failed_block_range(
+                    self.config.chain_id,
+                    *from,
+                    *to,
+                    &format!("{:#}", error),
+                    failed_range_retry_delay(1),
+                )
+                .await?;
+
+            metrics::counter!("failed_block_ranges_total", "chain_id" => self.config.chain_id.to_string())
+                .increment(1);
+            warn!(
+                "[{}] Queued blocks {}-{} for retry",
+                self.config.name, from, to
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Re-index `from..=to` (one `max_block_span` chunk at a time) without moving the cursor
+    /// Runs alongside the live loop; events already stored are skipped and failed chunks are
+    /// queued for retry. Returns the number of events found
+    pub async fn backfill(
+        &self,
+        from: u64,
+        to: u64,
+        shutdown: &CancellationToken,
+    ) -> Result<usize> {
+        info!("[{}] Backfilling blocks {}-{}", self.config.name, from, to);
+        self.refresh_provider_if_needed().await?;
+
+        let mut events = 0;
+        for (chunk_start, chunk_end) in split_block_range(from, to, self.config.max_block_span) {
+            if shutdown.is_cancelled() {
+                warn!(
+                    "[{}] Backfill stopped by shutdown before block {}",
+                    self.config.name, chunk_start
+                );
+                break;
+            }
+
+            let result = match self.fetch_range(chunk_start, chunk_end).await {
+                Ok(fetched) => self.commit_range(fetched, None).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(chunk_events) => events += chunk_events,
+                Err(e) => {
+                    warn!(
+                        "[{}] Failed to backfill blocks {}-{}: {}",
+                        self.config.name, chunk_start, chunk_end, e
+                    );
+                    self.queue_failed_chunks(&[(chunk_start, chunk_end, e)])
+                        .await?;
+                }
+            }
+
+            sleep_unless_cancelled(shutdown, self.config.batch_processing_delay).await;
+        }
+
+        Ok(events)
+    }
+
+    /// Retry queued failed block ranges that are due
+    /// Ranges are removed from the queue once they sync; failures back off exponentially
+    async fn retry_failed_ranges(&self) {
+        let due = match self
+            .storage
+            .get_due_failed_block_ranges(self.config.chain_id, FAILED_RANGE_RETRY_BATCH)
+            .await
+        {
+            Ok(due) => due,
+            Err(e) => {
+                warn!(
+                    "[{}] Failed to load failed blocks for retry: {}",
+                    self.config.name, e
+                );
+                return;
+            }
+        };
+
+        for range in due {
+            let result = match self.fetch_range(range.from_block, range.to_block).await {
+                // The cursor is already past queued ranges
+                Ok(fetched) => self.commit_range(fetched, None).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(events) => {
+                    info!(
+                        "[{}] Retried blocks {}-{} after {} failed attempts: {} events",
+                        self.config.name, range.from_block, range.to_block, range.attempts, events
+                    );
+                    if let Err(e) = self
+                        .storage
+                        .delete_failed_block_range(self.config.chain_id, range.from_block)
+                        .await
+                    {
+                        warn!(
+                            "[{}] Failed to remove blocks {}-{} from retry queue: {}",
+                            self.config.name, range.from_block, range.to_block, e
+                        );
+                    }
+                }
+                Err(e) => {
+                    let attempts = range.attempts + 1;
+                    warn!(
+                        "[{}] Retry {} of blocks {}-{} failed: {}",
+                        self.config.name, attempts, range.from_block, range.to_block, e
+                    );
+                    if let Err(e) = self
+                        .storage
+                        .record_failed_block_range(
+                            self.config.chain_id,
+                            range.from_block,
+                            range.to_block,
+                            &format!("{:#}", e),
+                            failed_range_retry_delay(attempts),
+                        )
+                        .await
+                    {
+                        warn!(
+                            "[{}] Failed to reschedule blocks {}-{}: {}",
+                            self.config.name, range.from_block, range.to_block, e
+                        );
+                    }
+                }
+            }
+        }
     }
 
     /// Sync a single block and return number of events found
-    async fn sync_block(&self, block_number: u64) -> Result<usize> {
-        // Get block info for timestamp (with 30s timeout)
-        let block_result = tokio::time::timeout(Duration::from_secs(30), async {
-            let provider = self.provider.read().await;
-            provider
-                .get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes)
-                .await
+    /// `header` is the block's header if it was already fetched; it is stored with the events
+    async fn sync_block(&self, block_number: u64, header: Option<&BlockHeader>) -> Result<usize> {
+        let fetched = match header {
+            Some(header) => {
+                let mut fetched = self.fetch_range(block_number, block_number).await?;
+                fetched.headers.retain(|h| h.block_number != block_number);
+                fetched.headers.push(header.clone());
+                fetched
+            }
+            None => {
+                self.fetch_range_with_checkpoint(block_number, block_number)
+                    .await?
+            }
+        };
+        self.commit_range(fetched, Some(block_number + 1)).await
+    }
+
+    /// Fetch a range that moves the sync cursor, including the header of its last block
+    /// The header is committed with the events, so the next reorg check always finds the
+    /// stored parent of the block after the range
+    async fn fetch_range_with_checkpoint(&self, from: u64, to: u64) -> Result<FetchedRange> {
+        let mut fetched = self.fetch_range(from, to).await?;
+        if !fetched.headers.iter().any(|h| h.block_number == to) {
+            fetched.headers.push(self.fetch_block_header(to).await?);
+        }
+        Ok(fetched)
+    }
+
+    /// Fetch all registry logs in `from..=to` along with the timestamps of their blocks
+    /// Nothing is written to storage, so ranges can be fetched concurrently
+    async fn fetch_range(&self, from: u64, to: u64) -> Result<FetchedRange> {
+        let logs = self.fetch_logs(from, to).await?;
+
+        // Block timestamps are only needed for blocks that actually contain logs
+        let mut block_timestamps: HashMap<u64, chrono::DateTime<chrono::Utc>> = HashMap::new();
+        let mut headers = Vec::new();
+
+        for log in &logs {
+            let Some(block_number) = log.block_number else {
+                continue;
+            };
+
+            if block_timestamps.contains_key(&block_number) {
+                continue;
+            }
+
+            // Some providers include the block timestamp in the log itself
+            let timestamp = match log.block_timestamp {
+                Some(ts) => {
+                    chrono::DateTime::from_timestamp(ts as i64, 0).unwrap_or_else(chrono::Utc::now)
+                }
+                None => {
+                    let header = self.fetch_block_header(block_number).await?;
+
+                    // A hash mismatch means the block was reorged while fetching
+                    if let Some(log_block_hash) = log.block_hash {
+                        let log_block_hash = format!("{:?}", log_block_hash);
+                        if log_block_hash != header.block_hash {
+                            anyhow::bail!(
+                                "Block {} hash changed while fetching logs ({} != {})",
+                                block_number,
+                                log_block_hash,
+                                header.block_hash
+                            );
+                        }
+                    }
+
+                    let timestamp = header.block_timestamp;
+                    headers.push(header);
+                    timestamp
+                }
+            };
+            block_timestamps.insert(block_number, timestamp);
+        }
+
+        Ok(FetchedRange {
+            logs,
+            block_timestamps,
+            headers,
         })
-        .await;
+    }
+
+    /// Decode a fetched range and commit its events and headers in one transaction
+    /// Moves the sync cursor to `next_block` if given and returns number of events found.
+    /// Registry logs that cannot be decoded are kept raw in the unknown logs table.
+    /// Newly inserted events reach WebSocket clients and other sinks through the outbox
+    async fn commit_range(&self, fetched: FetchedRange, next_block: Option<u64>) -> Result<usize> {
+        let mut events = Vec::with_capacity(fetched.logs.len());
+        let mut unknown_logs = Vec::new();
+        let finalized_block = self.finalized_block.load(Ordering::Relaxed);
+
+        for log in &fetched.logs {
+            let Some(block_number) = log.block_number else {
+                warn!(
+                    "[{}] Skipping pending log in tx {}",
+                    self.config.name,
+                    log.transaction_hash.unwrap_or_default()
+                );
+                continue;
+            };
+
+            let block_timestamp = fetched
+                .block_timestamps
+                .get(&block_number)
+                .copied()
+                .unwrap_or_else(chrono::Utc::now);
+
+            // Logs of contracts outside their configured block range are not indexed
+            let Some(deployment) = self.config.decoders.deployment(log.address(), block_number)
+            else {
+                continue;
+            };
+            let role = deployment.role.as_str();
 
-        let block = match block_result {
-            Ok(Ok(Some(b))) => {
-                self.provider_manager.mark_success().await;
-                b
+            let ctx =
+                LogContext::from_rpc_log(self.config.chain_id, log, block_number, block_timestamp);
+            let primitive_log = decoder::convert_log(log);
+
+            match self
+                .config
+                .decoders
+                .decode(role, &deployment.abi_version, &ctx, &primitive_log)
+            {
+                Ok(mut event) => {
+                    event.finalized = block_number <= finalized_block;
+                    events.push(event);
+                }
+                Err(e) => {
+                    warn!(
+                        "[{}] Storing undecodable log in tx {} for re-decoding: {}",
+                        self.config.name, ctx.transaction_hash, e
+                    );
+                    metrics::counter!("unknown_logs_total", "chain_id" => self.config.chain_id.to_string())
+                        .increment(1);
+                    unknown_logs.push(decoder::unknown_log(role, &ctx, &primitive_log, &e));
+                }
             }
-            Ok(Ok(None)) => {
+        }
+
+        let events_found = events.len();
+        self.storage
+            .commit_block_range(
+                self.config.chain_id,
+                events,
+                &unknown_logs,
+                &fetched.headers,
+                next_block,
+            )
+            .await
+            .context("Failed to commit block range")?;
+
+        Ok(events_found)
+    }
+
+    /// Fetch logs from all indexed contracts in `from..=to` (with 30s timeout)
+    /// With log verification enabled, sampled answers are cross-checked against other providers
+    async fn fetch_logs(&self, from: u64, to: u64) -> Result<Vec<Log>> {
+        let addresses = self.config.decoders.addresses_active_in(from, to);
+        if addresses.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let filter = Filter::new()
+            .from_block(from)
+            .to_block(to)
+            .address(addresses);
+
+        let (provider, url) = self.current_provider().await;
+        let started = Instant::now();
+        let logs_result =
+            tokio::time::timeout(Duration::from_secs(30), provider.get_logs(&filter)).await;
+
+        match logs_result {
+            Ok(Ok(logs)) => {
                 self.provider_manager
-                    .mark_error(&format!("Block {} not found", block_number))
+                    .mark_success(&url, started.elapsed())
                     .await;
-                return Err(anyhow::anyhow!("Block {} not found", block_number));
+                self.verify_logs(&filter, from, to, url, logs).await
             }
             Ok(Err(e)) => {
                 self.provider_manager
-                    .mark_error(&format!("get_block_by_number failed: {}", e))
+                    .mark_error(&url, ProviderError::from_transport("get_logs failed", &e))
                     .await;
-                return Err(e).context("Failed to fetch block");
+                Err(e).context("Failed to fetch logs")
             }
             Err(_) => {
                 self.provider_manager
-                    .mark_error("get_block_by_number timeout")
+                    .mark_error(&url, ProviderError::timeout("get_logs timeout"))
                     .await;
-                return Err(anyhow::anyhow!("Timeout fetching block"));
+                Err(anyhow::anyhow!("Timeout fetching logs"))
             }
-        };
+        }
+    }
 
-        let block_timestamp = chrono::DateTime::from_timestamp(block.header.timestamp as i64, 0)
-            .unwrap_or_else(chrono::Utc::now);
+    /// Fetch the block number for a head tag (with 30s timeout)
+    async fn fetch_head_block(&self, tag: HeadBlockTag) -> Result<u64> {
+        let block_tag = match tag {
+            HeadBlockTag::Latest => BlockNumberOrTag::Latest,
+            HeadBlockTag::Safe => BlockNumberOrTag::Safe,
+            HeadBlockTag::Finalized => BlockNumberOrTag::Finalized,
+        };
 
-        // Fetch logs from all three contracts (with 30s timeout)
-        let filter = Filter::new()
-            .from_block(block_number)
-            .to_block(block_number)
-            .address(vec![
-                self.config.identity_registry,
-                self.config.reputation_registry,
-                self.config.validation_registry,
-            ]);
-
-        let logs_result = tokio::time::timeout(Duration::from_secs(30), async {
-            let provider = self.provider.read().await;
-            provider.get_logs(&filter).await
+        let (provider, url) = self.current_provider().await;
+        let started = Instant::now();
+        let result = tokio::time::timeout(Duration::from_secs(30), async {
+            match block_tag {
+                BlockNumberOrTag::Latest => provider.get_block_number().await.map(Some),
+                _ => provider
+                    .get_block_by_number(block_tag, BlockTransactionsKind::Hashes)
+                    .await
+                    .map(|block| block.map(|b| b.header.number)),
+            }
         })
         .await;
 
-        let logs = match logs_result {
-            Ok(Ok(l)) => {
-                self.provider_manager.mark_success().await;
-                l
+        match result {
+            Ok(Ok(Some(block))) => {
+                self.provider_manager
+                    .mark_success(&url, started.elapsed())
+                    .await;
+                Ok(block)
+            }
+            Ok(Ok(None)) => {
+                self.provider_manager
+                    .mark_error(
+                        &url,
+                        ProviderError::data_missing(format!("{} block not available", block_tag)),
+                    )
+                    .await;
+                Err(anyhow::anyhow!("{} block not available", block_tag))
             }
             Ok(Err(e)) => {
                 self.provider_manager
-                    .mark_error(&format!("get_logs failed: {}", e))
+                    .mark_error(
+                        &url,
+                        ProviderError::from_transport(
+                            &format!("get {} block failed", block_tag),
+                            &e,
+                        ),
+                    )
                     .await;
-                return Err(e).context("Failed to fetch logs");
+                Err(e).context(format!("Failed to get {} block", block_tag))
             }
             Err(_) => {
-                self.provider_manager.mark_error("get_logs timeout").await;
-                return Err(anyhow::anyhow!("Timeout fetching logs"));
-            }
-        };
-
-        // Process each log
-        for log in &logs {
-            if let Err(e) = self.process_log(log, block_number, block_timestamp).await {
-                warn!(
-                    "Failed to process log in tx {}: {}",
-                    log.transaction_hash.unwrap_or_default(),
-                    e
-                );
+                self.provider_manager
+                    .mark_error(
+                        &url,
+                        ProviderError::timeout(format!("get {} block timeout", block_tag)),
+                    )
+                    .await;
+                Err(anyhow::anyhow!("Timeout getting {} block", block_tag))
             }
         }
-
-        Ok(logs.len())
     }
 
-    /// Process a single log entry
-    async fn process_log(
-        &self,
-        log: &Log,
-        block_number: u64,
-        block_timestamp: chrono::DateTime<chrono::Utc>,
-    ) -> Result<()> {
-        let contract_address = format!("{:?}", log.address());
-        let tx_hash = format!("{:?}", log.transaction_hash.unwrap_or_default());
-        let log_index = log.log_index.unwrap_or_default() as u32;
-
-        // Determine which contract and decode the event
-        let event = if log.address() == self.config.identity_registry {
-            self.decode_identity_event(
-                log,
-                block_number,
-                block_timestamp,
-                &contract_address,
-                &tx_hash,
-                log_index,
-            )?
-        } else if log.address() == self.config.reputation_registry {
-            self.decode_reputation_event(
-                log,
-                block_number,
-                block_timestamp,
-                &contract_address,
-                &tx_hash,
-                log_index,
-            )?
-        } else if log.address() == self.config.validation_registry {
-            self.decode_validation_event(
-                log,
-                block_number,
-                block_timestamp,
-                &contract_address,
-                &tx_hash,
-                log_index,
-            )?
+    /// Track the finalized block and upgrade stored events that became final
+    /// When following `finalized` the head itself is final; otherwise the
+    /// finalized tag is refetched at most every `FINALITY_REFRESH_INTERVAL`
+    async fn refresh_finalized_block(&self, head_block: u64, last_refresh: &mut Option<Instant>) {
+        let finalized = if self.config.block_tag == HeadBlockTag::Finalized {
+            head_block
         } else {
-            return Ok(());
-        };
-
-        // Store the event in database
-        self.storage.store_event(event.clone()).await?;
-
-        // Broadcast event to WebSocket clients (ignore errors if no receivers)
-        let _ = self.event_tx.send(event);
+            if last_refresh.is_some_and(|t| t.elapsed() < FINALITY_REFRESH_INTERVAL) {
+                return;
+            }
+            *last_refresh = Some(Instant::now());
 
-        Ok(())
-    }
+            match self.fetch_head_block(HeadBlockTag::Finalized).await {
+                Ok(block) => block,
+                Err(e) => {
+                    // Not every chain/provider supports the finalized tag
+                    debug!(
+                        "[{}] Failed to get finalized block: {}",
+                        self.config.name, e
+                    );
+                    return;
+                }
+            }
+        };
 
-    /// Convert RPC Log to Primitive Log for event decoding
-    fn convert_log(log: &Log) -> PrimitiveLog {
-        PrimitiveLog {
-            address: log.address(),
-            data: LogData::new_unchecked(log.topics().to_vec(), log.data().data.clone()),
+        let previous = self.finalized_block.fetch_max(finalized, Ordering::Relaxed);
+        if finalized <= previous {
+            return;
         }
-    }
 
-    fn decode_identity_event(
-        &self,
-        log: &Log,
-        block_number: u64,
-        block_timestamp: chrono::DateTime<chrono::Utc>,
-        contract_address: &str,
-        tx_hash: &str,
-        log_index: u32,
-    ) -> Result<Event> {
-        let prim_log = Self::convert_log(log);
-
-        // Try Registered
-        if let Ok(decoded) = IdentityRegistry::Registered::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::Registered,
-                event_data: EventData::Registered(RegisteredData {
-                    agent_id: decoded.agentId.to_string(),
-                    token_uri: decoded.tokenURI.clone(),
-                    owner: format!("{:?}", decoded.owner),
-                }),
-                created_at: None,
-            });
+        match self
+            .storage
+            .mark_events_finalized(self.config.chain_id, finalized)
+            .await
+        {
+            Ok(0) => {}
+            Ok(count) => debug!(
+                "[{}] Marked {} events finalized up to block {}",
+                self.config.name, count, finalized
+            ),
+            Err(e) => warn!(
+                "[{}] Failed to mark events finalized up to block {}: {}",
+                self.config.name, finalized, e
+            ),
         }
+    }
 
-        // Try MetadataSet
-        if let Ok(decoded) = IdentityRegistry::MetadataSet::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::MetadataSet,
-                event_data: EventData::MetadataSet(MetadataSetData {
-                    agent_id: decoded.agentId.to_string(),
-                    indexed_key: format!("{:?}", decoded.indexedKey),
-                    key: decoded.key.clone(),
-                    value: format!("0x{}", hex::encode(&decoded.value)),
-                }),
-                created_at: None,
-            });
-        }
+    /// Fetch the header of a single block (with 30s timeout)
+    async fn fetch_block_header(&self, block_number: u64) -> Result<BlockHeader> {
+        let (provider, url) = self.current_provider().await;
+        let started = Instant::now();
+        let block_result = tokio::time::timeout(
+            Duration::from_secs(30),
+            provider.get_block_by_number(block_number.into(), BlockTransactionsKind::Hashes),
+        )
+        .await;
 
-        // Try UriUpdated
-        if let Ok(decoded) = IdentityRegistry::UriUpdated::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::UriUpdated,
-                event_data: EventData::UriUpdated(UriUpdatedData {
-                    agent_id: decoded.agentId.to_string(),
-                    new_uri: decoded.newUri.clone(),
-                    updated_by: format!("{:?}", decoded.updatedBy),
-                }),
-                created_at: None,
-            });
+        match block_result {
+            Ok(Ok(Some(block))) => {
+                self.provider_manager
+                    .mark_success(&url, started.elapsed())
+                    .await;
+                Ok(BlockHeader {
+                    block_number,
+                    block_hash: format!("{:?}", block.header.hash),
+                    parent_hash: format!("{:?}", block.header.parent_hash),
+                    block_timestamp: chrono::DateTime::from_timestamp(
+                        block.header.timestamp as i64,
+                        0,
+                    )
+                    .unwrap_or_else(chrono::Utc::now),
+                })
+            }
+            Ok(Ok(None)) => {
+                self.provider_manager
+                    .mark_error(
+                        &url,
+                        ProviderError::data_missing(format!("Block {} not found", block_number)),
+                    )
+                    .await;
+                Err(anyhow::anyhow!("Block {} not found", block_number))
+            }
+            Ok(Err(e)) => {
+                self.provider_manager
+                    .mark_error(
+                        &url,
+                        ProviderError::from_transport("get_block_by_number failed", &e),
+                    )
+                    .await;
+                Err(e).context("Failed to fetch block")
+            }
+            Err(_) => {
+                self.provider_manager
+                    .mark_error(&url, ProviderError::timeout("get_block_by_number timeout"))
+                    .await;
+                Err(anyhow::anyhow!("Timeout fetching block"))
+            }
         }
+    }
 
-        anyhow::bail!("Unknown IdentityRegistry event")
+    /// Move the sync cursor back to `block` for an admin rewind
+    /// The canonical header of the block before it becomes the stored chain tip, so the next
+    /// reorg check accepts the rewound cursor. Returns false when the cursor is behind `block`
+    pub async fn rewind(&self, block: u64) -> Result<bool> {
+        let parent = match block.checked_sub(1) {
+            Some(parent) => Some(self.fetch_block_header(parent).await?),
+            None => None,
+        };
+        self.storage
+            .rewind_chain_to_block(self.config.chain_id, block, parent.as_ref())
+            .await
     }
 
-    fn decode_reputation_event(
-        &self,
-        log: &Log,
-        block_number: u64,
-        block_timestamp: chrono::DateTime<chrono::Utc>,
-        contract_address: &str,
-        tx_hash: &str,
-        log_index: u32,
-    ) -> Result<Event> {
-        let prim_log = Self::convert_log(log);
-
-        // Try NewFeedback
-        if let Ok(decoded) = ReputationRegistry::NewFeedback::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::NewFeedback,
-                event_data: EventData::NewFeedback(NewFeedbackData {
-                    agent_id: decoded.agentId.to_string(),
-                    client: format!("{:?}", decoded.client),
-                    score: decoded.score,
-                    tag1: format!("{:?}", decoded.tag1),
-                    tag2: format!("{:?}", decoded.tag2),
-                    feedback_uri: decoded.feedbackURI.clone(),
-                    feedback_hash: format!("{:?}", decoded.feedbackHash),
-                }),
-                created_at: None,
-            });
-        }
+    /// Compare the parent hash of `next_block` with the stored header of the previous block
+    /// On mismatch, or when the previous block's header is missing, roll storage back to the
+    /// common ancestor and notify WebSocket clients
+    async fn check_for_reorg(&self, next_block: u64) -> Result<ReorgCheck> {
+        let header = self.fetch_block_header(next_block).await?;
 
-        // Try FeedbackRevoked
-        if let Ok(decoded) = ReputationRegistry::FeedbackRevoked::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::FeedbackRevoked,
-                event_data: EventData::FeedbackRevoked(FeedbackRevokedData {
-                    agent_id: decoded.agentId.to_string(),
-                    client: format!("{:?}", decoded.client),
-                    feedback_index: decoded.feedbackIndex.to_string(),
-                }),
-                created_at: None,
-            });
-        }
+        let Some(previous_block) = next_block.checked_sub(1) else {
+            return Ok(ReorgCheck::Canonical(header));
+        };
 
-        // Try ResponseAppended
-        if let Ok(decoded) = ReputationRegistry::ResponseAppended::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::ResponseAppended,
-                event_data: EventData::ResponseAppended(ResponseAppendedData {
-                    agent_id: decoded.agentId.to_string(),
-                    client: format!("{:?}", decoded.client),
-                    feedback_index: decoded.feedbackIndex.to_string(),
-                    responder: format!("{:?}", decoded.responder),
-                    response_uri: decoded.responseURI.clone(),
-                    response_hash: format!("{:?}", decoded.responseHash),
-                }),
-                created_at: None,
-            });
-        }
+        let ancestor = match self
+            .storage
+            .get_block_header(self.config.chain_id, previous_block)
+            .await?
+        {
+            Some(stored) if stored.block_hash == header.parent_hash => {
+                return Ok(ReorgCheck::Canonical(header));
+            }
+            Some(stored) => {
+                warn!(
+                    "[{}] Reorg detected at block {}: parent hash {} does not match stored {}",
+                    self.config.name, next_block, header.parent_hash, stored.block_hash
+                );
+                metrics::counter!("chain_reorgs_total", "chain_id" => self.config.chain_id.to_string())
+                    .increment(1);
+                self.find_common_ancestor(previous_block).await?
+            }
+            None => {
+                // Nothing recorded at all yet (fresh start, or rewound past every stored header)
+                if self
+                    .storage
+                    .get_block_headers_before(self.config.chain_id, previous_block)
+                    .await?
+                    .is_empty()
+                {
+                    return Ok(ReorgCheck::Canonical(header));
+                }
+
+                // Blocks synced since the newest stored header cannot be checked, so they are
+                // re-synced from the newest header still on the canonical chain
+                warn!(
+                    "[{}] No stored header for block {}, re-checking from the stored headers before it",
+                    self.config.name, previous_block
+                );
+                self.find_common_ancestor(previous_block).await?
+            }
+        };
+        let removed = self
+            .storage
+            .rollback_chain_to_block(self.config.chain_id, ancestor)
+            .await?;
+
+        warn!(
+            "[{}] Rolled back to common ancestor {} ({} events removed)",
+            self.config.name,
+            ancestor,
+            removed.len()
+        );
 
-        anyhow::bail!("Unknown ReputationRegistry event")
+        Ok(ReorgCheck::RolledBack(ancestor))
     }
 
-    fn decode_validation_event(
-        &self,
-        log: &Log,
-        block_number: u64,
-        block_timestamp: chrono::DateTime<chrono::Utc>,
-        contract_address: &str,
-        tx_hash: &str,
-        log_index: u32,
-    ) -> Result<Event> {
-        let prim_log = Self::convert_log(log);
-
-        // Try ValidationRequest
-        if let Ok(decoded) = ValidationRegistry::ValidationRequest::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::ValidationRequest,
-                event_data: EventData::ValidationRequest(ValidationRequestData {
-                    validator_address: format!("{:?}", decoded.validatorAddress),
-                    agent_id: decoded.agentId.to_string(),
-                    request_uri: decoded.requestUri.clone(),
-                    request_hash: format!("{:?}", decoded.requestHash),
-                }),
-                created_at: None,
-            });
-        }
+    /// Walk stored headers backwards from `orphaned_block` until one matches the canonical chain
+    async fn find_common_ancestor(&self, orphaned_block: u64) -> Result<u64> {
+        let stored_headers = self
+            .storage
+            .get_block_headers_before(self.config.chain_id, orphaned_block)
+            .await?;
 
-        // Try ValidationResponse
-        if let Ok(decoded) = ValidationRegistry::ValidationResponse::decode_log(&prim_log, true) {
-            return Ok(Event {
-                id: None,
-                chain_id: self.config.chain_id,
-                block_number,
-                block_timestamp,
-                transaction_hash: tx_hash.to_string(),
-                log_index,
-                contract_address: contract_address.to_string(),
-                event_type: EventType::ValidationResponse,
-                event_data: EventData::ValidationResponse(ValidationResponseData {
-                    validator_address: format!("{:?}", decoded.validatorAddress),
-                    agent_id: decoded.agentId.to_string(),
-                    request_hash: format!("{:?}", decoded.requestHash),
-                    response: decoded.response,
-                    response_uri: decoded.responseUri.clone(),
-                    response_hash: format!("{:?}", decoded.responseHash),
-                    tag: format!("{:?}", decoded.tag),
-                }),
-                created_at: None,
-            });
+        let mut oldest_checked = orphaned_block;
+
+        for stored in stored_headers {
+            let canonical = self.fetch_block_header(stored.block_number).await?;
+            if canonical.block_hash == stored.block_hash {
+                return Ok(stored.block_number);
+            }
+            oldest_checked = stored.block_number;
         }
 
-        anyhow::bail!("Unknown ValidationRegistry event")
+        warn!(
+            "[{}] No common ancestor found in tracked headers, rolling back before block {}",
+            self.config.name, oldest_checked
+        );
+        Ok(oldest_checked.saturating_sub(1))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{failed_range_retry_delay, sleep_unless_cancelled, split_block_range};
     use crate::models::{Event, EventData, EventType, RegisteredData};
     use chrono::Utc;
     use tokio::sync::broadcast;
+    use tokio::time::{Duration, Instant};
+    use tokio_util::sync::CancellationToken;
 
     fn create_test_event() -> Event {
         Event {
@@ -775,9 +1194,53 @@ mod tests {
                 owner: "0x5678".to_string(),
             }),
             created_at: None,
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
         }
     }
 
+    #[tokio::test]
+    async fn test_sleep_unless_cancelled() {
+        let shutdown = CancellationToken::new();
+        let start = Instant::now();
+        sleep_unless_cancelled(&shutdown, Duration::from_millis(20)).await;
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // A cancelled token cuts the sleep short
+        shutdown.cancel();
+        let start = Instant::now();
+        sleep_unless_cancelled(&shutdown, Duration::from_secs(60)).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_split_block_range() {
+        assert_eq!(
+            split_block_range(100, 349, 100),
+            vec![(100, 199), (200, 299), (300, 349)]
+        );
+        assert_eq!(split_block_range(5, 5, 1000), vec![(5, 5)]);
+        assert_eq!(
+            split_block_range(10, 12, 1),
+            vec![(10, 10), (11, 11), (12, 12)]
+        );
+        assert!(split_block_range(10, 9, 100).is_empty());
+    }
+
+    #[test]
+    fn test_failed_range_retry_delay() {
+        assert_eq!(failed_range_retry_delay(1), Duration::from_secs(30));
+        assert_eq!(failed_range_retry_delay(2), Duration::from_secs(60));
+        assert_eq!(failed_range_retry_delay(4), Duration::from_secs(240));
+        assert_eq!(failed_range_retry_delay(10), Duration::from_secs(3600));
+        assert_eq!(
+            failed_range_retry_delay(u32::MAX),
+            Duration::from_secs(3600)
+        );
+    }
+
     #[test]
     fn test_event_broadcast_channel_creation() {
         // Test that broadcast channel can be created and used for events
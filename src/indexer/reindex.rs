@@ -0,0 +1,98 @@
+use super::decoder::{ChainDecoders, LogContext};
+use crate::models::Event;
+use crate::storage::Storage;
+use anyhow::Result;
+use tracing::{info, warn};
+
+/// Number of events loaded and updated per batch
+const REDECODE_BATCH_SIZE: i64 = 1000;
+
+/// Outcome of rebuilding `event_data` from stored raw logs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedecodeSummary {
+    /// Events scanned
+    pub scanned: usize,
+    /// Events whose decoded type, data or spec version changed and were rewritten
+    pub updated: usize,
+    /// Events indexed before raw logs were stored
+    pub missing_raw: usize,
+    /// Events whose raw log no longer decodes (or whose contract role is unknown)
+    pub failed: usize,
+}
+
+/// Rebuild `event_data` of stored events from their raw topics and data
+/// Runs entirely against the database, without any RPC access
+pub async fn redecode_events(
+    storage: &Storage,
+    decoders: &ChainDecoders,
+    chain_id: Option<u64>,
+) -> Result<RedecodeSummary> {
+    let mut summary = RedecodeSummary::default();
+    let mut after_id = 0;
+
+    loop {
+        let events = storage
+            .get_events_after(chain_id, after_id, REDECODE_BATCH_SIZE)
+            .await?;
+        let Some(last) = events.last() else {
+            break;
+        };
+        after_id = last.id.unwrap_or(after_id);
+        summary.scanned += events.len();
+
+        let mut updates: Vec<Event> = Vec::new();
+
+        for event in &events {
+            let (Some(id), Some(raw)) = (event.id, &event.raw) else {
+                summary.missing_raw += 1;
+                continue;
+            };
+
+            let registry = decoders.get(event.chain_id);
+            let Some((role, abi_version)) = registry.event_deployment(event) else {
+                warn!(
+                    "Unknown contract role for event {} ({} at {})",
+                    id,
+                    event.event_type.as_str(),
+                    event.contract_address
+                );
+                summary.failed += 1;
+                continue;
+            };
+
+            let redecoded =
+                match registry.decode_raw(&role, &abi_version, &LogContext::from_event(event), raw)
+                {
+                    Ok(redecoded) => redecoded,
+                    Err(e) => {
+                        warn!(
+                            "Failed to re-decode event {} (tx {}, log {}): {}",
+                            id, event.transaction_hash, event.log_index, e
+                        );
+                        summary.failed += 1;
+                        continue;
+                    }
+                };
+
+            let changed = redecoded.event_type != event.event_type
+                || redecoded.spec_version != event.spec_version
+                || serde_json::to_value(&redecoded.event_data)?
+                    != serde_json::to_value(&event.event_data)?;
+            if changed {
+                updates.push(Event {
+                    id: Some(id),
+                    ..redecoded
+                });
+            }
+        }
+
+        summary.updated += storage.update_event_data(&updates).await? as usize;
+
+        info!(
+            "Re-decoded {} events ({} updated so far)",
+            summary.scanned, summary.updated
+        );
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,142 @@
+use alloy::providers::{Provider, ProviderBuilder, WsConnect};
+use anyhow::{anyhow, Context, Result};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{debug, info, warn};
+
+/// Delay before reconnecting after a head subscription dropped or failed to connect
+const RECONNECT_DELAY: Duration = Duration::from_secs(15);
+
+/// A subscription that delivers no new head for this long is treated as dropped
+const STALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Latest head reported by a WebSocket `newHeads` subscription
+/// A background task keeps the subscription open, rotating through the configured endpoints
+/// when it drops. While it is down `latest()` is None and the indexer polls instead.
+pub struct HeadSubscription {
+    rx: watch::Receiver<Option<u64>>,
+}
+
+impl HeadSubscription {
+    /// Start the subscription task (None without WebSocket endpoints)
+    /// The task stops once the HeadSubscription is dropped
+    pub fn spawn(chain_name: &str, chain_id: u64, ws_urls: Vec<String>) -> Option<Self> {
+        if ws_urls.is_empty() {
+            return None;
+        }
+
+        let (tx, rx) = watch::channel(None);
+        tokio::spawn(run(chain_name.to_string(), chain_id, ws_urls, tx));
+        Some(Self { rx })
+    }
+
+    /// Latest head number, None while disconnected
+    pub fn latest(&self) -> Option<u64> {
+        *self.rx.borrow()
+    }
+
+    /// Wait until a head at or above `block` was seen, for at most `max_wait`
+    pub async fn wait_for_head(&mut self, block: u64, max_wait: Duration) {
+        let _ = timeout(
+            max_wait,
+            self.rx
+                .wait_for(|head| head.is_some_and(|head| head >= block)),
+        )
+        .await;
+    }
+}
+
+/// Keep a subscription open until every receiver is gone
+async fn run(
+    chain_name: String,
+    chain_id: u64,
+    ws_urls: Vec<String>,
+    tx: watch::Sender<Option<u64>>,
+) {
+    for ws_url in ws_urls.iter().cycle() {
+        let result = subscribe(&chain_name, chain_id, ws_url, &tx).await;
+        if tx.is_closed() {
+            break;
+        }
+
+        if let Err(e) = result {
+            warn!(
+                "[{}] Head subscription on {} dropped, polling until it reconnects: {:#}",
+                chain_name, ws_url, e
+            );
+        }
+        tx.send_replace(None);
+        metrics::gauge!("ws_subscription_connected", "chain_id" => chain_id.to_string()).set(0.0);
+
+        sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Forward the heads of one subscription until it drops, stalls or every receiver is gone
+async fn subscribe(
+    chain_name: &str,
+    chain_id: u64,
+    ws_url: &str,
+    tx: &watch::Sender<Option<u64>>,
+) -> Result<()> {
+    let provider = ProviderBuilder::new()
+        .on_ws(WsConnect::new(ws_url))
+        .await
+        .context("Failed to connect")?;
+    let mut subscription = provider
+        .subscribe_blocks()
+        .await
+        .context("Failed to subscribe to newHeads")?;
+
+    info!("[{}] Subscribed to new heads on {}", chain_name, ws_url);
+    metrics::gauge!("ws_subscription_connected", "chain_id" => chain_id.to_string()).set(1.0);
+
+    loop {
+        let header = tokio::select! {
+            _ = tx.closed() => return Ok(()),
+            header = timeout(STALL_TIMEOUT, subscription.recv()) => header,
+        };
+
+        match header {
+            Ok(Ok(header)) => {
+                debug!("[{}] New head {}", chain_name, header.number);
+                tx.send_replace(Some(header.number));
+            }
+            Ok(Err(RecvError::Lagged(skipped))) => {
+                debug!("[{}] Skipped {} heads", chain_name, skipped);
+            }
+            Ok(Err(RecvError::Closed)) => return Err(anyhow!("Subscription closed")),
+            Err(_) => return Err(anyhow!("No new head for {:?}", STALL_TIMEOUT)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_for_head() {
+        let (tx, rx) = watch::channel(None);
+        let mut heads = HeadSubscription { rx };
+        assert_eq!(heads.latest(), None);
+
+        // Disconnected: waits the full interval (polling fallback)
+        let start = tokio::time::Instant::now();
+        heads.wait_for_head(10, Duration::from_millis(50)).await;
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // A head at the target wakes the waiter immediately
+        tx.send_replace(Some(10));
+        let start = tokio::time::Instant::now();
+        heads.wait_for_head(10, Duration::from_secs(10)).await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(heads.latest(), Some(10));
+    }
+
+    #[test]
+    fn test_spawn_without_endpoints() {
+        assert!(HeadSubscription::spawn("Test", 1, vec![]).is_none());
+    }
+}
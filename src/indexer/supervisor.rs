@@ -1,13 +1,28 @@
 use crate::config::IndexerConfig;
-use crate::indexer::Indexer;
-use crate::models::Event;
+use crate::indexer::control::{IndexerCommand, IndexerControl};
+use crate::indexer::{sleep_unless_cancelled, Indexer};
+use crate::rpc::{verify_providers, ProviderManager, ProviderProber};
 use crate::stats::StatsTracker;
-use crate::storage::Storage;
+use crate::storage::{ChainLock, Storage};
 use anyhow::Result;
-use tokio::sync::broadcast;
-use tokio::time::{sleep, Duration};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{sleep, timeout, Duration};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
+/// Interval at which a standby instance tries to take over a chain's indexing lock
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Interval at which the lock holder checks that its lock connection is still alive
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum time a lock connection check may take before the lock is considered lost
+const LOCK_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Interval at which the lock holder takes admin commands queued in the database
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Restart policy for indexer supervisor
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -30,6 +45,10 @@ pub enum ChainStatus {
     CatchingUp,
     Stalled,
     Failed,
+    /// Indexer shut down gracefully
+    Stopped,
+    /// Indexer paused by an admin command
+    Paused,
 }
 
 impl ChainStatus {
@@ -40,6 +59,8 @@ impl ChainStatus {
             ChainStatus::CatchingUp => "catching_up",
             ChainStatus::Stalled => "stalled",
             ChainStatus::Failed => "failed",
+            ChainStatus::Stopped => "stopped",
+            ChainStatus::Paused => "paused",
         }
     }
 }
@@ -47,34 +68,213 @@ impl ChainStatus {
 /// Supervisor that manages a single indexer with auto-restart capability
 pub struct IndexerSupervisor {
     config: IndexerConfig,
+    /// Shared by every indexer run, so provider lists can be replaced in place
+    provider_manager: Arc<ProviderManager>,
     storage: Storage,
-    event_tx: broadcast::Sender<Event>,
     restart_policy: RestartPolicy,
     stats_tracker: StatsTracker,
+    control: IndexerControl,
+    commands: Mutex<mpsc::Receiver<IndexerCommand>>,
+    /// Recorded as the holder of the chain's indexing lock
+    instance_id: String,
 }
 
 impl IndexerSupervisor {
     pub fn new(
         config: IndexerConfig,
         storage: Storage,
-        event_tx: broadcast::Sender<Event>,
         restart_policy: RestartPolicy,
         stats_tracker: StatsTracker,
-    ) -> Self {
-        Self {
+        instance_id: String,
+    ) -> Result<Self> {
+        let provider_manager = Arc::new(ProviderManager::new(
+            config.rpc_providers.clone(),
+            config.provider_strategy,
+            config.chain_id,
+            config.name.clone(),
+        )?);
+        let (control, commands) = IndexerControl::channel();
+        Ok(Self {
             config,
+            provider_manager,
             storage,
-            event_tx,
             restart_policy,
             stats_tracker,
-        }
+            control,
+            commands: Mutex::new(commands),
+            instance_id,
+        })
+    }
+
+    /// Provider manager used by this supervisor's indexers
+    pub fn provider_manager(&self) -> Arc<ProviderManager> {
+        self.provider_manager.clone()
+    }
+
+    /// Handle for sending admin commands to this supervisor
+    pub fn control(&self) -> IndexerControl {
+        self.control.clone()
     }
 
     /// Start the supervisor loop
-    pub async fn start(&self) -> Result<()> {
-        let mut retry_count = 0;
+    /// Only the instance holding the chain's indexing lock runs the indexer; the others stand by
+    /// and take over once the lock is released (or its holder dies)
+    /// Once `shutdown` is cancelled the indexer is stopped, restarts are skipped and the chain is
+    /// marked as stopped
+    pub async fn start(&self, shutdown: CancellationToken) -> Result<()> {
+        let mut commands = self.commands.lock().await;
 
         loop {
+            let Some(lock) = self.acquire_lock(&shutdown, &mut commands).await else {
+                return Ok(());
+            };
+
+            // Cancelled when the lock is lost, so the indexer stops before another instance
+            // takes over
+            let leadership = shutdown.child_token();
+            let holder = tokio::spawn(hold_lock(
+                lock,
+                leadership.clone(),
+                self.config.name.clone(),
+            ));
+
+            // Never index from a provider serving another chain
+            let verification = verify_providers(
+                &self.provider_manager,
+                &self.config.name,
+                self.config.provider_health.as_ref(),
+            );
+            tokio::select! {
+                _ = leadership.cancelled() => {}
+                result = verification => {
+                    if let Err(e) = result {
+                        error!("[{}] {}", self.config.name, e);
+                    }
+                }
+            }
+
+            // Providers are only probed by the instance using them
+            let prober = self.config.provider_health.map(|thresholds| {
+                let prober = ProviderProber::new(
+                    self.config.chain_id,
+                    self.config.name.clone(),
+                    self.provider_manager.clone(),
+                    thresholds,
+                );
+                tokio::spawn(prober.run(leadership.clone()))
+            });
+
+            // Admin commands are queued by any instance and applied by the lock holder
+            let relay = tokio::spawn(relay_commands(
+                self.storage.clone(),
+                self.config.chain_id,
+                self.control.clone(),
+                leadership.clone(),
+                self.config.name.clone(),
+            ));
+
+            self.control.set_leader(true);
+            let result = self.lead(&shutdown, &leadership, &mut commands).await;
+            let lost = leadership.is_cancelled() && !shutdown.is_cancelled();
+            self.control.set_leader(false);
+
+            leadership.cancel();
+            if let Err(e) = holder.await {
+                warn!("[{}] Lock holder task panicked: {}", self.config.name, e);
+            }
+            if let Err(e) = relay.await {
+                warn!("[{}] Admin command relay panicked: {}", self.config.name, e);
+            }
+            if let Some(prober) = prober {
+                if let Err(e) = prober.await {
+                    warn!("[{}] Provider prober panicked: {}", self.config.name, e);
+                }
+            }
+
+            if !lost {
+                return result;
+            }
+            warn!(
+                "[{}] Lost the indexing lock, switching to standby",
+                self.config.name
+            );
+        }
+    }
+
+    /// Wait until this instance holds the chain's indexing lock (None on shutdown)
+    /// Commands relayed before the lock was lost cannot be applied on standby and are dropped
+    async fn acquire_lock(
+        &self,
+        shutdown: &CancellationToken,
+        commands: &mut mpsc::Receiver<IndexerCommand>,
+    ) -> Option<ChainLock> {
+        let mut standby = false;
+
+        loop {
+            match self
+                .storage
+                .try_acquire_chain_lock(self.config.chain_id, &self.instance_id)
+                .await
+            {
+                Ok(Some(lock)) => {
+                    info!(
+                        "[{}] Acquired indexing lock as {}",
+                        self.config.name, self.instance_id
+                    );
+                    return Some(lock);
+                }
+                Ok(None) if !standby => {
+                    info!(
+                        "[{}] Another instance holds the indexing lock, standing by",
+                        self.config.name
+                    );
+                    standby = true;
+                }
+                Ok(None) => {}
+                Err(e) => warn!(
+                    "[{}] Failed to acquire indexing lock: {}",
+                    self.config.name, e
+                ),
+            }
+
+            let retry = sleep(LOCK_RETRY_INTERVAL);
+            tokio::pin!(retry);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => return None,
+                    _ = &mut retry => break,
+                    Some(command) = commands.recv() => warn!(
+                        "[{}] Ignoring admin command on standby instance: {}",
+                        self.config.name, command
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Run the indexer with restarts and admin commands while this instance holds the lock
+    /// `leadership` is cancelled on shutdown and when the lock is lost
+    async fn lead(
+        &self,
+        shutdown: &CancellationToken,
+        leadership: &CancellationToken,
+        commands: &mut mpsc::Receiver<IndexerCommand>,
+    ) -> Result<()> {
+        let mut retry_count = 0;
+        let mut paused = false;
+
+        while !leadership.is_cancelled() {
+            if paused {
+                // A paused chain only wakes up for commands, shutdown or the loss of the lock
+                tokio::select! {
+                    _ = leadership.cancelled() => break,
+                    Some(command) = commands.recv() => {
+                        paused = self.apply_command(command, true, leadership).await;
+                    }
+                }
+                continue;
+            }
+
             info!(
                 "[{}] Starting indexer for chain_id {}",
                 self.config.name, self.config.chain_id
@@ -95,8 +295,8 @@ impl IndexerSupervisor {
             // Create and start indexer
             let indexer = match Indexer::new(
                 self.config.clone(),
+                self.provider_manager.clone(),
                 self.storage.clone(),
-                self.event_tx.clone(),
                 self.stats_tracker.clone(),
             )
             .await
@@ -116,7 +316,60 @@ impl IndexerSupervisor {
             };
 
             // Run indexer in isolated task
-            let result = tokio::spawn(async move { indexer.start().await }).await;
+            // It gets its own token so admin commands can stop it without shutting down
+            let run = leadership.child_token();
+            let indexer_run = run.clone();
+            let mut task = tokio::spawn(async move { indexer.start(indexer_run).await });
+
+            let mut stop_command = None;
+            let result = loop {
+                tokio::select! {
+                    result = &mut task => break result,
+                    Some(command) = commands.recv(), if stop_command.is_none() => {
+                        if command.stops_indexer() {
+                            info!("[{}] Stopping indexer to {}", self.config.name, command);
+                            run.cancel();
+                            stop_command = Some(command);
+                        } else {
+                            self.apply_command(command, false, leadership).await;
+                        }
+                    }
+                }
+            };
+
+            if leadership.is_cancelled() {
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        warn!(
+                            "[{}] Indexer failed while stopping: {}",
+                            self.config.name, e
+                        )
+                    }
+                    Err(e) => warn!(
+                        "[{}] Indexer task panicked while stopping: {}",
+                        self.config.name, e
+                    ),
+                }
+                break;
+            }
+
+            if let Some(command) = stop_command {
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!(
+                        "[{}] Indexer failed while stopping: {}",
+                        self.config.name, e
+                    ),
+                    Err(e) => warn!(
+                        "[{}] Indexer task panicked while stopping: {}",
+                        self.config.name, e
+                    ),
+                }
+                retry_count = 0;
+                paused = self.apply_command(command, false, leadership).await;
+                continue;
+            }
 
             match result {
                 Ok(Ok(())) => {
@@ -125,7 +378,7 @@ impl IndexerSupervisor {
                     self.storage
                         .update_chain_status(self.config.chain_id, ChainStatus::Active, None)
                         .await?;
-                    break;
+                    return Ok(());
                 }
                 Ok(Err(e)) => {
                     // Indexer returned an error
@@ -138,12 +391,12 @@ impl IndexerSupervisor {
                                 "[{}] Restarting immediately (Always policy)",
                                 self.config.name
                             );
-                            sleep(Duration::from_secs(1)).await;
+                            sleep_unless_cancelled(leadership, Duration::from_secs(1)).await;
                             continue;
                         }
                         RestartPolicy::OnFailure => {
                             warn!("[{}] Restarting on failure", self.config.name);
-                            sleep(Duration::from_secs(1)).await;
+                            sleep_unless_cancelled(leadership, Duration::from_secs(1)).await;
                             continue;
                         }
                         RestartPolicy::Exponential {
@@ -184,7 +437,7 @@ impl IndexerSupervisor {
                                 )
                                 .await?;
 
-                            sleep(delay).await;
+                            sleep_unless_cancelled(leadership, delay).await;
                         }
                     }
                 }
@@ -205,14 +458,111 @@ impl IndexerSupervisor {
                         "[{}] Restarting after panic in 1 second...",
                         self.config.name
                     );
-                    sleep(Duration::from_secs(1)).await;
+                    sleep_unless_cancelled(leadership, Duration::from_secs(1)).await;
                 }
             }
         }
 
+        info!("[{}] Indexer stopped", self.config.name);
+        // After losing the lock the chain's status belongs to the new holder
+        if shutdown.is_cancelled() {
+            self.storage
+                .update_chain_status(self.config.chain_id, ChainStatus::Stopped, None)
+                .await?;
+        }
         Ok(())
     }
 
+    /// Apply an admin command; commands that stop the indexer are applied once it has stopped
+    /// Returns whether the chain is paused afterwards
+    async fn apply_command(
+        &self,
+        command: IndexerCommand,
+        paused: bool,
+        shutdown: &CancellationToken,
+    ) -> bool {
+        info!("[{}] Applying admin command: {}", self.config.name, command);
+
+        match command {
+            IndexerCommand::Pause => {
+                if let Err(e) = self
+                    .storage
+                    .update_chain_status(self.config.chain_id, ChainStatus::Paused, None)
+                    .await
+                {
+                    warn!(
+                        "[{}] Failed to update chain status: {}",
+                        self.config.name, e
+                    );
+                }
+                true
+            }
+            IndexerCommand::Resume | IndexerCommand::Restart => false,
+            IndexerCommand::Rewind { block } => {
+                match self.rewind(block).await {
+                    Ok(true) => {}
+                    Ok(false) => error!(
+                        "[{}] Not rewinding to block {}: it is ahead of the last synced block",
+                        self.config.name, block
+                    ),
+                    Err(e) => error!(
+                        "[{}] Failed to rewind to block {}: {}",
+                        self.config.name, block, e
+                    ),
+                }
+                paused
+            }
+            IndexerCommand::Backfill {
+                from_block,
+                to_block,
+            } => {
+                self.spawn_backfill(from_block, to_block, shutdown.clone());
+                paused
+            }
+        }
+    }
+
+    /// Rewind the sync cursor with an indexer of its own (the live one is stopped by then)
+    async fn rewind(&self, block: u64) -> Result<bool> {
+        let indexer = Indexer::new(
+            self.config.clone(),
+            self.provider_manager.clone(),
+            self.storage.clone(),
+            self.stats_tracker.clone(),
+        )
+        .await?;
+        indexer.rewind(block).await
+    }
+
+    /// Re-index a block range in its own task, alongside the live indexer
+    fn spawn_backfill(&self, from_block: u64, to_block: u64, shutdown: CancellationToken) {
+        let name = self.config.name.clone();
+        let indexer = Indexer::new(
+            self.config.clone(),
+            self.provider_manager.clone(),
+            self.storage.clone(),
+            self.stats_tracker.clone(),
+        );
+
+        tokio::spawn(async move {
+            let result = match indexer.await {
+                Ok(indexer) => indexer.backfill(from_block, to_block, &shutdown).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(events) => info!(
+                    "[{}] Backfill of blocks {}-{} finished: {} events",
+                    name, from_block, to_block, events
+                ),
+                Err(e) => error!(
+                    "[{}] Backfill of blocks {}-{} failed: {}",
+                    name, from_block, to_block, e
+                ),
+            }
+        });
+    }
+
     /// Calculate exponential backoff delay
     fn calculate_backoff(retry: u32, base_delay_ms: u64, max_delay_ms: u64) -> Duration {
         let multiplier = 2u64.pow(retry);
@@ -221,6 +571,63 @@ impl IndexerSupervisor {
     }
 }
 
+/// Keep the indexing lock until `leadership` is cancelled, then release it
+/// Cancels `leadership` itself when the lock connection dies (the lock is gone with it)
+async fn hold_lock(mut lock: ChainLock, leadership: CancellationToken, name: String) {
+    loop {
+        tokio::select! {
+            _ = leadership.cancelled() => break,
+            _ = sleep(LOCK_CHECK_INTERVAL) => {
+                if !matches!(timeout(LOCK_CHECK_TIMEOUT, lock.is_held()).await, Ok(true)) {
+                    error!("[{}] Indexing lock connection lost, stopping indexer", name);
+                    leadership.cancel();
+                    return;
+                }
+            }
+        }
+    }
+
+    lock.release().await;
+}
+
+/// Forward the chain's admin commands queued in the database to its supervisor until
+/// `leadership` is cancelled
+async fn relay_commands(
+    storage: Storage,
+    chain_id: u64,
+    control: IndexerControl,
+    leadership: CancellationToken,
+    name: String,
+) {
+    loop {
+        tokio::select! {
+            _ = leadership.cancelled() => return,
+            _ = sleep(COMMAND_POLL_INTERVAL) => {}
+        }
+
+        let commands = match storage.take_indexer_commands(chain_id).await {
+            Ok(commands) => commands,
+            Err(e) => {
+                warn!("[{}] Failed to take admin commands: {}", name, e);
+                continue;
+            }
+        };
+        for command in commands {
+            tokio::select! {
+                _ = leadership.cancelled() => {
+                    warn!("[{}] Lost leadership before applying {}", name, command);
+                    return;
+                }
+                result = control.send(command.clone()) => {
+                    if result.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -253,5 +660,7 @@ mod tests {
         assert_eq!(ChainStatus::CatchingUp.as_str(), "catching_up");
         assert_eq!(ChainStatus::Stalled.as_str(), "stalled");
         assert_eq!(ChainStatus::Failed.as_str(), "failed");
+        assert_eq!(ChainStatus::Stopped.as_str(), "stopped");
+        assert_eq!(ChainStatus::Paused.as_str(), "paused");
     }
 }
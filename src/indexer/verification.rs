@@ -0,0 +1,492 @@
+use super::Indexer;
+use crate::config::{redact_rpc_url, LogVerificationConfig};
+use crate::rpc::ProviderError;
+use alloy::primitives::{keccak256, Address, Bloom, BloomInput, B256};
+use alloy::providers::{Provider, ProviderBuilder, RootProvider};
+use alloy::rpc::types::{BlockTransactionsKind, Filter, Log};
+use alloy::transports::http::{Client, Http};
+use anyhow::{anyhow, Context, Result};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use std::fmt;
+use tokio::time::{timeout, Duration, Instant};
+use tracing::{info, warn};
+
+/// Timeout of an eth_getLogs request cross-checking another provider's answer
+const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest range whose logs blooms are checked block by block; longer ranges (e.g. while catching
+/// up) are verified without checking, which takes one eth_getLogs instead of a header per block
+const MAX_BLOOM_SCAN_BLOCKS: u64 = 32;
+
+/// Log count and content hash of an eth_getLogs answer, independent of the log order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogFingerprint {
+    pub count: usize,
+    pub hash: B256,
+}
+
+impl LogFingerprint {
+    pub fn of(logs: &[Log]) -> Self {
+        let mut keys: Vec<Vec<u8>> = logs.iter().map(log_key).collect();
+        keys.sort();
+        Self {
+            count: logs.len(),
+            hash: keccak256(keys.concat()),
+        }
+    }
+}
+
+impl fmt::Display for LogFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} logs (hash {})", self.count, self.hash)
+    }
+}
+
+/// Fields identifying a log: its position, emitting contract, topics and data
+/// Provider specific extras such as `blockTimestamp` are left out
+fn log_key(log: &Log) -> Vec<u8> {
+    let mut key = Vec::new();
+    key.extend(log.block_number.unwrap_or_default().to_be_bytes());
+    key.extend(log.log_index.unwrap_or_default().to_be_bytes());
+    key.extend(log.block_hash.unwrap_or_default().as_slice());
+    key.extend(log.transaction_hash.unwrap_or_default().as_slice());
+    key.extend(log.address().as_slice());
+    key.push(log.topics().len() as u8);
+    for topic in log.topics() {
+        key.extend(topic.as_slice());
+    }
+    key.extend((log.data().data.len() as u64).to_be_bytes());
+    key.extend(log.data().data.as_ref());
+    key
+}
+
+/// Whether a range is picked at random for a cross-check, `sample` being uniformly random in [0, 1)
+pub fn is_sampled(config: &LogVerificationConfig, sample: f64) -> bool {
+    sample * 100.0 < f64::from(config.sample_percent)
+}
+
+/// Whether a block's logs bloom may contain logs of any of `addresses`
+/// Blooms have false positives but no false negatives
+pub fn bloom_matches(bloom: &Bloom, addresses: &[Address]) -> bool {
+    addresses
+        .iter()
+        .any(|address| bloom.contains_input(BloomInput::Raw(address.as_slice())))
+}
+
+/// Answer a quorum of providers agreed on
+#[derive(Debug)]
+pub struct QuorumLogs {
+    pub logs: Vec<Log>,
+    pub agreeing: Vec<String>,
+    /// Providers that answered something else
+    pub disagreeing: Vec<String>,
+}
+
+/// The answer returned by at least `quorum` providers and by more providers than any other answer
+pub fn quorum_logs(answers: Vec<(String, Vec<Log>)>, quorum: usize) -> Option<QuorumLogs> {
+    let mut groups: Vec<(LogFingerprint, Vec<String>, Vec<Log>)> = Vec::new();
+    for (url, logs) in answers {
+        let fingerprint = LogFingerprint::of(&logs);
+        match groups.iter_mut().find(|(f, _, _)| *f == fingerprint) {
+            Some((_, urls, _)) => urls.push(url),
+            None => groups.push((fingerprint, vec![url], logs)),
+        }
+    }
+    groups.sort_by_key(|(_, urls, _)| std::cmp::Reverse(urls.len()));
+
+    let mut groups = groups.into_iter();
+    let (_, agreeing, logs) = groups.next()?;
+    let disagreeing: Vec<(LogFingerprint, Vec<String>, Vec<Log>)> = groups.collect();
+    if agreeing.len() < quorum
+        || disagreeing
+            .first()
+            .is_some_and(|(_, urls, _)| urls.len() == agreeing.len())
+    {
+        return None;
+    }
+
+    Some(QuorumLogs {
+        logs,
+        agreeing,
+        disagreeing: disagreeing
+            .into_iter()
+            .flat_map(|(_, urls, _)| urls)
+            .collect(),
+    })
+}
+
+impl Indexer {
+    /// Cross-check the logs `primary` returned for `filter` if the range is picked for verification
+    /// They are compared with the answer of a second provider; on a mismatch every usable provider
+    /// is asked and the answer a quorum agrees on is returned. Without a quorum the range fails,
+    /// so it is retried instead of committed. When no other provider answers, the range is
+    /// left unverified
+    pub(super) async fn verify_logs(
+        &self,
+        filter: &Filter,
+        from: u64,
+        to: u64,
+        primary: String,
+        logs: Vec<Log>,
+    ) -> Result<Vec<Log>> {
+        let Some(config) = &self.config.log_verification else {
+            return Ok(logs);
+        };
+        // A range with logs is recognized from the block headers, so an answer missing all of
+        // them is still cross-checked
+        let picked = is_sampled(config, rand::random())
+            || (config.blocks_with_logs
+                && (!logs.is_empty() || self.blooms_show_logs(&primary, from, to).await));
+        if !picked {
+            return Ok(logs);
+        }
+
+        let chain_id = self.config.chain_id.to_string();
+        let expected = LogFingerprint::of(&logs);
+        let mut others = self
+            .provider_manager
+            .other_usable_providers(&primary)
+            .await
+            .into_iter();
+
+        // Compare with the first other provider that answers
+        let mut answers = vec![(primary, logs)];
+        for url in others.by_ref() {
+            match self.query_logs(&url, filter).await {
+                Ok(logs) => {
+                    answers.push((url, logs));
+                    break;
+                }
+                Err(e) => warn!(
+                    "[{}] Failed to verify logs of blocks {}-{} on {}: {:#}",
+                    self.config.name,
+                    from,
+                    to,
+                    redact_rpc_url(&url),
+                    e
+                ),
+            }
+        }
+
+        let Some(found) = answers.get(1).map(|(_, logs)| LogFingerprint::of(logs)) else {
+            warn!(
+                "[{}] No other provider answered, logs of blocks {}-{} are not verified",
+                self.config.name, from, to
+            );
+            metrics::counter!("log_verifications_skipped_total", "chain_id" => chain_id)
+                .increment(1);
+            return Ok(answers.swap_remove(0).1);
+        };
+
+        metrics::counter!("log_verifications_total", "chain_id" => chain_id.clone()).increment(1);
+        if found == expected {
+            return Ok(answers.swap_remove(0).1);
+        }
+
+        warn!(
+            "[{}] Providers disagree on the logs of blocks {}-{}: {} returned {}, {} returned {}",
+            self.config.name,
+            from,
+            to,
+            redact_rpc_url(&answers[0].0),
+            expected,
+            redact_rpc_url(&answers[1].0),
+            found
+        );
+        metrics::counter!("log_verification_mismatches_total", "chain_id" => chain_id.clone())
+            .increment(1);
+
+        let remaining: Vec<String> = others.collect();
+        let results = join_all(remaining.iter().map(|url| self.query_logs(url, filter))).await;
+        for (url, result) in remaining.into_iter().zip(results) {
+            match result {
+                Ok(logs) => answers.push((url, logs)),
+                Err(e) => warn!(
+                    "[{}] Failed to fetch logs of blocks {}-{} from {}: {:#}",
+                    self.config.name,
+                    from,
+                    to,
+                    redact_rpc_url(&url),
+                    e
+                ),
+            }
+        }
+
+        let answered = answers.len();
+        let Some(quorum) = quorum_logs(answers, config.quorum) else {
+            metrics::counter!("log_verification_quorum_failures_total", "chain_id" => chain_id)
+                .increment(1);
+            return Err(anyhow!(
+                "No quorum of {} providers on the logs of blocks {}-{} ({} answered)",
+                config.quorum,
+                from,
+                to,
+                answered
+            ));
+        };
+
+        for url in &quorum.disagreeing {
+            metrics::counter!(
+                "log_verification_faulty_answers_total",
+                "chain_id" => chain_id.clone(),
+                "provider" => redact_rpc_url(url)
+            )
+            .increment(1);
+            self.provider_manager
+                .record_request(
+                    url,
+                    Err(ProviderError::data_missing(format!(
+                        "get_logs of blocks {}-{} disagrees with {} providers",
+                        from,
+                        to,
+                        quorum.agreeing.len()
+                    ))),
+                )
+                .await;
+        }
+
+        info!(
+            "[{}] {} of {} providers agree on {} in blocks {}-{}",
+            self.config.name,
+            quorum.agreeing.len(),
+            answered,
+            LogFingerprint::of(&quorum.logs),
+            from,
+            to
+        );
+        Ok(quorum.logs)
+    }
+
+    /// Whether the logs bloom of any block in `from..=to` matches a registry address
+    /// The headers are fetched from another usable provider than `primary`, so the scan does not
+    /// use up the indexing provider's request budget; without one there is nothing to cross-check
+    /// with anyway. Ranges longer than `MAX_BLOOM_SCAN_BLOCKS`, or whose headers cannot be
+    /// fetched, are assumed to have logs.
+    async fn blooms_show_logs(&self, primary: &str, from: u64, to: u64) -> bool {
+        let Some(url) = self
+            .provider_manager
+            .other_usable_providers(primary)
+            .await
+            .into_iter()
+            .next()
+        else {
+            return false;
+        };
+        if to - from >= MAX_BLOOM_SCAN_BLOCKS {
+            return true;
+        }
+
+        let addresses = self.config.decoders.addresses_active_in(from, to);
+        let Ok(parsed) = url.parse() else {
+            return true;
+        };
+        let provider = ProviderBuilder::new().on_http(parsed);
+
+        let mut blooms = stream::iter(from..=to)
+            .map(|block| self.query_logs_bloom(&provider, &url, block))
+            .buffered(self.config.max_parallel_blocks);
+        while let Some(result) = blooms.next().await {
+            match result {
+                Ok(bloom) if !bloom_matches(&bloom, &addresses) => {}
+                Ok(_) => return true,
+                Err(e) => {
+                    warn!(
+                        "[{}] Failed to check the logs bloom of blocks {}-{} on {}: {:#}",
+                        self.config.name,
+                        from,
+                        to,
+                        redact_rpc_url(&url),
+                        e
+                    );
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Fetch the logs bloom of a block, recording the outcome in the provider manager
+    async fn query_logs_bloom(
+        &self,
+        provider: &RootProvider<Http<Client>>,
+        url: &str,
+        block: u64,
+    ) -> Result<Bloom> {
+        let started = Instant::now();
+        let request = provider.get_block_by_number(block.into(), BlockTransactionsKind::Hashes);
+        let result = match timeout(VERIFICATION_TIMEOUT, request).await {
+            Ok(Ok(Some(block))) => Ok(block.header.logs_bloom),
+            Ok(Ok(None)) => Err(ProviderError::data_missing(format!(
+                "Block {} not found",
+                block
+            ))),
+            Ok(Err(e)) => Err(ProviderError::from_transport(
+                "get_block_by_number failed",
+                &e,
+            )),
+            Err(_) => Err(ProviderError::timeout("get_block_by_number timeout")),
+        };
+
+        match result {
+            Ok(bloom) => {
+                self.provider_manager
+                    .record_request(url, Ok(started.elapsed()))
+                    .await;
+                Ok(bloom)
+            }
+            Err(e) => {
+                let message = e.to_string();
+                self.provider_manager.record_request(url, Err(e)).await;
+                Err(anyhow!(message))
+            }
+        }
+    }
+
+    /// Fetch logs from a given provider, recording the outcome in the provider manager
+    async fn query_logs(&self, url: &str, filter: &Filter) -> Result<Vec<Log>> {
+        let provider = ProviderBuilder::new().on_http(url.parse().context("Invalid RPC URL")?);
+
+        let started = Instant::now();
+        let result = match timeout(VERIFICATION_TIMEOUT, provider.get_logs(filter)).await {
+            Ok(Ok(logs)) => Ok(logs),
+            Ok(Err(e)) => Err(ProviderError::from_transport("get_logs failed", &e)),
+            Err(_) => Err(ProviderError::timeout("get_logs timeout")),
+        };
+
+        match result {
+            Ok(logs) => {
+                self.provider_manager
+                    .record_request(url, Ok(started.elapsed()))
+                    .await;
+                Ok(logs)
+            }
+            Err(e) => {
+                let message = e.to_string();
+                self.provider_manager.record_request(url, Err(e)).await;
+                Err(anyhow!(message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::{address, b256, Bytes, LogData};
+
+    fn log(block_number: u64, log_index: u64, data: &'static [u8]) -> Log {
+        Log {
+            inner: alloy::primitives::Log {
+                address: address!("8004a6090Cd10A7288092483047B097295Fb8847"),
+                data: LogData::new_unchecked(
+                    vec![b256!(
+                        "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+                    )],
+                    Bytes::from_static(data),
+                ),
+            },
+            block_number: Some(block_number),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn urls(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_log_fingerprint() {
+        let logs = vec![log(10, 0, b"a"), log(10, 1, b"b"), log(12, 0, b"c")];
+        let fingerprint = LogFingerprint::of(&logs);
+        assert_eq!(fingerprint.count, 3);
+
+        // Order and provider specific extras do not matter
+        let mut reordered = vec![logs[2].clone(), logs[0].clone(), logs[1].clone()];
+        reordered[0].block_timestamp = Some(1_700_000_000);
+        assert_eq!(LogFingerprint::of(&reordered), fingerprint);
+
+        // Missing or altered logs do
+        assert_ne!(LogFingerprint::of(&logs[..2]), fingerprint);
+        let altered = vec![log(10, 0, b"a"), log(10, 1, b"x"), log(12, 0, b"c")];
+        assert_ne!(LogFingerprint::of(&altered).hash, fingerprint.hash);
+        assert_ne!(LogFingerprint::of(&[]), fingerprint);
+    }
+
+    #[test]
+    fn test_is_sampled() {
+        let sampled = LogVerificationConfig {
+            sample_percent: 10,
+            blocks_with_logs: false,
+            quorum: 2,
+        };
+        assert!(is_sampled(&sampled, 0.05));
+        assert!(!is_sampled(&sampled, 0.1));
+        assert!(!is_sampled(&sampled, 0.5));
+
+        let never = LogVerificationConfig {
+            sample_percent: 0,
+            blocks_with_logs: true,
+            quorum: 2,
+        };
+        assert!(!is_sampled(&never, 0.0));
+    }
+
+    #[test]
+    fn test_bloom_matches() {
+        let identity = address!("8004a6090Cd10A7288092483047B097295Fb8847");
+        let reputation = address!("8004B8FD1A363aa02fDC07635C0c5F94f6Af5B7E");
+        let other = address!("1111111111111111111111111111111111111111");
+
+        let mut bloom = Bloom::default();
+        assert!(!bloom_matches(&bloom, &[identity, reputation]));
+
+        bloom.accrue_log(&log(1, 0, b"a").inner);
+        assert!(bloom_matches(&bloom, &[identity, reputation]));
+        assert!(bloom_matches(&bloom, &[reputation, identity]));
+        assert!(!bloom_matches(&bloom, &[other]));
+        assert!(!bloom_matches(&bloom, &[]));
+    }
+
+    #[test]
+    fn test_quorum_logs() {
+        let full = vec![log(10, 0, b"a"), log(10, 1, b"b")];
+
+        // The provider that lost a log is outvoted
+        let quorum = quorum_logs(
+            vec![
+                ("http://a".to_string(), vec![]),
+                ("http://b".to_string(), full.clone()),
+                ("http://c".to_string(), full.clone()),
+            ],
+            2,
+        )
+        .unwrap();
+        assert_eq!(quorum.logs, full);
+        assert_eq!(quorum.agreeing, urls(&["http://b", "http://c"]));
+        assert_eq!(quorum.disagreeing, urls(&["http://a"]));
+
+        // Not enough providers agree
+        let answers = vec![
+            ("http://a".to_string(), vec![]),
+            ("http://b".to_string(), full.clone()),
+        ];
+        assert!(quorum_logs(answers, 2).is_none());
+        let answers = vec![
+            ("http://a".to_string(), full.clone()),
+            ("http://b".to_string(), full.clone()),
+            ("http://c".to_string(), full[..1].to_vec()),
+        ];
+        assert!(quorum_logs(answers, 3).is_none());
+
+        // Ties are not resolved
+        let answers = vec![
+            ("http://a".to_string(), vec![]),
+            ("http://b".to_string(), vec![]),
+            ("http://c".to_string(), full.clone()),
+            ("http://d".to_string(), full.clone()),
+        ];
+        assert!(quorum_logs(answers, 2).is_none());
+    }
+}
@@ -6,6 +6,7 @@ pub mod config;
 pub mod contracts;
 pub mod indexer;
 pub mod models;
+pub mod outbox;
 pub mod rpc;
 pub mod stats;
 pub mod storage;
@@ -4,22 +4,146 @@ mod config;
 mod contracts;
 mod indexer;
 mod models;
+mod outbox;
 mod rpc;
 mod stats;
 mod storage;
 
-use anyhow::Result;
-use config::{Config, IndexerConfig};
-use indexer::supervisor::{IndexerSupervisor, RestartPolicy};
+use anyhow::{anyhow, Context, Result};
+use config::watch::ConfigWatcher;
+use config::{Config, Role};
+use indexer::chains::ChainSupervisors;
+use indexer::decoder::ChainDecoders;
+use outbox::{BroadcastSink, EventSink, OutboxDispatcher, WebhookSink};
 use sqlx::postgres::PgPoolOptions;
 use stats::StatsTracker;
 use std::path::Path;
+use std::sync::Arc;
 use storage::Storage;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// Maximum time to wait for indexers and the API server to stop after a shutdown signal
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Chain configuration file, reloaded when it changes
+const CHAINS_YAML: &str = "chains.yaml";
+
+/// Interval at which chains.yaml is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Command selected on the command line
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    /// Run the indexers and/or the API server (default): `[--role <api|indexer|all>]`
+    /// Without `--role` the ROLE environment variable decides (both by default)
+    Serve { role: Option<Role> },
+    /// Rebuild event_data from stored raw logs: `reindex --redecode [--chain-id <id>]`
+    Redecode { chain_id: Option<u64> },
+}
+
+/// Parse command line arguments (without the program name)
+fn parse_command(args: &[String]) -> Result<Command> {
+    let Some((command, options)) = args.split_first() else {
+        return Ok(Command::Serve { role: None });
+    };
+
+    match command.as_str() {
+        "--role" => {
+            let (role, rest) = options
+                .split_first()
+                .ok_or_else(|| anyhow!("--role requires a value"))?;
+            if let Some(other) = rest.first() {
+                return Err(anyhow!("Unexpected argument: {}", other));
+            }
+            Ok(Command::Serve {
+                role: Some(role.parse()?),
+            })
+        }
+        "reindex" => {
+            let mut redecode = false;
+            let mut chain_id = None;
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--redecode" => redecode = true,
+                    "--chain-id" => {
+                        let value = options
+                            .next()
+                            .ok_or_else(|| anyhow!("--chain-id requires a value"))?;
+                        chain_id = Some(value.parse().context("Invalid --chain-id")?);
+                    }
+                    other => return Err(anyhow!("Unknown reindex option: {}", other)),
+                }
+            }
+
+            if !redecode {
+                return Err(anyhow!(
+                    "reindex currently only supports --redecode (rebuild event data from stored raw logs)"
+                ));
+            }
+
+            Ok(Command::Redecode { chain_id })
+        }
+        other => Err(anyhow!(
+            "Unknown command: {} (usage: api_8004_dev [--role <api|indexer|all>] | api_8004_dev reindex --redecode [--chain-id <id>])",
+            other
+        )),
+    }
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM (sent by fly.io and most process managers)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install CTRL+C signal handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Resolve when the API server task exits (never in indexer-only processes)
+async fn api_exited(handle: &mut Option<JoinHandle<()>>) {
+    match handle {
+        Some(handle) => {
+            let _ = handle.await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Resolve when the watched configuration changed (never without a watcher)
+async fn config_changed(watcher: &mut Option<ConfigWatcher>) {
+    match watcher {
+        Some(watcher) => watcher.changed().await,
+        None => std::future::pending().await,
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = parse_command(&args)?;
+
     // Initialize logging (JSON format if LOG_FORMAT=json)
     let log_format = std::env::var("LOG_FORMAT").unwrap_or_else(|_| "text".to_string());
 
@@ -50,9 +174,9 @@ async fn main() -> Result<()> {
     info!("✅ Metrics initialized");
 
     // Load configuration (try chains.yaml first, fallback to env)
-    let config = if Path::new("chains.yaml").exists() {
-        info!("📋 Loading configuration from chains.yaml");
-        Config::from_yaml_and_env("chains.yaml")?
+    let config = if Path::new(CHAINS_YAML).exists() {
+        info!("📋 Loading configuration from {}", CHAINS_YAML);
+        Config::from_yaml_and_env(CHAINS_YAML)?
     } else {
         warn!(
             "⚠️  chains.yaml not found, falling back to environment variables (single-chain mode)"
@@ -61,6 +185,7 @@ async fn main() -> Result<()> {
     };
 
     info!("✅ Configuration loaded successfully");
+    info!("🪪 Instance id: {}", config.instance_id);
     info!("📊 Enabled chains: {}", config.chains.len());
     for chain in &config.chains {
         info!("   - {} (chain_id: {})", chain.name, chain.chain_id);
@@ -103,119 +228,230 @@ async fn main() -> Result<()> {
     // Create shared storage
     let storage = Storage::new(pool, config.max_events_in_memory);
 
+    let role = match command {
+        Command::Serve { role } => role.unwrap_or(config.role),
+        Command::Redecode { chain_id } => {
+            info!("🔁 Re-decoding stored events from raw logs (no RPC access)...");
+            let decoders = ChainDecoders::from_chains(&config.chains)?;
+            let summary = indexer::reindex::redecode_events(&storage, &decoders, chain_id).await?;
+            info!(
+                "✅ Re-decode complete: {} scanned, {} updated, {} without raw log, {} failed",
+                summary.scanned, summary.updated, summary.missing_raw, summary.failed
+            );
+            return Ok(());
+        }
+    };
+    info!("🎭 Role: {}", role);
+
+    // Register the configured chains (disabling chains no longer configured)
+    if role.runs_indexers() {
+        storage.sync_configured_chains(&config.chains).await?;
+        info!("✅ Chains table synchronised");
+    }
+
     // Create broadcast channel for real-time event streaming
     let (event_tx, _) = tokio::sync::broadcast::channel::<models::Event>(1000);
 
     // Create stats tracker for monitoring
     let stats_tracker = StatsTracker::new();
 
-    // Spawn supervisor for each enabled chain
-    info!(
-        "🔧 Starting indexer supervisors for {} chains...",
-        config.chains.len()
-    );
-
-    let mut supervisor_handles = vec![];
+    // Cancelled on SIGTERM/Ctrl-C; propagated to every supervisor and the API server
+    let shutdown = CancellationToken::new();
 
-    for chain in &config.chains {
-        // Convert ChainConfig to IndexerConfig
-        let indexer_config = match IndexerConfig::from_chain_config(chain) {
-            Ok(cfg) => cfg,
-            Err(e) => {
-                error!(
-                    "❌ Failed to create indexer config for {}: {}",
-                    chain.name, e
-                );
-                continue;
-            }
-        };
+    let mut supervisors = ChainSupervisors::new(
+        storage.clone(),
+        stats_tracker.clone(),
+        shutdown.clone(),
+        config.instance_id.clone(),
+    );
 
-        // Create supervisor with exponential backoff restart policy
-        let supervisor = IndexerSupervisor::new(
-            indexer_config,
-            storage.clone(),
-            event_tx.clone(),
-            RestartPolicy::Exponential {
-                max_retries: config.global.max_indexer_retries,
-                base_delay_ms: config.global.retry_base_delay_ms,
-                max_delay_ms: config.global.retry_max_delay_ms,
-            },
-            stats_tracker.clone(),
+    // Spawn supervisor for each enabled chain
+    if role.runs_indexers() {
+        info!(
+            "🔧 Starting indexer supervisors for {} chains...",
+            config.chains.len()
         );
+        supervisors.apply(&config.chains, &config.global).await;
 
-        let chain_name = chain.name.clone();
-
-        // Spawn supervisor in its own task
-        let handle = tokio::spawn(async move {
-            info!("🚀 Starting supervisor for {}", chain_name);
-            match supervisor.start().await {
-                Ok(()) => {
-                    info!("✅ Supervisor {} exited cleanly", chain_name);
-                }
-                Err(e) => {
-                    error!("❌ Supervisor {} failed: {}", chain_name, e);
-                }
-            }
-        });
+        if supervisors.is_empty() {
+            warn!("⚠️  No indexer supervisor could be started");
+        } else {
+            info!("✅ {} supervisors started", supervisors.len());
+        }
+    }
 
-        supervisor_handles.push((chain.name.clone(), handle));
+    // API processes relay the events announced by the outbox's broadcast sink (served by any
+    // instance) to their WebSocket clients
+    let mut listener_handle = None;
+    if role.runs_api() {
+        let listener_storage = storage.clone();
+        let listener_tx = event_tx.clone();
+        let listener_shutdown = shutdown.clone();
+        listener_handle = Some(tokio::spawn(storage::notify::forward_events(
+            listener_storage,
+            listener_tx,
+            listener_shutdown,
+        )));
     }
 
-    info!("✅ All supervisors started");
+    // Deliver the outbox to the WebSocket broadcast and the configured sinks (each sink is served
+    // by one instance at a time)
+    let mut sinks: Vec<Arc<dyn EventSink>> = vec![Arc::new(BroadcastSink::new(storage.clone()))];
+    for webhook in &config.outbox_webhooks {
+        sinks.push(Arc::new(WebhookSink::new(webhook)?));
+    }
+    let dispatcher = OutboxDispatcher::new(storage.clone(), sinks);
+    let dispatcher_handle = tokio::spawn(dispatcher.run(shutdown.clone()));
 
     // Start API server
-    let api_storage = storage.clone();
-    let api_host = config.server_host.clone();
-    let api_port = config.server_port;
-    let api_metrics = metrics_handle.clone();
-    let api_stats = stats_tracker.clone();
-
-    info!("🌐 Starting API server on {}:{}", api_host, api_port);
-
-    let api_handle = tokio::spawn(async move {
-        if let Err(e) = api::start_server(
-            api_host,
-            api_port,
-            api_storage,
-            event_tx,
-            api_metrics,
-            api_stats,
-        )
-        .await
-        {
-            error!("❌ API server error: {}", e);
-        }
-    });
+    let mut api_handle = None;
+    if role.runs_api() {
+        let api_storage = storage.clone();
+        let api_host = config.server_host.clone();
+        let api_port = config.server_port;
+        let api_metrics = metrics_handle.clone();
+        let api_stats = stats_tracker.clone();
+        // Without local indexers, unknown logs are re-decoded with the decoders of chains.yaml
+        let api_decoders = if role.runs_indexers() {
+            supervisors.decoders()
+        } else {
+            ChainDecoders::from_chains(&config.chains)?
+        };
+        let api_controls = supervisors.controls();
+        let api_providers = supervisors.providers();
+        let api_shutdown = shutdown.clone();
+        let api_event_tx = event_tx.clone();
 
-    // Setup graceful shutdown signal
-    let shutdown_signal = async {
-        tokio::signal::ctrl_c()
+        info!("🌐 Starting API server on {}:{}", api_host, api_port);
+
+        api_handle = Some(tokio::spawn(async move {
+            if let Err(e) = api::start_server(
+                api_host,
+                api_port,
+                api_storage,
+                api_event_tx,
+                api_decoders,
+                api_metrics,
+                api_stats,
+                api_controls,
+                api_providers,
+                api_shutdown,
+            )
             .await
-            .expect("Failed to install CTRL+C signal handler");
-        info!("🛑 Shutdown signal received, gracefully shutting down...");
-    };
+            {
+                error!("❌ API server error: {}", e);
+            }
+        }));
+    }
 
-    // Wait for either:
-    // 1. All supervisors to complete (they shouldn't unless there's an error)
-    // 2. API server to crash
-    // 3. Shutdown signal
-    tokio::select! {
-        _ = async {
-            for (chain_name, handle) in supervisor_handles {
-                if let Err(e) = handle.await {
-                    error!("❌ Supervisor {} panicked: {}", chain_name, e);
+    // chains.yaml is watched for changes (and reloaded on SIGHUP) by processes running indexers;
+    // env-only configs are static
+    let mut config_watcher = (role.runs_indexers() && Path::new(CHAINS_YAML).exists())
+        .then(|| ConfigWatcher::new(CHAINS_YAML, CONFIG_POLL_INTERVAL));
+
+    let shutdown_signal = shutdown_signal();
+    tokio::pin!(shutdown_signal);
+
+    // Run until either:
+    // 1. API server crashes
+    // 2. Shutdown signal (SIGTERM or Ctrl-C)
+    // Configuration changes start, stop or update supervisors in the meantime
+    let mut api_done = false;
+    loop {
+        tokio::select! {
+            _ = api_exited(&mut api_handle) => {
+                error!("⚠️  API server terminated");
+                api_done = true;
+                break;
+            }
+            _ = &mut shutdown_signal => {
+                info!("🛑 Shutdown signal received, gracefully shutting down...");
+                break;
+            }
+            _ = config_changed(&mut config_watcher) => {
+                match Config::from_yaml_and_env(CHAINS_YAML) {
+                    Ok(new_config) => {
+                        info!(
+                            "🔁 Reloading {} ({} enabled chains)",
+                            CHAINS_YAML,
+                            new_config.chains.len()
+                        );
+                        if let Err(e) = storage.sync_configured_chains(&new_config.chains).await {
+                            error!("❌ Failed to synchronise chains table: {:#}", e);
+                        }
+                        supervisors.apply(&new_config.chains, &new_config.global).await;
+                    }
+                    Err(e) => error!(
+                        "❌ Failed to reload {}, keeping the current configuration: {:#}",
+                        CHAINS_YAML, e
+                    ),
                 }
             }
-        } => {
-            error!("⚠️  All supervisors terminated");
         }
-        _ = api_handle => {
-            error!("⚠️  API server terminated");
+    }
+
+    // Stop whatever is still running: indexers finish the block being committed and mark their
+    // chain as stopped, the API server drains in-flight requests and closes WebSocket clients
+    shutdown.cancel();
+    let drain = async {
+        supervisors.join().await;
+        let _ = dispatcher_handle.await;
+        if let Some(handle) = listener_handle {
+            let _ = handle.await;
         }
-        _ = shutdown_signal => {
-            info!("✅ Graceful shutdown completed");
+        if let Some(handle) = api_handle.filter(|_| !api_done) {
+            let _ = handle.await;
         }
+    };
+
+    match timeout(SHUTDOWN_TIMEOUT, drain).await {
+        Ok(()) => info!("✅ Graceful shutdown completed"),
+        Err(_) => warn!(
+            "⚠️  Shutdown did not complete within {:?}, exiting anyway",
+            SHUTDOWN_TIMEOUT
+        ),
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command(&args(&[])).unwrap(),
+            Command::Serve { role: None }
+        );
+        assert_eq!(
+            parse_command(&args(&["--role", "api"])).unwrap(),
+            Command::Serve {
+                role: Some(Role::Api)
+            }
+        );
+        assert_eq!(
+            parse_command(&args(&["reindex", "--redecode"])).unwrap(),
+            Command::Redecode { chain_id: None }
+        );
+        assert_eq!(
+            parse_command(&args(&["reindex", "--chain-id", "84532", "--redecode"])).unwrap(),
+            Command::Redecode {
+                chain_id: Some(84532)
+            }
+        );
+
+        assert!(parse_command(&args(&["reindex"])).is_err());
+        assert!(parse_command(&args(&["reindex", "--redecode", "--chain-id"])).is_err());
+        assert!(parse_command(&args(&["reindex", "--redecode", "--chain-id", "abc"])).is_err());
+        assert!(parse_command(&args(&["serve"])).is_err());
+        assert!(parse_command(&args(&["--role"])).is_err());
+        assert!(parse_command(&args(&["--role", "worker"])).is_err());
+        assert!(parse_command(&args(&["--role", "api", "reindex"])).is_err());
+    }
+}
@@ -14,16 +14,41 @@ pub struct Event {
     pub event_type: EventType,
     pub event_data: EventData,
     pub created_at: Option<DateTime<Utc>>,
+    /// True when the event was rolled back by a chain reorganization
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub removed: bool,
+    /// True once the event's block is at or below the chain's finalized block
+    #[serde(default)]
+    pub finalized: bool,
+    /// Raw log the event was decoded from (not part of the API payload)
+    #[serde(skip)]
+    pub raw: Option<RawLog>,
+    /// ERC-8004 spec (ABI) version of the registry deployment that emitted the event
+    /// None for events of custom contracts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_version: Option<String>,
+}
+
+/// Raw topics and data of a log, kept so events can be re-decoded without RPC access
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawLog {
+    /// Hex-encoded log topics (topic0 is the event signature)
+    pub topics: Vec<String>,
+    /// Hex-encoded log data
+    pub data: String,
 }
 
-/// All possible event types from the three registries
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(tag = "type")]
+/// All possible event types from the three registries, plus events loaded from JSON ABIs
+/// Serialized as `{"type": "<name>"}`
+#[derive(Debug, Clone, PartialEq)]
 pub enum EventType {
     // IdentityRegistry events
     Registered,
     MetadataSet,
     UriUpdated,
+    Transfer,
+    Approval,
+    ApprovalForAll,
 
     // ReputationRegistry events
     NewFeedback,
@@ -33,23 +58,70 @@ pub enum EventType {
     // ValidationRegistry events
     ValidationRequest,
     ValidationResponse,
+
+    /// Event decoded from a JSON ABI configured in chains.yaml
+    Custom(String),
 }
 
 impl EventType {
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             EventType::Registered => "Registered",
             EventType::MetadataSet => "MetadataSet",
             EventType::UriUpdated => "UriUpdated",
+            EventType::Transfer => "Transfer",
+            EventType::Approval => "Approval",
+            EventType::ApprovalForAll => "ApprovalForAll",
             EventType::NewFeedback => "NewFeedback",
             EventType::FeedbackRevoked => "FeedbackRevoked",
             EventType::ResponseAppended => "ResponseAppended",
             EventType::ValidationRequest => "ValidationRequest",
             EventType::ValidationResponse => "ValidationResponse",
+            EventType::Custom(name) => name,
+        }
+    }
+
+    /// Parse a stored event type name (names without a built-in type are custom events)
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "Registered" => EventType::Registered,
+            "MetadataSet" => EventType::MetadataSet,
+            "UriUpdated" => EventType::UriUpdated,
+            "Transfer" => EventType::Transfer,
+            "Approval" => EventType::Approval,
+            "ApprovalForAll" => EventType::ApprovalForAll,
+            "NewFeedback" => EventType::NewFeedback,
+            "FeedbackRevoked" => EventType::FeedbackRevoked,
+            "ResponseAppended" => EventType::ResponseAppended,
+            "ValidationRequest" => EventType::ValidationRequest,
+            "ValidationResponse" => EventType::ValidationResponse,
+            other => EventType::Custom(other.to_string()),
         }
     }
 }
 
+impl Serialize for EventType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut tagged = serializer.serialize_struct("EventType", 1)?;
+        tagged.serialize_field("type", self.as_str())?;
+        tagged.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Tagged {
+            #[serde(rename = "type")]
+            name: String,
+        }
+
+        Tagged::deserialize(deserializer).map(|tagged| EventType::parse(&tagged.name))
+    }
+}
+
 /// Event-specific data for each event type
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -62,6 +134,30 @@ pub enum EventData {
     ResponseAppended(ResponseAppendedData),
     ValidationRequest(ValidationRequestData),
     ValidationResponse(ValidationResponseData),
+    Transfer(TransferData),
+    Approval(ApprovalData),
+    ApprovalForAll(ApprovalForAllData),
+    /// Parameters of a custom event, keyed by ABI parameter name
+    Custom(serde_json::Map<String, serde_json::Value>),
+}
+
+impl EventData {
+    /// Parse stored event data for a known event type
+    /// Custom events (and data that no longer fits its built-in type) are kept as raw JSON
+    pub fn from_json(event_type: &EventType, value: serde_json::Value) -> Option<Self> {
+        if !matches!(event_type, EventType::Custom(_)) {
+            if let Ok(data) = serde_json::from_value::<EventData>(value.clone()) {
+                if !matches!(data, EventData::Custom(_)) {
+                    return Some(data);
+                }
+            }
+        }
+
+        match value {
+            serde_json::Value::Object(fields) => Some(EventData::Custom(fields)),
+            _ => None,
+        }
+    }
 }
 
 // IdentityRegistry events
@@ -88,8 +184,33 @@ pub struct UriUpdatedData {
     pub updated_by: String,
 }
 
+/// ERC-721 transfer of an agent identity (`from` is the zero address on mint, `to` on burn)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferData {
+    pub agent_id: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalData {
+    pub agent_id: String,
+    pub owner: String,
+    pub approved: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalForAllData {
+    pub owner: String,
+    pub operator: String,
+    pub approved: bool,
+}
+
 // ReputationRegistry events
 
+/// Feedback normalised across spec versions
+/// v1 emits a 0-100 `score` and bytes32 tags; v2 emits a signed `value` with `value_decimals`
+/// (from which `score` is derived), string tags, the feedback index and an endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewFeedbackData {
     pub agent_id: String,
@@ -99,6 +220,15 @@ pub struct NewFeedbackData {
     pub tag2: String,
     pub feedback_uri: String,
     pub feedback_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback_index: Option<String>,
+    /// Signed feedback value as a decimal string, scaled by `value_decimals`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_decimals: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +269,42 @@ pub struct ValidationResponseData {
     pub tag: String,
 }
 
+/// Registry log that could not be decoded, stored raw for later re-decoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnknownLog {
+    pub id: Option<i64>,
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub block_timestamp: DateTime<Utc>,
+    pub transaction_hash: String,
+    pub log_index: u32,
+    pub contract_address: String,
+    /// Contract role that emitted the log (identity, reputation, validation or a custom role)
+    pub registry: String,
+    /// Hex-encoded log topics (topic0 is the event signature)
+    pub topics: Vec<String>,
+    /// Hex-encoded log data
+    pub data: String,
+    /// Decoding error at the time the log was indexed
+    pub error: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Query parameters for listing unknown logs
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UnknownLogQuery {
+    pub chain_id: Option<u64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Query parameters of the agent owner lookup
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AgentOwnerQuery {
+    /// Identity registry address; defaults to the chain's newest identity registry deployment
+    pub registry: Option<String>,
+}
+
 /// Query parameters for filtering events
 #[derive(Debug, Clone, Deserialize)]
 pub struct EventQuery {
@@ -163,13 +329,20 @@ pub struct EventQuery {
     /// Filter by agent ID
     pub agent_id: Option<String>,
 
-    /// Filter by category (agents, metadata, validation, feedback, all)
+    /// Filter by ERC-8004 spec version of the emitting registry (v1, v2)
+    pub spec_version: Option<String>,
+
+    /// Filter by category (agents, ownership, metadata, validation, feedback, all)
     pub category: Option<String>,
 
     /// Include category statistics in response (default: false)
     #[serde(default)]
     pub include_stats: bool,
 
+    /// Only return events from finalized blocks (default: false)
+    #[serde(default)]
+    pub finalized_only: bool,
+
     /// Offset for pagination (number of records to skip)
     pub offset: Option<i64>,
 
@@ -196,6 +369,7 @@ impl EventQuery {
     pub fn event_types_for_category(&self) -> Option<Vec<&'static str>> {
         match self.category.as_deref() {
             Some("agents") => Some(vec!["Registered"]),
+            Some("ownership") => Some(vec!["Transfer", "Approval", "ApprovalForAll"]),
             Some("metadata") => Some(vec!["MetadataSet", "UriUpdated"]),
             Some("validation") => Some(vec!["ValidationRequest", "ValidationResponse"]),
             Some("feedback") => Some(vec!["NewFeedback", "FeedbackRevoked", "ResponseAppended"]),
@@ -216,8 +390,10 @@ impl Default for EventQuery {
             contract: None,
             event_type: None,
             agent_id: None,
+            spec_version: None,
             category: None,
             include_stats: false,
+            finalized_only: false,
             offset: None,
             limit: Some(1000),
         }
@@ -240,6 +416,38 @@ mod tests {
         assert_eq!(EventType::ValidationResponse.as_str(), "ValidationResponse");
     }
 
+    #[test]
+    fn test_custom_event_type_serialization() {
+        let json = serde_json::to_value(EventType::Registered).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "Registered"}));
+
+        let custom = EventType::Custom("Delegated".to_string());
+        let json = serde_json::to_value(&custom).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "Delegated"}));
+        assert_eq!(serde_json::from_value::<EventType>(json).unwrap(), custom);
+
+        assert_eq!(EventType::parse("NewFeedback"), EventType::NewFeedback);
+        assert_eq!(EventType::parse("Transfer"), EventType::Transfer);
+        assert_eq!(EventType::parse("Delegated"), custom);
+    }
+
+    #[test]
+    fn test_custom_event_data_from_json() {
+        // Custom event data with the same fields as a built-in event stays custom
+        let value = serde_json::json!({
+            "agent_id": "1",
+            "client": "0xclient",
+            "feedback_index": "2"
+        });
+        let data = EventData::from_json(&EventType::Custom("Revoked".to_string()), value.clone());
+        assert!(matches!(data, Some(EventData::Custom(_))));
+
+        let data = EventData::from_json(&EventType::FeedbackRevoked, value);
+        assert!(matches!(data, Some(EventData::FeedbackRevoked(_))));
+
+        assert!(EventData::from_json(&EventType::Registered, serde_json::json!(42)).is_none());
+    }
+
     #[test]
     fn test_event_query_default_values() {
         let query = EventQuery::default();
@@ -385,6 +593,16 @@ mod tests {
         assert!(!query.include_stats);
     }
 
+    #[test]
+    fn test_event_query_finalized_only() {
+        let query: EventQuery = serde_urlencoded::from_str("chain_id=1&limit=10").unwrap();
+        assert!(!query.finalized_only);
+
+        let query: EventQuery =
+            serde_urlencoded::from_str("chain_id=1&finalized_only=true").unwrap();
+        assert!(query.finalized_only);
+    }
+
     #[test]
     fn test_event_serialization() {
         use chrono::Utc;
@@ -404,6 +622,10 @@ mod tests {
                 owner: "0x5678".to_string(),
             }),
             created_at: Some(Utc::now()),
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
         };
 
         // Test serialization
@@ -418,6 +640,44 @@ mod tests {
         assert_eq!(deserialized.block_number, 12345);
     }
 
+    #[test]
+    fn test_event_removed_flag_serialization() {
+        use chrono::Utc;
+
+        let mut event = Event {
+            id: Some(1),
+            chain_id: 84532,
+            block_number: 500,
+            block_timestamp: Utc::now(),
+            transaction_hash: "0xabcd".to_string(),
+            log_index: 0,
+            contract_address: "0x1234".to_string(),
+            event_type: EventType::Registered,
+            event_data: EventData::Registered(RegisteredData {
+                agent_id: "1".to_string(),
+                token_uri: "https://example.com".to_string(),
+                owner: "0x5678".to_string(),
+            }),
+            created_at: None,
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
+        };
+
+        // Canonical events don't carry the flag
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(!json.contains("removed"));
+
+        // Rolled back events do
+        event.removed = true;
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"removed\":true"));
+
+        let deserialized: Event = serde_json::from_str(&json).unwrap();
+        assert!(deserialized.removed);
+    }
+
     #[test]
     fn test_registered_data_serialization() {
         let data = RegisteredData {
@@ -461,6 +721,20 @@ mod tests {
         assert_eq!(event_types, Some(vec!["Registered"]));
     }
 
+    #[test]
+    fn test_category_mapping_ownership() {
+        let query = EventQuery {
+            category: Some("ownership".to_string()),
+            ..Default::default()
+        };
+
+        let event_types = query.event_types_for_category();
+        assert_eq!(
+            event_types,
+            Some(vec!["Transfer", "Approval", "ApprovalForAll"])
+        );
+    }
+
     #[test]
     fn test_category_mapping_metadata() {
         let query = EventQuery {
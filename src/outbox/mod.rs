@@ -0,0 +1,315 @@
+use crate::config::WebhookConfig;
+use crate::models::Event;
+use crate::storage::notify::CATCH_UP_WINDOW;
+use crate::storage::Storage;
+use anyhow::{anyhow, Result};
+use axum::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Maximum number of outbox entries delivered to a sink at once
+const BATCH_SIZE: i64 = 100;
+
+/// Interval at which the outbox is checked for undelivered entries
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Delay before retrying a sink after a failed delivery (doubled per failure up to the maximum)
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Interval at which delivered outbox entries are deleted and sink lag is checked
+const PRUNE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Age of the oldest undelivered entry above which a sink is reported as stuck
+/// Undelivered entries are never pruned, so a stuck sink makes the outbox grow
+const STUCK_SINK_AGE: Duration = Duration::from_secs(3600);
+
+/// Interval at which the delivery horizon is checked
+const HORIZON_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Age of the oldest held back entry above which the delivery horizon is reported as stalled
+const STALLED_HORIZON_AGE: Duration = Duration::from_secs(60);
+
+/// Timeout of a webhook request
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Destination of outbox events (webhook, message bus, ...)
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    /// Stable, unique name; the sink's delivery cursor is stored under it
+    fn name(&self) -> &str;
+
+    /// Deliver a batch of events in outbox order
+    /// Errors leave the batch to be delivered again, so sinks must tolerate duplicates
+    async fn deliver(&self, events: &[Event]) -> Result<()>;
+}
+
+/// Sink announcing events to the WebSocket clients of every API process
+/// It notifies the channel each API process listens on; the listeners then read the new outbox
+/// entries themselves (see `storage::notify::forward_events`), so it does not matter which
+/// instance serves the sink. Writers already notify on commit; the sink wakes the listeners again
+/// for entries that were held back behind an older transaction.
+pub struct BroadcastSink {
+    storage: Storage,
+}
+
+impl BroadcastSink {
+    pub fn new(storage: Storage) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl EventSink for BroadcastSink {
+    fn name(&self) -> &str {
+        "broadcast"
+    }
+
+    async fn deliver(&self, _events: &[Event]) -> Result<()> {
+        self.storage.announce_outbox().await
+    }
+}
+
+/// Sink POSTing `{"sink": <name>, "events": [...]}` to a URL; any non-2xx response is a failure
+pub struct WebhookSink {
+    name: String,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(config: &WebhookConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            name: format!("webhook:{}", config.name),
+            url: config.url.clone(),
+            client,
+        })
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn deliver(&self, events: &[Event]) -> Result<()> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "sink": self.name, "events": events }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Webhook responded with {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+/// Delivers outbox entries to the registered sinks with at-least-once semantics
+/// Each sink has its own cursor in the database and is served by one instance at a time, so any
+/// number of processes can run a dispatcher
+pub struct OutboxDispatcher {
+    storage: Storage,
+    sinks: Vec<Arc<dyn EventSink>>,
+}
+
+impl OutboxDispatcher {
+    pub fn new(storage: Storage, sinks: Vec<Arc<dyn EventSink>>) -> Self {
+        Self { storage, sinks }
+    }
+
+    /// Deliver and prune the outbox until `shutdown` is cancelled
+    pub async fn run(self, shutdown: CancellationToken) {
+        info!("Outbox dispatcher started with {} sinks", self.sinks.len());
+
+        // Consecutive failures and next attempt of sinks that failed
+        let mut backoff: HashMap<String, (u32, Instant)> = HashMap::new();
+        let mut next_prune = Instant::now();
+        let mut next_horizon_check = Instant::now();
+
+        loop {
+            for sink in &self.sinks {
+                if backoff
+                    .get(sink.name())
+                    .is_some_and(|(_, retry_at)| Instant::now() < *retry_at)
+                {
+                    continue;
+                }
+
+                match self.deliver_pending(sink.as_ref(), &shutdown).await {
+                    Ok(()) => {
+                        backoff.remove(sink.name());
+                    }
+                    Err(e) => {
+                        let failures = backoff.get(sink.name()).map_or(0, |(f, _)| *f) + 1;
+                        let delay = retry_delay(failures);
+                        warn!(
+                            "Outbox delivery to {} failed ({} in a row), retrying in {:?}: {:#}",
+                            sink.name(),
+                            failures,
+                            delay,
+                            e
+                        );
+                        metrics::counter!("outbox_delivery_failures_total", "sink" => sink.name().to_string())
+                            .increment(1);
+                        backoff.insert(sink.name().to_string(), (failures, Instant::now() + delay));
+                    }
+                }
+            }
+
+            if Instant::now() >= next_prune {
+                next_prune = Instant::now() + PRUNE_INTERVAL;
+                match self.storage.prune_outbox(CATCH_UP_WINDOW).await {
+                    Ok(0) => {}
+                    Ok(pruned) => info!("Pruned {} delivered outbox entries", pruned),
+                    Err(e) => warn!("Failed to prune outbox: {}", e),
+                }
+                self.check_sink_lag().await;
+            }
+            if Instant::now() >= next_horizon_check {
+                next_horizon_check = Instant::now() + HORIZON_CHECK_INTERVAL;
+                self.check_horizon_lag().await;
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sleep(POLL_INTERVAL) => {}
+            }
+        }
+
+        info!("Outbox dispatcher stopped");
+    }
+
+    /// Export the delivery lag of every sink with a cursor (including sinks no instance serves
+    /// any more) and warn about stuck ones
+    async fn check_sink_lag(&self) {
+        let lags = match self.storage.outbox_sink_lag().await {
+            Ok(lags) => lags,
+            Err(e) => {
+                warn!("Failed to check outbox sink lag: {}", e);
+                return;
+            }
+        };
+
+        for (sink, lag) in lags {
+            metrics::gauge!("outbox_sink_lag_seconds", "sink" => sink.clone())
+                .set(lag.as_secs_f64());
+            if lag > STUCK_SINK_AGE {
+                warn!(
+                    "Outbox sink {} is stuck: its oldest undelivered entry is {}s old. Entries are \
+                     kept until it catches up (delete its outbox_cursors row if it was removed)",
+                    sink,
+                    lag.as_secs()
+                );
+            }
+        }
+    }
+
+    /// Export how long outbox entries are held back by running transactions and warn when a
+    /// long-running transaction stalls every sink
+    async fn check_horizon_lag(&self) {
+        let lag = match self.storage.outbox_horizon_lag().await {
+            Ok(lag) => lag,
+            Err(e) => {
+                warn!("Failed to check outbox horizon lag: {}", e);
+                return;
+            }
+        };
+
+        metrics::gauge!("outbox_horizon_lag_seconds").set(lag.as_secs_f64());
+        if lag > STALLED_HORIZON_AGE {
+            warn!(
+                "Outbox delivery is stalled: entries committed {}s ago wait for an older \
+                 transaction to end (look for long-running or idle in transaction sessions in \
+                 pg_stat_activity)",
+                lag.as_secs()
+            );
+        }
+    }
+
+    /// Deliver batches to a sink until it is caught up (or served by another instance)
+    async fn deliver_pending(
+        &self,
+        sink: &dyn EventSink,
+        shutdown: &CancellationToken,
+    ) -> Result<()> {
+        while !shutdown.is_cancelled() {
+            let Some(batch) = self
+                .storage
+                .claim_outbox_batch(sink.name(), BATCH_SIZE)
+                .await?
+            else {
+                return Ok(());
+            };
+
+            // Acknowledging an empty batch records when the sink was last served
+            if batch.is_empty() {
+                batch.ack().await?;
+                break;
+            }
+
+            let caught_up = batch.len() < BATCH_SIZE as usize;
+            if !batch.events.is_empty() {
+                if let Err(e) = sink.deliver(&batch.events).await {
+                    if let Err(release_error) = batch.release().await {
+                        warn!(
+                            "Failed to release outbox sink {}: {}",
+                            sink.name(),
+                            release_error
+                        );
+                    }
+                    return Err(e);
+                }
+                metrics::counter!("outbox_events_delivered_total", "sink" => sink.name().to_string())
+                    .increment(batch.events.len() as u64);
+            }
+            batch.ack().await?;
+
+            if caught_up {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Delay before the next attempt after `failures` consecutive failed deliveries
+fn retry_delay(failures: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(RETRY_MAX_DELAY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(4), Duration::from_secs(8));
+        assert_eq!(retry_delay(10), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(100), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_webhook_sink_name() {
+        let sink = WebhookSink::new(&WebhookConfig {
+            name: "crm".to_string(),
+            url: "https://crm.example.com/hooks/8004".to_string(),
+        })
+        .unwrap();
+        assert_eq!(sink.name(), "webhook:crm");
+    }
+}
@@ -0,0 +1,273 @@
+use alloy::transports::{RpcError, TransportError, TransportErrorKind};
+use std::fmt;
+use std::time::Duration;
+
+/// Kind of failure a provider returned, deciding how long it is put aside
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// 429 or a provider specific rate/capacity limit
+    RateLimited,
+    /// Timeouts, 5xx, connection failures and malformed responses
+    Transient,
+    /// Authentication, disabled keys and unsupported methods; retrying soon will not help
+    Fatal,
+    /// The provider does not have the requested block (yet)
+    DataMissing,
+}
+
+impl ErrorClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::RateLimited => "rate_limited",
+            ErrorClass::Transient => "transient",
+            ErrorClass::Fatal => "fatal",
+            ErrorClass::DataMissing => "data_missing",
+        }
+    }
+}
+
+/// Classified error of a request to a provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderError {
+    pub class: ErrorClass,
+    /// Delay the provider asked for before the next request
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl ProviderError {
+    pub fn new(class: ErrorClass, message: impl Into<String>) -> Self {
+        Self {
+            class,
+            retry_after: None,
+            message: message.into(),
+        }
+    }
+
+    /// A request that did not complete in time
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::new(ErrorClass::Transient, message)
+    }
+
+    /// The provider answered without the requested block
+    pub fn data_missing(message: impl Into<String>) -> Self {
+        Self::new(ErrorClass::DataMissing, message)
+    }
+
+    /// Classify an error from the error text alone
+    pub fn classify(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let class = classify_message(&message);
+        let retry_after = match class {
+            ErrorClass::RateLimited => retry_after_hint(&message),
+            _ => None,
+        };
+        Self {
+            class,
+            retry_after,
+            message,
+        }
+    }
+
+    /// Classify an RPC transport error, using its HTTP status or JSON-RPC error code when present
+    /// The HTTP transport does not expose response headers, so a `Retry-After` value is only seen
+    /// when the provider repeats it in the response body
+    pub fn from_transport(context: &str, error: &TransportError) -> Self {
+        let message = format!("{}: {}", context, error);
+        let class = match error {
+            RpcError::Transport(TransportErrorKind::HttpError(http)) => match http.status {
+                429 => Some(ErrorClass::RateLimited),
+                401..=403 => Some(ErrorClass::Fatal),
+                _ => None,
+            },
+            RpcError::ErrorResp(payload) => match payload.code {
+                429 | -32005 => Some(ErrorClass::RateLimited),
+                -32601 => Some(ErrorClass::Fatal),
+                _ => None,
+            },
+            RpcError::NullResp | RpcError::DeserError { .. } | RpcError::SerError(_) => {
+                Some(ErrorClass::Transient)
+            }
+            _ => None,
+        };
+
+        match class {
+            Some(class) => Self {
+                class,
+                retry_after: match class {
+                    ErrorClass::RateLimited => retry_after_hint(&message),
+                    _ => None,
+                },
+                message,
+            },
+            None => Self::classify(message),
+        }
+    }
+}
+
+impl From<&str> for ProviderError {
+    fn from(message: &str) -> Self {
+        Self::classify(message)
+    }
+}
+
+impl From<String> for ProviderError {
+    fn from(message: String) -> Self {
+        Self::classify(message)
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.class.as_str())
+    }
+}
+
+/// Error class from the wording providers use
+fn classify_message(message: &str) -> ErrorClass {
+    let message = message.to_lowercase();
+    let contains_any = |needles: &[&str]| needles.iter().any(|n| message.contains(n));
+
+    if contains_any(&[
+        "error 429",
+        "code 429",
+        "rate limit",
+        "too many requests",
+        "request rate exceeded",
+        "compute units",
+        "capacity",
+        "throughput",
+    ]) {
+        ErrorClass::RateLimited
+    } else if contains_any(&[
+        "error 401",
+        "error 403",
+        "unauthorized",
+        "forbidden",
+        "api key",
+        "apikey",
+        "project id",
+        "not whitelisted",
+        "method not found",
+        "does not exist/is not available",
+    ]) {
+        ErrorClass::Fatal
+    } else if contains_any(&[
+        "not found",
+        "not available",
+        "unknown block",
+        "missing trie node",
+    ]) {
+        ErrorClass::DataMissing
+    } else {
+        ErrorClass::Transient
+    }
+}
+
+/// Retry delay announced in a rate limit response (`Retry-After: 5`, `retry after 5s`,
+/// `"backoff_seconds": 30`, `try again in 10 seconds`)
+fn retry_after_hint(message: &str) -> Option<Duration> {
+    let message = message.to_lowercase();
+    [
+        "retry-after",
+        "retry after",
+        "backoff_seconds",
+        "try again in",
+    ]
+    .iter()
+    .find_map(|marker| {
+        let rest = &message[message.find(marker)? + marker.len()..];
+        let rest =
+            rest.trim_start_matches(|c: char| c.is_whitespace() || matches!(c, ':' | '=' | '"'));
+        let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let seconds: u64 = digits.parse().ok()?;
+        let rest = rest[digits.len()..].trim_start();
+        Some(
+            if rest.starts_with("ms") || rest.starts_with("millisecond") {
+                Duration::from_millis(seconds)
+            } else {
+                Duration::from_secs(seconds)
+            },
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_messages() {
+        let cases = [
+            (
+                "HTTP error 429 with body: Too Many Requests",
+                ErrorClass::RateLimited,
+            ),
+            ("Block 14290 not found", ErrorClass::DataMissing),
+            (
+                "Your app has exceeded its compute units per second capacity",
+                ErrorClass::RateLimited,
+            ),
+            ("get_logs timeout", ErrorClass::Transient),
+            (
+                "HTTP error 502 with body: Bad Gateway",
+                ErrorClass::Transient,
+            ),
+            (
+                "deserialization error: expected value at line 1 column 1",
+                ErrorClass::Transient,
+            ),
+            (
+                "HTTP error 401 with body: invalid project id",
+                ErrorClass::Fatal,
+            ),
+            ("API key is disabled", ErrorClass::Fatal),
+            ("Block 123 not found", ErrorClass::DataMissing),
+            ("finalized block not available", ErrorClass::DataMissing),
+        ];
+        for (message, class) in cases {
+            assert_eq!(ProviderError::classify(message).class, class, "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_retry_after_hint() {
+        let error = ProviderError::classify("HTTP error 429 with body: Retry-After: 12");
+        assert_eq!(error.retry_after, Some(Duration::from_secs(12)));
+
+        let error = ProviderError::classify(
+            r#"project ID request rate exceeded {"rate":{"allowed_rps":10,"backoff_seconds":30}}"#,
+        );
+        assert_eq!(error.class, ErrorClass::RateLimited);
+        assert_eq!(error.retry_after, Some(Duration::from_secs(30)));
+
+        let error = ProviderError::classify("rate limited, try again in 500ms");
+        assert_eq!(error.retry_after, Some(Duration::from_millis(500)));
+
+        assert_eq!(ProviderError::classify("rate limited").retry_after, None);
+        // Hints are only read from rate limit errors
+        assert_eq!(
+            ProviderError::classify("Block 5 not found, retry after 3s").retry_after,
+            None
+        );
+    }
+
+    #[test]
+    fn test_classify_transport_errors() {
+        let http =
+            |status: u16, body: &str| TransportErrorKind::http_error(status, body.to_string());
+
+        let error = ProviderError::from_transport("get_logs failed", &http(429, "slow down"));
+        assert_eq!(error.class, ErrorClass::RateLimited);
+        assert!(error.message.starts_with("get_logs failed: "));
+
+        let error = ProviderError::from_transport("get_logs failed", &http(403, "forbidden"));
+        assert_eq!(error.class, ErrorClass::Fatal);
+
+        let error = ProviderError::from_transport("get_logs failed", &http(503, "unavailable"));
+        assert_eq!(error.class, ErrorClass::Transient);
+
+        let error = ProviderError::from_transport("get_logs failed", &RpcError::NullResp);
+        assert_eq!(error.class, ErrorClass::Transient);
+    }
+}
@@ -0,0 +1,279 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Number of probe latencies percentiles are computed over
+const LATENCY_WINDOW: usize = 100;
+
+/// Probes needed before a provider can be taken out of rotation for being slow
+const MIN_LATENCY_SAMPLES: usize = 5;
+
+/// Consecutive failed probes after which a provider is taken out of rotation
+const MAX_PROBE_FAILURES: u32 = 3;
+
+/// Limits beyond which a provider is taken out of rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthThresholds {
+    /// Interval between two probes of every provider
+    pub probe_interval: Duration,
+    /// Maximum number of blocks a provider's head may trail the best head
+    pub max_blocks_behind: u64,
+    /// Maximum p90 probe latency
+    pub max_latency: Duration,
+}
+
+/// Rolling window of the latest probe latencies
+#[derive(Debug, Clone, Default)]
+pub struct LatencyWindow {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    pub fn record(&mut self, latency: Duration) {
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Nearest-rank percentile (`quantile` between 0 and 1) of the window
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<Duration> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        Some(sorted[rank.saturating_sub(1)])
+    }
+}
+
+/// Outcome of probing one provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    /// Duration of the eth_blockNumber call
+    pub latency: Duration,
+    pub head_block: u64,
+    pub chain_id: u64,
+}
+
+/// Why a provider was taken out of rotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exclusion {
+    /// Consecutive probes failed
+    Unreachable { failures: u32 },
+    /// Head trails the best provider's head
+    Lagging { blocks_behind: u64 },
+    /// p90 probe latency is above the threshold
+    Slow { p90: Duration },
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exclusion::Unreachable { failures } => write!(f, "{} probes failed", failures),
+            Exclusion::Lagging { blocks_behind } => {
+                write!(f, "{} blocks behind", blocks_behind)
+            }
+            Exclusion::Slow { p90 } => write!(f, "p90 latency {}ms", p90.as_millis()),
+        }
+    }
+}
+
+/// Probe results of one provider
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHealth {
+    pub latencies: LatencyWindow,
+    /// Head block of the last successful probe
+    pub head_block: Option<u64>,
+    /// Chain ID reported by the last successful probe
+    pub chain_id: Option<u64>,
+    pub last_probe: Option<Instant>,
+    pub last_probe_error: Option<String>,
+    pub probe_failures: u32,
+    /// Set while the provider is out of rotation
+    pub exclusion: Option<Exclusion>,
+}
+
+impl ProviderHealth {
+    pub fn record(&mut self, result: Result<ProbeResult, String>) {
+        self.last_probe = Some(Instant::now());
+        match result {
+            Ok(probe) => {
+                self.latencies.record(probe.latency);
+                self.head_block = Some(probe.head_block);
+                self.chain_id = Some(probe.chain_id);
+                self.last_probe_error = None;
+                self.probe_failures = 0;
+            }
+            Err(e) => {
+                self.last_probe_error = Some(e);
+                self.probe_failures += 1;
+            }
+        }
+    }
+
+    /// Whether the last probe succeeded
+    pub fn is_reachable(&self) -> bool {
+        self.last_probe.is_some() && self.probe_failures == 0
+    }
+
+    /// Blocks this provider's head trails `best_head`
+    pub fn blocks_behind(&self, best_head: Option<u64>) -> Option<u64> {
+        Some(best_head?.saturating_sub(self.head_block?))
+    }
+
+    /// Why the provider should be out of rotation given the best head among the providers
+    /// Providers that were never probed stay in rotation
+    pub fn evaluate(
+        &self,
+        best_head: Option<u64>,
+        thresholds: &HealthThresholds,
+    ) -> Option<Exclusion> {
+        if self.probe_failures >= MAX_PROBE_FAILURES {
+            return Some(Exclusion::Unreachable {
+                failures: self.probe_failures,
+            });
+        }
+
+        if let Some(blocks_behind) = self.blocks_behind(best_head) {
+            if blocks_behind > thresholds.max_blocks_behind {
+                return Some(Exclusion::Lagging { blocks_behind });
+            }
+        }
+
+        if self.latencies.len() >= MIN_LATENCY_SAMPLES {
+            if let Some(p90) = self.latencies.percentile(0.9) {
+                if p90 > thresholds.max_latency {
+                    return Some(Exclusion::Slow { p90 });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Health of a provider as reported by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHealthSnapshot {
+    /// Provider URL with credentials redacted
+    pub url: String,
+    pub in_rotation: bool,
+    /// Why the provider is never used (e.g. it serves another chain)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quarantined_because: Option<String>,
+    /// Chain ID reported by the last successful probe
+    pub chain_id: Option<u64>,
+    /// Why the provider is out of rotation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded_because: Option<String>,
+    pub in_cooldown: bool,
+    /// Circuit breaker state: closed, open (cooling down) or half_open (on trial)
+    pub circuit: &'static str,
+    pub consecutive_errors: u32,
+    /// Class of the last failed request: rate_limited, transient, fatal or data_missing
+    pub last_error_class: Option<&'static str>,
+    pub head_block: Option<u64>,
+    pub blocks_behind: Option<u64>,
+    pub latency_p50_ms: Option<u64>,
+    pub latency_p90_ms: Option<u64>,
+    pub latency_p99_ms: Option<u64>,
+    pub probes: usize,
+    /// Seconds since the last probe
+    pub last_probe_secs_ago: Option<u64>,
+    pub last_probe_error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: HealthThresholds = HealthThresholds {
+        probe_interval: Duration::from_secs(15),
+        max_blocks_behind: 10,
+        max_latency: Duration::from_millis(500),
+    };
+
+    fn probe(latency_ms: u64, head_block: u64) -> Result<ProbeResult, String> {
+        Ok(ProbeResult {
+            latency: Duration::from_millis(latency_ms),
+            head_block,
+            chain_id: 11155111,
+        })
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut window = LatencyWindow::default();
+        assert_eq!(window.percentile(0.5), None);
+
+        for ms in (1..=100).rev() {
+            window.record(Duration::from_millis(ms));
+        }
+        assert_eq!(window.percentile(0.5), Some(Duration::from_millis(50)));
+        assert_eq!(window.percentile(0.9), Some(Duration::from_millis(90)));
+        assert_eq!(window.percentile(0.99), Some(Duration::from_millis(99)));
+        assert_eq!(window.percentile(1.0), Some(Duration::from_millis(100)));
+
+        // Only the latest samples are kept
+        for _ in 0..LATENCY_WINDOW {
+            window.record(Duration::from_millis(7));
+        }
+        assert_eq!(window.len(), LATENCY_WINDOW);
+        assert_eq!(window.percentile(0.99), Some(Duration::from_millis(7)));
+    }
+
+    #[test]
+    fn test_health_evaluation() {
+        let mut health = ProviderHealth::default();
+        assert_eq!(health.evaluate(Some(1000), &THRESHOLDS), None);
+
+        health.record(probe(100, 995));
+        assert!(health.is_reachable());
+        assert_eq!(health.evaluate(Some(1000), &THRESHOLDS), None);
+
+        // Trailing the best head by more than the threshold
+        health.record(probe(100, 980));
+        assert_eq!(
+            health.evaluate(Some(1000), &THRESHOLDS),
+            Some(Exclusion::Lagging { blocks_behind: 20 })
+        );
+
+        // Slow once enough samples are above the latency threshold
+        for _ in 0..MIN_LATENCY_SAMPLES {
+            health.record(probe(900, 1000));
+        }
+        assert_eq!(
+            health.evaluate(Some(1000), &THRESHOLDS),
+            Some(Exclusion::Slow {
+                p90: Duration::from_millis(900)
+            })
+        );
+
+        // Failing probes
+        let mut health = ProviderHealth::default();
+        for _ in 0..MAX_PROBE_FAILURES {
+            health.record(Err("connection refused".to_string()));
+        }
+        assert!(!health.is_reachable());
+        assert_eq!(
+            health.evaluate(None, &THRESHOLDS),
+            Some(Exclusion::Unreachable {
+                failures: MAX_PROBE_FAILURES
+            })
+        );
+        health.record(probe(100, 1000));
+        assert_eq!(health.evaluate(Some(1000), &THRESHOLDS), None);
+    }
+}
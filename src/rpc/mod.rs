@@ -1,3 +1,9 @@
+pub mod error;
+pub mod health;
+pub mod prober;
 pub mod provider_manager;
 
-pub use provider_manager::ProviderManager;
+pub use error::ProviderError;
+pub use health::HealthThresholds;
+pub use prober::{verify_providers, ProviderProber};
+pub use provider_manager::{ChainProviders, ProviderManager};
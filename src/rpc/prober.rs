@@ -0,0 +1,278 @@
+use super::health::{HealthThresholds, ProbeResult};
+use super::ProviderManager;
+use crate::config::redact_rpc_url;
+use alloy::providers::{Provider, ProviderBuilder};
+use anyhow::{anyhow, Context, Result};
+use futures::future::join_all;
+use std::sync::Arc;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Maximum time a single probe call may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Periodically probes every RPC provider of a chain and takes lagging, slow or unreachable ones
+/// out of rotation
+pub struct ProviderProber {
+    chain_id: u64,
+    name: String,
+    provider_manager: Arc<ProviderManager>,
+    thresholds: HealthThresholds,
+}
+
+impl ProviderProber {
+    pub fn new(
+        chain_id: u64,
+        name: String,
+        provider_manager: Arc<ProviderManager>,
+        thresholds: HealthThresholds,
+    ) -> Self {
+        Self {
+            chain_id,
+            name,
+            provider_manager,
+            thresholds,
+        }
+    }
+
+    /// Probe the providers every `probe_interval` until `shutdown` is cancelled
+    pub async fn run(self, shutdown: CancellationToken) {
+        info!(
+            "[{}] Probing RPC providers every {:?}",
+            self.name, self.thresholds.probe_interval
+        );
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = self.probe_all() => {}
+            }
+
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sleep(self.thresholds.probe_interval) => {}
+            }
+        }
+
+        debug!("[{}] Provider prober stopped", self.name);
+    }
+
+    /// Probe every provider concurrently and update their rotation
+    async fn probe_all(&self) {
+        probe_providers(&self.provider_manager).await;
+        self.provider_manager
+            .evaluate_health(&self.thresholds)
+            .await;
+
+        let chain_id = self.chain_id.to_string();
+        for health in self.provider_manager.health_snapshot().await {
+            let labels = [
+                ("chain_id", chain_id.clone()),
+                ("provider", health.url.clone()),
+            ];
+            metrics::gauge!("rpc_provider_in_rotation", &labels)
+                .set(f64::from(u8::from(health.in_rotation)));
+            metrics::gauge!("rpc_provider_quarantined", &labels)
+                .set(f64::from(u8::from(health.quarantined_because.is_some())));
+            if let Some(p90) = health.latency_p90_ms {
+                metrics::gauge!("rpc_provider_latency_p90_ms", &labels).set(p90 as f64);
+            }
+            if let Some(blocks_behind) = health.blocks_behind {
+                metrics::gauge!("rpc_provider_blocks_behind", &labels).set(blocks_behind as f64);
+            }
+        }
+    }
+}
+
+/// Probe every provider of a manager concurrently and record the results
+async fn probe_providers(provider_manager: &ProviderManager) {
+    let urls = provider_manager.provider_urls().await;
+    let results = join_all(urls.iter().map(|url| probe(url))).await;
+
+    for (url, result) in urls.iter().zip(results) {
+        let result = result.map_err(|e| format!("{:#}", e).replace(url, &redact_rpc_url(url)));
+        provider_manager.record_probe(url, result).await;
+    }
+}
+
+/// Check every provider before a chain is indexed
+/// Providers serving another chain ID are quarantined and providers far behind the others are
+/// taken out of rotation (with `thresholds`). Fails when every provider serves another chain.
+/// Providers that cannot be reached stay in use, so an outage does not block indexing.
+pub async fn verify_providers(
+    provider_manager: &ProviderManager,
+    name: &str,
+    thresholds: Option<&HealthThresholds>,
+) -> Result<()> {
+    probe_providers(provider_manager).await;
+    if let Some(thresholds) = thresholds {
+        provider_manager.evaluate_health(thresholds).await;
+    }
+
+    let health = provider_manager.health_snapshot().await;
+    for provider in &health {
+        match (&provider.last_probe_error, provider.blocks_behind) {
+            (Some(e), _) => warn!(
+                "[{}] Could not verify provider {}: {}",
+                name, provider.url, e
+            ),
+            (None, Some(blocks_behind)) if blocks_behind > 0 => info!(
+                "[{}] Provider {} is {} blocks behind the best head",
+                name, provider.url, blocks_behind
+            ),
+            _ => {}
+        }
+    }
+
+    let quarantined = health
+        .iter()
+        .filter(|p| p.quarantined_because.is_some())
+        .count();
+    if quarantined == health.len() {
+        return Err(anyhow!("Every RPC provider serves another chain ID"));
+    }
+    info!(
+        "[{}] Verified {} RPC providers ({} quarantined)",
+        name,
+        health.len(),
+        quarantined
+    );
+    Ok(())
+}
+
+/// Call eth_blockNumber (timed) and eth_chainId on a provider
+pub async fn probe(url: &str) -> Result<ProbeResult> {
+    let provider = ProviderBuilder::new().on_http(url.parse().context("Invalid RPC URL")?);
+
+    let started = Instant::now();
+    let head_block = timeout(PROBE_TIMEOUT, provider.get_block_number())
+        .await
+        .map_err(|_| anyhow!("eth_blockNumber timed out"))?
+        .context("eth_blockNumber failed")?;
+    let latency = started.elapsed();
+
+    let chain_id = timeout(PROBE_TIMEOUT, provider.get_chain_id())
+        .await
+        .map_err(|_| anyhow!("eth_chainId timed out"))?
+        .context("eth_chainId failed")?;
+
+    Ok(ProbeResult {
+        latency,
+        head_block,
+        chain_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ProviderStrategy, RpcProvider};
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    /// Serve eth_chainId and eth_blockNumber like a provider of `chain_id` at block `head`
+    async fn mock_provider(chain_id: u64, head: u64) -> String {
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| async move {
+                let result = match request["method"].as_str() {
+                    Some("eth_chainId") => format!("0x{:x}", chain_id),
+                    _ => format!("0x{:x}", head),
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    /// Providers at `urls`, in priority order
+    fn providers(urls: &[String]) -> Vec<RpcProvider> {
+        urls.iter()
+            .enumerate()
+            .map(|(i, url)| RpcProvider {
+                url: url.clone(),
+                weight: 10,
+                priority: i as u32 + 1,
+                max_requests_per_minute: 100,
+                cooldown_on_error_ms: 60000,
+                ws_url: None,
+            })
+            .collect()
+    }
+
+    fn manager(urls: &[String]) -> ProviderManager {
+        ProviderManager::new(
+            providers(urls),
+            ProviderStrategy::Priority,
+            59141,
+            "test".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_verify_providers() {
+        let wrong_chain = mock_provider(37111, 5000).await;
+        let lagging = mock_provider(59141, 900).await;
+        let healthy = mock_provider(59141, 1000).await;
+        let manager = manager(&[wrong_chain.clone(), lagging.clone(), healthy.clone()]);
+        let thresholds = HealthThresholds {
+            probe_interval: Duration::from_secs(15),
+            max_blocks_behind: 20,
+            max_latency: Duration::from_secs(2),
+        };
+
+        verify_providers(&manager, "test", Some(&thresholds))
+            .await
+            .unwrap();
+        assert_eq!(manager.get_current_provider().await.unwrap(), healthy);
+
+        let health = manager.health_snapshot().await;
+        assert_eq!(health[0].chain_id, Some(37111));
+        assert_eq!(
+            health[0].quarantined_because.as_deref(),
+            Some("serves chain ID 37111 instead of 59141")
+        );
+        // Its far higher head does not count as the chain's head
+        assert!(!health[1].in_rotation);
+        assert_eq!(health[1].blocks_behind, Some(100));
+        assert!(health[2].in_rotation);
+        assert_eq!(health[2].blocks_behind, Some(0));
+
+        // Quarantined providers are not used even when nothing else is available
+        manager.mark_error(&healthy, "get_logs timeout").await;
+        manager.mark_error(&lagging, "get_logs timeout").await;
+        assert!(manager.get_current_provider().await.is_err());
+
+        let manager = self::manager(&[wrong_chain]);
+        assert!(verify_providers(&manager, "test", None).await.is_err());
+        assert!(manager.get_current_provider().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_reloaded_providers() {
+        let wrong_chain = mock_provider(37111, 1000).await;
+        let added = mock_provider(59141, 1000).await;
+        let healthy = mock_provider(59141, 1000).await;
+        let manager = manager(std::slice::from_ref(&healthy));
+
+        // Providers added in front by a reload are not used before their chain ID is verified
+        manager
+            .replace_providers(providers(&[wrong_chain, added.clone(), healthy.clone()]))
+            .await
+            .unwrap();
+        assert_eq!(manager.get_current_provider().await.unwrap(), healthy);
+
+        verify_providers(&manager, "test", None).await.unwrap();
+        assert_eq!(manager.get_current_provider().await.unwrap(), added);
+        let health = manager.health_snapshot().await;
+        assert_eq!(
+            health[0].quarantined_because.as_deref(),
+            Some("serves chain ID 37111 instead of 59141")
+        );
+    }
+}
@@ -1,9 +1,28 @@
-use crate::config::RpcProvider;
+use super::error::{ErrorClass, ProviderError};
+use super::health::{HealthThresholds, ProbeResult, ProviderHealth, ProviderHealthSnapshot};
+use crate::config::{redact_rpc_url, ProviderStrategy, RpcProvider};
 use anyhow::{anyhow, Result};
+use dashmap::DashMap;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
+
+/// Weight of the latest sample in a provider's average latency
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// The sticky strategy leaves a provider once its average latency is this many times the fastest
+const STICKY_DEGRADATION_FACTOR: f64 = 2.0;
+
+/// Upper bound of escalated cooldowns, and the cooldown after fatal errors
+const MAX_COOLDOWN: Duration = Duration::from_secs(1800);
+
+/// Cooldown after a provider did not have the requested block (it is usually just behind)
+const DATA_MISSING_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Quarantine reason of providers added by a reload until a probe confirms their chain ID
+const UNVERIFIED: &str = "chain ID not verified yet";
 
 /// State for a single RPC provider
 #[derive(Debug, Clone)]
@@ -13,8 +32,20 @@ struct ProviderState {
     requests_this_minute: u32,    // Requests in current minute window
     minute_window_start: Instant, // Start of current minute window
     last_error: Option<Instant>,
+    last_error_class: Option<ErrorClass>,
+    /// Circuit open: no requests until `cooldown` has passed since the last error
     in_cooldown: bool,
+    cooldown: Duration,
+    /// Cooldown expired: the next request or probe decides whether the circuit closes
+    half_open: bool,
     consecutive_errors: u32,
+    /// Results of the background prober
+    health: ProviderHealth,
+    /// Exponentially weighted average latency of requests and probes
+    latency_ewma: Option<Duration>,
+    /// Set when a probe found the provider serving another chain, or while a provider added by a
+    /// reload has not been probed yet; it is never used then
+    quarantine: Option<String>,
 }
 
 impl ProviderState {
@@ -25,8 +56,14 @@ impl ProviderState {
             requests_this_minute: 0,
             minute_window_start: Instant::now(),
             last_error: None,
+            last_error_class: None,
             in_cooldown: false,
+            cooldown: Duration::ZERO,
+            half_open: false,
             consecutive_errors: 0,
+            health: ProviderHealth::default(),
+            latency_ewma: None,
+            quarantine: None,
         }
     }
 
@@ -45,6 +82,75 @@ impl ProviderState {
         }
     }
 
+    /// Whether the prober left the provider in rotation
+    fn in_rotation(&self) -> bool {
+        self.health.exclusion.is_none() && self.quarantine.is_none()
+    }
+
+    /// Check if provider can be selected
+    /// Providers out of rotation are only used while no provider in rotation is available, and
+    /// quarantined providers never are
+    fn is_usable(&self, any_in_rotation: bool) -> bool {
+        self.is_available() && self.quarantine.is_none() && (self.in_rotation() || !any_in_rotation)
+    }
+
+    /// Fold a request or probe latency into the average latency
+    fn record_latency(&mut self, latency: Duration) {
+        self.latency_ewma = Some(match self.latency_ewma {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_EWMA_ALPHA) + latency.mul_f64(LATENCY_EWMA_ALPHA)
+            }
+            None => latency,
+        });
+    }
+
+    /// Open the circuit after a failed request or probe; returns the cooldown
+    fn trip(&mut self, error: &ProviderError) -> Duration {
+        self.consecutive_errors += 1;
+        self.cooldown = cooldown_for(
+            Duration::from_millis(self.provider.cooldown_on_error_ms),
+            error,
+            self.consecutive_errors,
+        );
+        self.last_error = Some(Instant::now());
+        self.last_error_class = Some(error.class);
+        self.in_cooldown = true;
+        self.half_open = false;
+        self.cooldown
+    }
+
+    /// Close the circuit after a successful request or probe; returns whether it was half-open
+    fn close(&mut self) -> bool {
+        let was_half_open = self.half_open;
+        self.half_open = false;
+        self.consecutive_errors = 0;
+        was_half_open
+    }
+
+    /// Move the circuit to half-open once the cooldown expired; returns whether it did
+    fn update_cooldown(&mut self) -> bool {
+        let expired = self.in_cooldown
+            && self
+                .last_error
+                .is_some_and(|last_error| last_error.elapsed() >= self.cooldown);
+        if expired {
+            self.in_cooldown = false;
+            self.half_open = true;
+        }
+        expired
+    }
+
+    /// Circuit breaker state as reported by the API
+    fn circuit(&self) -> &'static str {
+        if self.in_cooldown {
+            "open"
+        } else if self.half_open {
+            "half_open"
+        } else {
+            "closed"
+        }
+    }
+
     /// Update minute window if needed
     fn update_minute_window(&mut self) {
         let elapsed = self.minute_window_start.elapsed();
@@ -69,12 +175,20 @@ impl ProviderState {
 pub struct ProviderManager {
     providers: Arc<RwLock<Vec<ProviderState>>>,
     current_index: Arc<RwLock<usize>>,
+    strategy: Arc<RwLock<ProviderStrategy>>,
+    /// Chain the providers must serve
+    chain_id: u64,
     chain_name: String,
 }
 
 impl ProviderManager {
     /// Create a new ProviderManager from a list of RPC providers
-    pub fn new(providers: Vec<RpcProvider>, chain_name: String) -> Result<Self> {
+    pub fn new(
+        providers: Vec<RpcProvider>,
+        strategy: ProviderStrategy,
+        chain_id: u64,
+        chain_name: String,
+    ) -> Result<Self> {
         if providers.is_empty() {
             return Err(anyhow!(
                 "No RPC providers configured for chain {}",
@@ -92,9 +206,10 @@ impl ProviderManager {
             .collect();
 
         info!(
-            "[{}] Initialized ProviderManager with {} providers",
+            "[{}] Initialized ProviderManager with {} providers ({:?} strategy)",
             chain_name,
-            provider_states.len()
+            provider_states.len(),
+            strategy
         );
 
         for (i, state) in provider_states.iter().enumerate() {
@@ -111,12 +226,27 @@ impl ProviderManager {
         Ok(Self {
             providers: Arc::new(RwLock::new(provider_states)),
             current_index: Arc::new(RwLock::new(0)),
+            strategy: Arc::new(RwLock::new(strategy)),
+            chain_id,
             chain_name,
         })
     }
 
+    /// Change how providers are selected (e.g. after chains.yaml was reloaded)
+    pub async fn set_strategy(&self, strategy: ProviderStrategy) {
+        let mut current = self.strategy.write().await;
+        if *current != strategy {
+            info!(
+                "[{}] Switching provider strategy from {:?} to {:?}",
+                self.chain_name, *current, strategy
+            );
+            *current = strategy;
+        }
+    }
+
     /// Get the current RPC provider URL
     pub async fn get_current_provider(&self) -> Result<String> {
+        let strategy = *self.strategy.read().await;
         let mut providers = self.providers.write().await;
         let mut current_index = self.current_index.write().await;
 
@@ -125,24 +255,43 @@ impl ProviderManager {
             provider.update_minute_window();
 
             // Check if cooldown expired
-            if provider.in_cooldown {
-                if let Some(last_error) = provider.last_error {
-                    let cooldown_duration =
-                        Duration::from_millis(provider.provider.cooldown_on_error_ms);
-                    if last_error.elapsed() >= cooldown_duration {
-                        provider.in_cooldown = false;
-                        provider.consecutive_errors = 0;
-                        info!(
-                            "[{}] Provider {} recovered from cooldown",
-                            self.chain_name, provider.provider.url
-                        );
-                    }
-                }
+            if provider.update_cooldown() {
+                info!(
+                    "[{}] Provider {} cooldown expired, trying it again",
+                    self.chain_name,
+                    redact_rpc_url(&provider.provider.url)
+                );
             }
         }
 
         // Find next available provider
         let total_providers = providers.len();
+        let any_in_rotation = any_in_rotation(&providers);
+
+        if strategy != ProviderStrategy::RoundRobin {
+            if let Some(index) = select_provider(
+                strategy,
+                &providers,
+                *current_index,
+                any_in_rotation,
+                rand::random(),
+            ) {
+                if index != *current_index {
+                    debug!(
+                        "[{}] Switching from provider {} to {} ({:?} strategy)",
+                        self.chain_name, *current_index, index, strategy
+                    );
+                    *current_index = index;
+                }
+                return Ok(providers[index].provider.url.clone());
+            }
+            return Err(anyhow!(
+                "[{}] All {} RPC providers are unavailable (rate limited or in cooldown)",
+                self.chain_name,
+                total_providers
+            ));
+        }
+
         let mut attempts = 0;
 
         while attempts < total_providers {
@@ -166,7 +315,7 @@ impl ProviderManager {
             // Check if current provider is available
             let is_available = {
                 let current = &providers[*current_index];
-                current.is_available()
+                current.is_usable(any_in_rotation)
             };
 
             if is_available {
@@ -178,11 +327,12 @@ impl ProviderManager {
             {
                 let current = &providers[*current_index];
                 warn!(
-                    "[{}] Provider {} unavailable (cooldown={}, rate_limited={}), trying next",
+                    "[{}] Provider {} unavailable (cooldown={}, rate_limited={}, in_rotation={}), trying next",
                     self.chain_name,
                     *current_index,
                     current.in_cooldown,
-                    !current.is_available()
+                    !current.in_cooldown && !current.is_available(),
+                    current.in_rotation()
                 );
             }
             *current_index = (*current_index + 1) % total_providers;
@@ -197,76 +347,323 @@ impl ProviderManager {
         ))
     }
 
-    /// Mark a successful request
-    pub async fn mark_success(&self) {
+    /// Mark a successful request to `url` that took `latency`
+    pub async fn mark_success(&self, url: &str, latency: Duration) {
         let mut providers = self.providers.write().await;
-        let current_index = self.current_index.read().await;
+        let Some(index) = providers.iter().position(|p| p.provider.url == url) else {
+            return;
+        };
 
-        if let Some(provider) = providers.get_mut(*current_index) {
-            provider.request_count += 1;
-            provider.requests_this_minute += 1;
-            provider.consecutive_errors = 0;
-
-            debug!(
-                "[{}] Provider {} request #{} successful (weight: {}/{})",
-                self.chain_name,
-                *current_index,
-                provider.request_count,
-                provider.request_count,
-                provider.provider.weight
-            );
+        let provider = &mut providers[index];
+        provider.request_count += 1;
+        provider.requests_this_minute += 1;
+        provider.record_latency(latency);
+        if provider.close() {
+            info!("[{}] Provider {} recovered", self.chain_name, index);
         }
+
+        debug!(
+            "[{}] Provider {} request #{} successful (weight: {}/{})",
+            self.chain_name,
+            index,
+            provider.request_count,
+            provider.request_count,
+            provider.provider.weight
+        );
     }
 
-    /// Mark a failed request (triggers cooldown)
-    pub async fn mark_error(&self, error_msg: &str) {
+    /// Mark a failed request to `url` (triggers a cooldown depending on the error class)
+    /// Rotates away from it if it is still the current provider
+    /// Plain messages are classified from their text
+    pub async fn mark_error(&self, url: &str, error: impl Into<ProviderError>) {
+        let error = error.into();
         let mut providers = self.providers.write().await;
         let mut current_index_lock = self.current_index.write().await;
+        let Some(failed_index) = providers.iter().position(|p| p.provider.url == url) else {
+            return;
+        };
+
+        let provider = &mut providers[failed_index];
+        let cooldown = provider.trip(&error);
+
+        warn!(
+            "[{}] Provider {} failed: {} (consecutive errors: {}, cooldown: {}ms)",
+            self.chain_name,
+            failed_index,
+            error,
+            provider.consecutive_errors,
+            cooldown.as_millis()
+        );
+
+        // Reset count and rotate to next provider
+        provider.reset_count();
+
+        // The rotation already moved on since the request was sent
         let current_index = *current_index_lock;
+        if current_index != failed_index {
+            return;
+        }
 
-        if let Some(provider) = providers.get_mut(current_index) {
-            provider.last_error = Some(Instant::now());
-            provider.consecutive_errors += 1;
-            provider.in_cooldown = true;
+        // Find next available provider
+        let start_index = current_index;
+        let total_providers = providers.len();
+        let mut next_index = (current_index + 1) % total_providers;
+        let any_in_rotation = any_in_rotation(&providers);
+        let mut attempts = 0;
 
-            warn!(
-                "[{}] Provider {} failed: {} (consecutive errors: {}, cooldown: {}ms)",
-                self.chain_name,
-                current_index,
-                error_msg,
-                provider.consecutive_errors,
-                provider.provider.cooldown_on_error_ms
-            );
+        while attempts < total_providers && next_index != start_index {
+            if providers[next_index].is_usable(any_in_rotation) {
+                info!(
+                    "[{}] Rotating to provider {} after error",
+                    self.chain_name, next_index
+                );
+                *current_index_lock = next_index;
+                return;
+            }
+            next_index = (next_index + 1) % total_providers;
+            attempts += 1;
+        }
+
+        warn!(
+            "[{}] No other providers available after error, staying on provider {}",
+            self.chain_name, current_index
+        );
+    }
+
+    /// Replace the provider list in place (e.g. after chains.yaml was reloaded)
+    /// Providers whose URL is unchanged keep their rotation, rate limit and cooldown state, and the
+    /// current provider stays selected if it is still configured. New providers are quarantined
+    /// until a probe confirms their chain ID (see `verify_providers`).
+    pub async fn replace_providers(&self, providers: Vec<RpcProvider>) -> Result<()> {
+        if providers.is_empty() {
+            return Err(anyhow!(
+                "No RPC providers configured for chain {}",
+                self.chain_name
+            ));
+        }
+
+        let mut sorted_providers = providers;
+        sorted_providers.sort_by_key(|p| p.priority);
+
+        let mut states = self.providers.write().await;
+        let mut current_index = self.current_index.write().await;
+        let current_url = states
+            .get(*current_index)
+            .map(|state| state.provider.url.clone());
+
+        let new_states: Vec<ProviderState> = sorted_providers
+            .into_iter()
+            .map(
+                |provider| match states.iter().find(|s| s.provider.url == provider.url) {
+                    Some(existing) => ProviderState {
+                        provider,
+                        ..existing.clone()
+                    },
+                    None => ProviderState {
+                        quarantine: Some(UNVERIFIED.to_string()),
+                        ..ProviderState::new(provider)
+                    },
+                },
+            )
+            .collect();
+
+        *current_index = current_url
+            .and_then(|url| new_states.iter().position(|s| s.provider.url == url))
+            .unwrap_or(0);
+        *states = new_states;
+
+        info!(
+            "[{}] Replaced RPC providers ({} configured)",
+            self.chain_name,
+            states.len()
+        );
+
+        Ok(())
+    }
 
-            // Reset count and rotate to next provider
-            provider.reset_count();
+    /// URLs of the configured providers, in priority order
+    pub async fn provider_urls(&self) -> Vec<String> {
+        let providers = self.providers.read().await;
+        providers.iter().map(|p| p.provider.url.clone()).collect()
+    }
+
+    /// URLs of the providers usable besides `primary`, in priority order
+    /// Used to cross-check an answer of the current provider
+    pub async fn other_usable_providers(&self, primary: &str) -> Vec<String> {
+        let providers = self.providers.read().await;
+        let any_in_rotation = any_in_rotation(&providers);
+        providers
+            .iter()
+            .filter(|p| p.provider.url != primary && p.is_usable(any_in_rotation))
+            .map(|p| p.provider.url.clone())
+            .collect()
+    }
 
-            // Find next available provider
-            let start_index = current_index;
-            let total_providers = providers.len();
-            let mut next_index = (current_index + 1) % total_providers;
-            let mut attempts = 0;
+    /// Record the outcome of a request sent to a given provider outside the rotation
+    /// Failures open its circuit like failed requests of the current provider, without rotating
+    pub async fn record_request(&self, url: &str, result: Result<Duration, ProviderError>) {
+        let mut providers = self.providers.write().await;
+        let Some(provider) = providers.iter_mut().find(|p| p.provider.url == url) else {
+            return;
+        };
 
-            while attempts < total_providers && next_index != start_index {
-                if providers[next_index].is_available() {
+        provider.requests_this_minute += 1;
+        match result {
+            Ok(latency) => {
+                provider.record_latency(latency);
+                if provider.close() {
                     info!(
-                        "[{}] Rotating to provider {} after error",
-                        self.chain_name, next_index
+                        "[{}] Provider {} recovered",
+                        self.chain_name,
+                        redact_rpc_url(url)
+                    );
+                }
+            }
+            Err(error) => {
+                let cooldown = provider.trip(&error);
+                warn!(
+                    "[{}] Provider {} failed: {} (consecutive errors: {}, cooldown: {}ms)",
+                    self.chain_name,
+                    redact_rpc_url(url),
+                    error,
+                    provider.consecutive_errors,
+                    cooldown.as_millis()
+                );
+            }
+        }
+    }
+
+    /// Record the outcome of a background probe of a provider
+    pub async fn record_probe(&self, url: &str, result: Result<ProbeResult, String>) {
+        let mut providers = self.providers.write().await;
+        if let Some(state) = providers.iter_mut().find(|p| p.provider.url == url) {
+            // Providers serving another chain are quarantined until a probe finds the right one
+            if let Ok(probe) = &result {
+                let reason = format!(
+                    "serves chain ID {} instead of {}",
+                    probe.chain_id, self.chain_id
+                );
+                if probe.chain_id != self.chain_id && state.quarantine.as_ref() != Some(&reason) {
+                    error!(
+                        "[{}] Quarantining provider {}: it serves chain ID {} instead of {}",
+                        self.chain_name,
+                        redact_rpc_url(url),
+                        probe.chain_id,
+                        self.chain_id
                     );
-                    *current_index_lock = next_index;
-                    return;
+                    state.quarantine = Some(reason);
+                } else if probe.chain_id == self.chain_id {
+                    match state.quarantine.take().as_deref() {
+                        Some(UNVERIFIED) => info!(
+                            "[{}] Provider {} serves chain ID {}, putting it in rotation",
+                            self.chain_name,
+                            redact_rpc_url(url),
+                            self.chain_id
+                        ),
+                        Some(_) => info!(
+                            "[{}] Provider {} serves chain ID {} again, lifting quarantine",
+                            self.chain_name,
+                            redact_rpc_url(url),
+                            self.chain_id
+                        ),
+                        None => {}
+                    }
                 }
-                next_index = (next_index + 1) % total_providers;
-                attempts += 1;
             }
 
+            // Probes of half-open providers decide whether their circuit closes
+            match &result {
+                Ok(probe) => {
+                    state.record_latency(probe.latency);
+                    if state.close() {
+                        info!(
+                            "[{}] Provider {} recovered (probe succeeded)",
+                            self.chain_name,
+                            redact_rpc_url(url)
+                        );
+                    }
+                }
+                Err(e) if state.half_open => {
+                    let cooldown = state.trip(&ProviderError::classify(e.as_str()));
+                    warn!(
+                        "[{}] Provider {} probe failed while half-open, cooling down for {}ms",
+                        self.chain_name,
+                        redact_rpc_url(url),
+                        cooldown.as_millis()
+                    );
+                }
+                Err(_) => {}
+            }
+            state.health.record(result);
+        }
+    }
+
+    /// Take providers out of rotation, or put them back, based on their probe results
+    /// Lag is measured against the highest head among providers whose last probe succeeded
+    pub async fn evaluate_health(&self, thresholds: &HealthThresholds) {
+        let mut providers = self.providers.write().await;
+        let best_head = best_head(&providers);
+
+        for state in providers.iter_mut() {
+            let exclusion = state.health.evaluate(best_head, thresholds);
+            match (&state.health.exclusion, &exclusion) {
+                (None, Some(reason)) => warn!(
+                    "[{}] Taking provider {} out of rotation: {}",
+                    self.chain_name,
+                    redact_rpc_url(&state.provider.url),
+                    reason
+                ),
+                (Some(_), None) => info!(
+                    "[{}] Provider {} is healthy again, back in rotation",
+                    self.chain_name,
+                    redact_rpc_url(&state.provider.url)
+                ),
+                _ => {}
+            }
+            state.health.exclusion = exclusion;
+        }
+
+        if !providers.is_empty() && !any_in_rotation(&providers) {
             warn!(
-                "[{}] No other providers available after error, staying on provider {}",
-                self.chain_name, current_index
+                "[{}] Every RPC provider is unhealthy, using them anyway",
+                self.chain_name
             );
         }
     }
 
+    /// Probe results of every provider, in priority order
+    pub async fn health_snapshot(&self) -> Vec<ProviderHealthSnapshot> {
+        let providers = self.providers.read().await;
+        let best_head = best_head(&providers);
+        let millis = |latency: Option<Duration>| latency.map(|l| l.as_millis() as u64);
+
+        providers
+            .iter()
+            .map(|state| {
+                let health = &state.health;
+                ProviderHealthSnapshot {
+                    url: redact_rpc_url(&state.provider.url),
+                    in_rotation: state.in_rotation(),
+                    quarantined_because: state.quarantine.clone(),
+                    chain_id: health.chain_id,
+                    excluded_because: health.exclusion.map(|reason| reason.to_string()),
+                    in_cooldown: state.in_cooldown,
+                    circuit: state.circuit(),
+                    consecutive_errors: state.consecutive_errors,
+                    last_error_class: state.last_error_class.map(|class| class.as_str()),
+                    head_block: health.head_block,
+                    blocks_behind: health.blocks_behind(best_head),
+                    latency_p50_ms: millis(health.latencies.percentile(0.5)),
+                    latency_p90_ms: millis(health.latencies.percentile(0.9)),
+                    latency_p99_ms: millis(health.latencies.percentile(0.99)),
+                    probes: health.latencies.len(),
+                    last_probe_secs_ago: health.last_probe.map(|at| at.elapsed().as_secs()),
+                    last_probe_error: health.last_probe_error.clone(),
+                }
+            })
+            .collect()
+    }
+
     /// Get statistics for monitoring
     #[allow(dead_code)]
     pub async fn get_stats(&self) -> ProviderStats {
@@ -275,16 +672,7 @@ impl ProviderManager {
 
         // Update cooldown status before counting
         for provider in providers.iter_mut() {
-            if provider.in_cooldown {
-                if let Some(last_error) = provider.last_error {
-                    let cooldown_duration =
-                        Duration::from_millis(provider.provider.cooldown_on_error_ms);
-                    if last_error.elapsed() >= cooldown_duration {
-                        provider.in_cooldown = false;
-                        provider.consecutive_errors = 0;
-                    }
-                }
-            }
+            provider.update_cooldown();
         }
 
         let total = providers.len();
@@ -304,6 +692,128 @@ impl ProviderManager {
     }
 }
 
+/// Provider managers of the supervised chains
+/// Clones share the managers, so chains started or stopped on config reload are seen by the API
+#[derive(Clone, Default)]
+pub struct ChainProviders {
+    chains: Arc<DashMap<u64, Arc<ProviderManager>>>,
+}
+
+impl ChainProviders {
+    pub fn insert(&self, chain_id: u64, provider_manager: Arc<ProviderManager>) {
+        self.chains.insert(chain_id, provider_manager);
+    }
+
+    pub fn remove(&self, chain_id: u64) {
+        self.chains.remove(&chain_id);
+    }
+
+    pub fn get(&self, chain_id: u64) -> Option<Arc<ProviderManager>> {
+        self.chains
+            .get(&chain_id)
+            .map(|provider_manager| provider_manager.clone())
+    }
+}
+
+/// Cooldown after an error, given the provider's configured base cooldown
+/// Transient errors and rate limits without a retry hint double the cooldown with every
+/// consecutive error; fatal errors go straight to the maximum
+fn cooldown_for(base: Duration, error: &ProviderError, consecutive_errors: u32) -> Duration {
+    let escalated = || {
+        base.saturating_mul(2u32.saturating_pow(consecutive_errors.saturating_sub(1)))
+            .min(MAX_COOLDOWN.max(base))
+    };
+
+    match error.class {
+        ErrorClass::RateLimited => error
+            .retry_after
+            .map_or_else(escalated, |retry_after| retry_after.min(MAX_COOLDOWN)),
+        ErrorClass::Transient => escalated(),
+        ErrorClass::Fatal => MAX_COOLDOWN.max(base),
+        ErrorClass::DataMissing => DATA_MISSING_COOLDOWN.min(base),
+    }
+}
+
+/// Index of the provider to use next under a strategy other than round robin
+/// `random` (in `0..1`) drives the weighted random strategy. Unmeasured providers count as the
+/// fastest, so each gets tried before the latency based strategies settle.
+fn select_provider(
+    strategy: ProviderStrategy,
+    providers: &[ProviderState],
+    current_index: usize,
+    any_in_rotation: bool,
+    random: f64,
+) -> Option<usize> {
+    let usable = || {
+        providers
+            .iter()
+            .enumerate()
+            .filter(move |(_, p)| p.is_usable(any_in_rotation))
+    };
+    let fastest = || {
+        usable()
+            .min_by_key(|(_, p)| p.latency_ewma.unwrap_or(Duration::ZERO))
+            .map(|(i, _)| i)
+    };
+
+    match strategy {
+        ProviderStrategy::RoundRobin | ProviderStrategy::Priority => {
+            usable().next().map(|(i, _)| i)
+        }
+        ProviderStrategy::WeightedRandom => {
+            let total: u64 = usable()
+                .map(|(_, p)| u64::from(p.provider.weight.max(1)))
+                .sum();
+            let mut target = (random.clamp(0.0, 1.0) * total as f64) as u64;
+            let mut last = None;
+            for (i, p) in usable() {
+                let weight = u64::from(p.provider.weight.max(1));
+                if target < weight {
+                    return Some(i);
+                }
+                target -= weight;
+                last = Some(i);
+            }
+            last
+        }
+        ProviderStrategy::LeastLatency => fastest(),
+        ProviderStrategy::Sticky => {
+            let best = usable().filter_map(|(_, p)| p.latency_ewma).min();
+            let stay = providers.get(current_index).is_some_and(|current| {
+                current.is_usable(any_in_rotation)
+                    && match (current.latency_ewma, best) {
+                        (Some(latency), Some(best)) => {
+                            latency.as_secs_f64() <= best.as_secs_f64() * STICKY_DEGRADATION_FACTOR
+                        }
+                        _ => true,
+                    }
+            });
+            if stay {
+                Some(current_index)
+            } else {
+                fastest()
+            }
+        }
+    }
+}
+
+/// Whether any rate limit and cooldown permitting provider is in rotation
+fn any_in_rotation(providers: &[ProviderState]) -> bool {
+    providers
+        .iter()
+        .any(|p| p.is_available() && p.in_rotation())
+}
+
+/// Highest head block among providers whose last probe succeeded
+/// Quarantined providers serve another chain, so their head says nothing about this one
+fn best_head(providers: &[ProviderState]) -> Option<u64> {
+    providers
+        .iter()
+        .filter(|p| p.quarantine.is_none() && p.health.is_reachable())
+        .filter_map(|p| p.health.head_block)
+        .max()
+}
+
 /// Statistics about provider manager status
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -328,6 +838,7 @@ mod tests {
                 priority: 1,
                 max_requests_per_minute: 10,
                 cooldown_on_error_ms: 1000,
+                ws_url: None,
             },
             RpcProvider {
                 url: "http://provider2.com".to_string(),
@@ -335,18 +846,29 @@ mod tests {
                 priority: 2,
                 max_requests_per_minute: 10,
                 cooldown_on_error_ms: 1000,
+                ws_url: None,
             },
         ];
 
-        let manager = ProviderManager::new(providers, "test".to_string()).unwrap();
+        let manager = ProviderManager::new(
+            providers,
+            ProviderStrategy::RoundRobin,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
 
         // First provider
         let url1 = manager.get_current_provider().await.unwrap();
         assert_eq!(url1, "http://provider1.com");
 
         // Mark 2 successful requests (reaches weight)
-        manager.mark_success().await;
-        manager.mark_success().await;
+        manager
+            .mark_success(&url1, Duration::from_millis(100))
+            .await;
+        manager
+            .mark_success(&url1, Duration::from_millis(100))
+            .await;
 
         // Should rotate to provider2
         let url2 = manager.get_current_provider().await.unwrap();
@@ -362,6 +884,7 @@ mod tests {
                 priority: 1,
                 max_requests_per_minute: 10,
                 cooldown_on_error_ms: 100,
+                ws_url: None,
             },
             RpcProvider {
                 url: "http://provider2.com".to_string(),
@@ -369,17 +892,24 @@ mod tests {
                 priority: 2,
                 max_requests_per_minute: 10,
                 cooldown_on_error_ms: 100,
+                ws_url: None,
             },
         ];
 
-        let manager = ProviderManager::new(providers, "test".to_string()).unwrap();
+        let manager = ProviderManager::new(
+            providers,
+            ProviderStrategy::RoundRobin,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
 
         // First provider
         let url1 = manager.get_current_provider().await.unwrap();
         assert_eq!(url1, "http://provider1.com");
 
         // Mark error - should failover to provider2
-        manager.mark_error("test error").await;
+        manager.mark_error(&url1, "test error").await;
         let url2 = manager.get_current_provider().await.unwrap();
         assert_eq!(url2, "http://provider2.com");
 
@@ -387,6 +917,13 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(150)).await;
         let stats = manager.get_stats().await;
         assert_eq!(stats.available_providers, 2);
+
+        // A late error of a request sent to provider1 does not rotate away from provider2
+        manager.mark_error(&url1, "test error").await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider2.com"
+        );
     }
 
     #[tokio::test]
@@ -397,16 +934,447 @@ mod tests {
             priority: 1,
             max_requests_per_minute: 2, // Very low limit
             cooldown_on_error_ms: 1000,
+            ws_url: None,
         }];
 
-        let manager = ProviderManager::new(providers, "test".to_string()).unwrap();
+        let manager = ProviderManager::new(
+            providers,
+            ProviderStrategy::RoundRobin,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
 
         // Make 2 requests (hits limit)
-        manager.mark_success().await;
-        manager.mark_success().await;
+        let url = "http://provider1.com";
+        manager.mark_success(url, Duration::from_millis(100)).await;
+        manager.mark_success(url, Duration::from_millis(100)).await;
 
         // Third request should fail due to rate limit
         let result = manager.get_current_provider().await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_replace_providers() {
+        let provider = |url: &str, priority: u32| RpcProvider {
+            url: url.to_string(),
+            weight: 10,
+            priority,
+            max_requests_per_minute: 10,
+            cooldown_on_error_ms: 60000,
+            ws_url: None,
+        };
+
+        let manager = ProviderManager::new(
+            vec![
+                provider("http://provider1.com", 1),
+                provider("http://provider2.com", 2),
+            ],
+            ProviderStrategy::RoundRobin,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        // provider1 goes into cooldown, provider2 becomes current
+        manager
+            .mark_error("http://provider1.com", "test error")
+            .await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider2.com"
+        );
+
+        // Add a provider in front; the current provider and provider1's cooldown are kept
+        manager
+            .replace_providers(vec![
+                provider("http://provider0.com", 0),
+                provider("http://provider1.com", 1),
+                provider("http://provider2.com", 2),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider2.com"
+        );
+        let stats = manager.get_stats().await;
+        assert_eq!(stats.total_providers, 3);
+        assert_eq!(stats.cooldown_providers, 1);
+
+        // The new provider is not used before a probe confirmed its chain ID, even when it is the
+        // only one left
+        let probe = |chain_id: u64| {
+            Ok(ProbeResult {
+                latency: Duration::from_millis(10),
+                head_block: 1000,
+                chain_id,
+            })
+        };
+        manager
+            .replace_providers(vec![provider("http://provider0.com", 0)])
+            .await
+            .unwrap();
+        assert!(manager.get_current_provider().await.is_err());
+        manager
+            .record_probe("http://provider0.com", probe(59141))
+            .await;
+        assert!(manager.get_current_provider().await.is_err());
+        assert_eq!(
+            manager.health_snapshot().await[0]
+                .quarantined_because
+                .as_deref(),
+            Some("serves chain ID 59141 instead of 11155111")
+        );
+
+        // Once confirmed, removing the current provider falls back to the highest priority one
+        manager
+            .record_probe("http://provider0.com", probe(11155111))
+            .await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider0.com"
+        );
+
+        assert!(manager.replace_providers(vec![]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unhealthy_providers_out_of_rotation() {
+        let provider = |url: &str, priority: u32| RpcProvider {
+            url: url.to_string(),
+            weight: 10,
+            priority,
+            max_requests_per_minute: 100,
+            cooldown_on_error_ms: 60000,
+            ws_url: None,
+        };
+        let thresholds = HealthThresholds {
+            probe_interval: Duration::from_secs(15),
+            max_blocks_behind: 10,
+            max_latency: Duration::from_millis(500),
+        };
+        let probe = |head_block: u64| {
+            Ok(ProbeResult {
+                latency: Duration::from_millis(100),
+                head_block,
+                chain_id: 11155111,
+            })
+        };
+
+        let manager = ProviderManager::new(
+            vec![
+                provider("http://provider1.com", 1),
+                provider("http://provider2.com", 2),
+            ],
+            ProviderStrategy::RoundRobin,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        // provider1 lags behind provider2 and is skipped
+        manager
+            .record_probe("http://provider1.com", probe(950))
+            .await;
+        manager
+            .record_probe("http://provider2.com", probe(1000))
+            .await;
+        manager.evaluate_health(&thresholds).await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider2.com"
+        );
+
+        let health = manager.health_snapshot().await;
+        assert!(!health[0].in_rotation);
+        assert_eq!(health[0].blocks_behind, Some(50));
+        assert_eq!(
+            health[0].excluded_because.as_deref(),
+            Some("50 blocks behind")
+        );
+        assert!(health[1].in_rotation);
+        assert_eq!(health[1].latency_p50_ms, Some(100));
+
+        // With the provider in rotation in cooldown, the excluded one is used anyway
+        manager
+            .mark_error("http://provider2.com", "test error")
+            .await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider1.com"
+        );
+
+        // Caught up providers go back into rotation
+        manager
+            .record_probe("http://provider1.com", probe(1000))
+            .await;
+        manager.evaluate_health(&thresholds).await;
+        assert!(manager
+            .health_snapshot()
+            .await
+            .iter()
+            .all(|p| p.in_rotation));
+    }
+
+    fn strategy_providers(weights: &[u32]) -> Vec<RpcProvider> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| RpcProvider {
+                url: format!("http://provider{}.com", i + 1),
+                weight: *weight,
+                priority: i as u32 + 1,
+                max_requests_per_minute: 10000,
+                cooldown_on_error_ms: 100,
+                ws_url: None,
+            })
+            .collect()
+    }
+
+    /// Send a request through the manager to providers answering with the given latencies
+    async fn simulate_request(manager: &ProviderManager, latencies_ms: &[u64]) -> usize {
+        let url = manager.get_current_provider().await.unwrap();
+        let index: usize = url["http://provider".len()..url.len() - ".com".len()]
+            .parse::<usize>()
+            .unwrap()
+            - 1;
+        manager
+            .mark_success(&url, Duration::from_millis(latencies_ms[index]))
+            .await;
+        index
+    }
+
+    #[tokio::test]
+    async fn test_priority_strategy() {
+        let manager = ProviderManager::new(
+            strategy_providers(&[2, 2]),
+            ProviderStrategy::Priority,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        // The primary is kept past its weight
+        for _ in 0..5 {
+            assert_eq!(simulate_request(&manager, &[100, 50]).await, 0);
+        }
+
+        // Failover while the primary cools down, then back to it
+        manager
+            .mark_error("http://provider1.com", "test error")
+            .await;
+        assert_eq!(simulate_request(&manager, &[100, 50]).await, 1);
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(simulate_request(&manager, &[100, 50]).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_random_strategy() {
+        let manager = ProviderManager::new(
+            strategy_providers(&[3, 1]),
+            ProviderStrategy::WeightedRandom,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        let mut counts = [0; 2];
+        for _ in 0..2000 {
+            counts[simulate_request(&manager, &[100, 100]).await] += 1;
+        }
+        // 1500 expected for provider1; far outside any plausible random deviation
+        assert!((1300..=1700).contains(&counts[0]), "{:?}", counts);
+
+        // Selection is proportional to the weights
+        let states: Vec<ProviderState> = strategy_providers(&[3, 1])
+            .into_iter()
+            .map(ProviderState::new)
+            .collect();
+        let select =
+            |random| select_provider(ProviderStrategy::WeightedRandom, &states, 0, true, random);
+        assert_eq!(select(0.0), Some(0));
+        assert_eq!(select(0.74), Some(0));
+        assert_eq!(select(0.76), Some(1));
+        assert_eq!(select(1.0), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_least_latency_strategy() {
+        let manager = ProviderManager::new(
+            strategy_providers(&[1, 1, 1]),
+            ProviderStrategy::LeastLatency,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        // Each provider is measured once, then the fastest one is used
+        let latencies = [300, 100, 200];
+        assert_eq!(simulate_request(&manager, &latencies).await, 0);
+        assert_eq!(simulate_request(&manager, &latencies).await, 1);
+        assert_eq!(simulate_request(&manager, &latencies).await, 2);
+        for _ in 0..5 {
+            assert_eq!(simulate_request(&manager, &latencies).await, 1);
+        }
+
+        // The fastest provider slows down until the third one overtakes it
+        let latencies = [300, 1000, 200];
+        assert_eq!(simulate_request(&manager, &latencies).await, 1);
+        assert_eq!(simulate_request(&manager, &latencies).await, 2);
+
+        // Probe latencies count too
+        for _ in 0..2 {
+            manager
+                .record_probe(
+                    "http://provider1.com",
+                    Ok(ProbeResult {
+                        latency: Duration::from_millis(10),
+                        head_block: 1000,
+                        chain_id: 11155111,
+                    }),
+                )
+                .await;
+        }
+        assert_eq!(simulate_request(&manager, &latencies).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sticky_strategy() {
+        let manager = ProviderManager::new(
+            strategy_providers(&[1, 1]),
+            ProviderStrategy::Sticky,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+
+        // Slightly slower than the other provider is not enough to leave it
+        for _ in 0..5 {
+            assert_eq!(simulate_request(&manager, &[150, 100]).await, 0);
+        }
+        manager
+            .record_probe(
+                "http://provider2.com",
+                Ok(ProbeResult {
+                    latency: Duration::from_millis(100),
+                    head_block: 1000,
+                    chain_id: 11155111,
+                }),
+            )
+            .await;
+        assert_eq!(simulate_request(&manager, &[150, 100]).await, 0);
+
+        // Degrading to more than twice the fastest moves to it, and it is kept from then on
+        assert_eq!(simulate_request(&manager, &[600, 100]).await, 0);
+        assert_eq!(simulate_request(&manager, &[600, 100]).await, 1);
+        for _ in 0..5 {
+            assert_eq!(simulate_request(&manager, &[50, 100]).await, 1);
+        }
+
+        // Errors move it too
+        manager
+            .mark_error("http://provider2.com", "test error")
+            .await;
+        assert_eq!(simulate_request(&manager, &[50, 100]).await, 0);
+    }
+
+    #[test]
+    fn test_cooldown_for() {
+        let base = Duration::from_secs(1);
+        let transient = ProviderError::timeout("get_logs timeout");
+        assert_eq!(cooldown_for(base, &transient, 1), base);
+        assert_eq!(cooldown_for(base, &transient, 3), Duration::from_secs(4));
+        assert_eq!(cooldown_for(base, &transient, 40), MAX_COOLDOWN);
+
+        // Rate limits honour the provider's retry hint
+        let rate_limited = ProviderError::classify("HTTP error 429 with body: Retry-After: 7");
+        assert_eq!(cooldown_for(base, &rate_limited, 5), Duration::from_secs(7));
+        let rate_limited = ProviderError::classify("HTTP error 429 with body: slow down");
+        assert_eq!(cooldown_for(base, &rate_limited, 2), Duration::from_secs(2));
+
+        let fatal = ProviderError::classify("HTTP error 401 with body: invalid API key");
+        assert_eq!(cooldown_for(base, &fatal, 1), MAX_COOLDOWN);
+
+        let missing = ProviderError::data_missing("Block 5 not found");
+        assert_eq!(cooldown_for(base, &missing, 4), base);
+        assert_eq!(
+            cooldown_for(Duration::from_secs(60), &missing, 4),
+            DATA_MISSING_COOLDOWN
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_breaker() {
+        let manager = ProviderManager::new(
+            strategy_providers(&[10, 10])
+                .into_iter()
+                .map(|p| RpcProvider {
+                    cooldown_on_error_ms: 50,
+                    ..p
+                })
+                .collect(),
+            ProviderStrategy::Priority,
+            11155111,
+            "test".to_string(),
+        )
+        .unwrap();
+        let circuit =
+            |health: Vec<ProviderHealthSnapshot>| (health[0].circuit, health[0].consecutive_errors);
+        let primary = "http://provider1.com";
+
+        manager.mark_error(primary, "get_logs timeout").await;
+        assert_eq!(circuit(manager.health_snapshot().await), ("open", 1));
+        assert_eq!(simulate_request(&manager, &[100, 100]).await, 1);
+
+        // After the cooldown the provider is on trial; failing again doubles the cooldown
+        tokio::time::advance(Duration::from_millis(49)).await;
+        assert_eq!(simulate_request(&manager, &[100, 100]).await, 1);
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert_eq!(
+            manager.get_current_provider().await.unwrap(),
+            "http://provider1.com"
+        );
+        assert_eq!(circuit(manager.health_snapshot().await), ("half_open", 1));
+        manager.mark_error(primary, "get_logs timeout").await;
+        tokio::time::advance(Duration::from_millis(30)).await;
+        assert_eq!(simulate_request(&manager, &[100, 100]).await, 1);
+        assert_eq!(circuit(manager.health_snapshot().await), ("open", 2));
+
+        // A successful trial request closes the circuit
+        tokio::time::advance(Duration::from_millis(90)).await;
+        assert_eq!(simulate_request(&manager, &[100, 100]).await, 0);
+        let health = manager.health_snapshot().await;
+        assert_eq!(circuit(health.clone()), ("closed", 0));
+        assert_eq!(health[0].last_error_class, Some("transient"));
+
+        // Probes decide for half-open providers too
+        manager
+            .mark_error(primary, "HTTP error 429 with body: Retry-After: 0")
+            .await;
+        tokio::time::advance(Duration::from_millis(10)).await;
+        manager.get_current_provider().await.unwrap();
+        assert_eq!(circuit(manager.health_snapshot().await), ("half_open", 1));
+        manager
+            .record_probe(
+                "http://provider1.com",
+                Err("connection refused".to_string()),
+            )
+            .await;
+        assert_eq!(circuit(manager.health_snapshot().await), ("open", 2));
+        tokio::time::advance(Duration::from_millis(150)).await;
+        manager.get_current_provider().await.unwrap();
+        manager
+            .record_probe(
+                "http://provider1.com",
+                Ok(ProbeResult {
+                    latency: Duration::from_millis(100),
+                    head_block: 1000,
+                    chain_id: 11155111,
+                }),
+            )
+            .await;
+        assert_eq!(circuit(manager.health_snapshot().await), ("closed", 0));
+    }
 }
@@ -0,0 +1,69 @@
+use super::Storage;
+use crate::indexer::control::IndexerCommand;
+use anyhow::Result;
+use sqlx::Row;
+use tracing::warn;
+
+impl Storage {
+    /// Queue an admin command for the instance holding the chain's indexing lock
+    /// Returns the command id, or None when the chain is not enabled
+    pub async fn queue_indexer_command(
+        &self,
+        chain_id: u64,
+        command: &IndexerCommand,
+        requested_by: &str,
+    ) -> Result<Option<i64>> {
+        let id = sqlx::query_scalar(
+            r#"
+            INSERT INTO indexer_commands (chain_id, command, requested_by)
+            SELECT $1, $2, $3
+            WHERE EXISTS (SELECT 1 FROM chains WHERE chain_id = $1 AND enabled = true)
+            RETURNING id
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(serde_json::to_value(command)?)
+        .bind(requested_by)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    /// Take the pending admin commands of a chain, oldest first, marking them applied
+    pub async fn take_indexer_commands(&self, chain_id: u64) -> Result<Vec<IndexerCommand>> {
+        let rows = sqlx::query(
+            r#"
+            WITH taken AS (
+                UPDATE indexer_commands
+                SET applied_at = NOW()
+                WHERE id IN (
+                    SELECT id FROM indexer_commands
+                    WHERE chain_id = $1 AND applied_at IS NULL
+                    ORDER BY id
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, command
+            )
+            SELECT id, command FROM taken ORDER BY id
+            "#,
+        )
+        .bind(chain_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let id: i64 = row.get("id");
+                match serde_json::from_value(row.get("command")) {
+                    Ok(command) => Some(command),
+                    Err(e) => {
+                        warn!("Skipping malformed admin command {}: {}", id, e);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
@@ -1,11 +1,30 @@
-use crate::models::{Event, EventQuery, EventType};
-use anyhow::Result;
+pub mod commands;
+pub mod notify;
+pub mod outbox;
+
+use crate::config::ChainConfig;
+use crate::models::{Event, EventData, EventQuery, EventType, RawLog, UnknownLog, UnknownLogQuery};
+use anyhow::{Context, Result};
 use chrono::{Duration, Utc};
 use dashmap::DashMap;
-use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Row, Transaction};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Number of blocks behind the newest stored header to keep for reorg detection
+const BLOCK_HEADER_RETENTION: i64 = 2048;
+
+/// Maximum number of stored headers compared when searching for a common ancestor
+const MAX_REORG_WALKBACK: i64 = 128;
+
+/// Maximum number of rows per multi-row events INSERT (12 bind parameters per row)
+const EVENT_INSERT_BATCH_SIZE: usize = 1000;
+
+/// application_name prefix of indexing lock connections, followed by the instance id
+const CHAIN_LOCK_APPLICATION_PREFIX: &str = "api_8004_dev:";
+
 /// Cache entry with timestamp for LRU eviction
 #[derive(Clone)]
 struct CachedEvent {
@@ -20,6 +39,9 @@ pub struct Storage {
     pool: PgPool,
     cache: Arc<DashMap<String, CachedEvent>>, // key: chain_id:tx_hash:log_index
     max_cache_size: usize,
+    /// Backend pid of the connection holding each chain's indexing lock taken by this process
+    /// Writes to those chains are fenced: they fail once the lock is gone
+    fences: Arc<DashMap<u64, i32>>,
 }
 
 impl Storage {
@@ -28,7 +50,40 @@ impl Storage {
             pool,
             cache: Arc::new(DashMap::new()),
             max_cache_size,
+            fences: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Fail unless this process still holds the chain's indexing lock (when it took one)
+    /// Run inside write transactions, so an instance that lost the lock stops writing right away
+    /// instead of at its next lock check
+    async fn check_fence(&self, tx: &mut Transaction<'_, Postgres>, chain_id: u64) -> Result<()> {
+        let Some(pid) = self.fences.get(&chain_id).map(|pid| *pid) else {
+            return Ok(());
+        };
+
+        let held: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM pg_locks
+                WHERE locktype = 'advisory'
+                  AND granted
+                  AND pid = $1
+                  AND objsubid = 1
+                  AND ((classid::bigint << 32) | objid::bigint) = $2
+                  AND database = (SELECT oid FROM pg_database WHERE datname = current_database())
+            )
+            "#,
+        )
+        .bind(pid)
+        .bind(chain_id as i64)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        if !held {
+            anyhow::bail!("Lost the indexing lock of chain {}, not writing", chain_id);
         }
+        Ok(())
     }
 
     /// Apply common query filters to a QueryBuilder
@@ -109,66 +164,22 @@ impl Storage {
             qb.push_bind(agent_id);
         }
 
-        Ok(())
-    }
-
-    /// Store a new event in both cache and database
-    pub async fn store_event(&self, event: Event) -> Result<()> {
-        // Generate cache key (includes chain_id to avoid collisions across chains)
-        let cache_key = format!(
-            "{}:{}:{}",
-            event.chain_id, event.transaction_hash, event.log_index
-        );
-
-        // Check if event already exists in cache
-        if self.cache.contains_key(&cache_key) {
-            return Ok(());
+        // Filter by spec version of the emitting registry
+        if let Some(spec_version) = &query.spec_version {
+            qb.push(" AND spec_version = ");
+            qb.push_bind(spec_version);
         }
 
-        // Store in database
-        let event_data_json = serde_json::to_value(&event.event_data)?;
-
-        let result = sqlx::query(
-            r#"
-            INSERT INTO events (
-                chain_id, block_number, block_timestamp, transaction_hash, log_index,
-                contract_address, event_type, event_data
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            ON CONFLICT (chain_id, transaction_hash, log_index) DO NOTHING
-            "#,
-        )
-        .bind(event.chain_id as i64)
-        .bind(event.block_number as i64)
-        .bind(event.block_timestamp)
-        .bind(&event.transaction_hash)
-        .bind(event.log_index as i32)
-        .bind(&event.contract_address)
-        .bind(event.event_type.as_str())
-        .bind(event_data_json)
-        .execute(&self.pool)
-        .await?;
-
-        // Increment total_events_indexed counter if event was inserted (not a duplicate)
-        if result.rows_affected() > 0 {
-            sqlx::query(
-                r#"
-                UPDATE chain_sync_state
-                SET total_events_indexed = total_events_indexed + 1,
-                    updated_at = NOW()
-                WHERE chain_id = $1
-                "#,
-            )
-            .bind(event.chain_id as i64)
-            .execute(&self.pool)
-            .await?;
-
-            // Update Prometheus metrics
-            metrics::counter!("events_indexed_total", "chain_id" => event.chain_id.to_string())
-                .increment(1);
+        // Only events from finalized blocks
+        if query.finalized_only {
+            qb.push(" AND finalized = true");
         }
 
-        // Store in cache with timestamp (evict oldest if needed)
+        Ok(())
+    }
+
+    /// Store an event in the cache with its insertion timestamp (evicting the oldest if full)
+    fn cache_event(&self, cache_key: String, event: Event) {
         if self.cache.len() >= self.max_cache_size {
             // LRU eviction: find and remove the oldest entry by timestamp
             let oldest_key = self
@@ -194,8 +205,203 @@ impl Storage {
                 inserted_at: now,
             },
         );
+    }
 
-        Ok(())
+    /// Insert events with multi-row INSERTs inside a transaction
+    /// Returns the events that were newly inserted (duplicates are skipped)
+    async fn insert_events(
+        tx: &mut Transaction<'_, Postgres>,
+        events: &[Event],
+    ) -> Result<Vec<Event>> {
+        let mut inserted = Vec::new();
+
+        for batch in events.chunks(EVENT_INSERT_BATCH_SIZE) {
+            let mut qb = sqlx::QueryBuilder::new(
+                r#"
+                INSERT INTO events (
+                    chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                    contract_address, event_type, event_data, finalized, raw_topics, raw_data,
+                    spec_version
+                )
+                "#,
+            );
+            let event_data = batch
+                .iter()
+                .map(|event| serde_json::to_value(&event.event_data))
+                .collect::<Result<Vec<_>, _>>()?;
+            qb.push_values(
+                batch.iter().zip(event_data),
+                |mut row, (event, event_data)| {
+                    row.push_bind(event.chain_id as i64)
+                        .push_bind(event.block_number as i64)
+                        .push_bind(event.block_timestamp)
+                        .push_bind(&event.transaction_hash)
+                        .push_bind(event.log_index as i32)
+                        .push_bind(&event.contract_address)
+                        .push_bind(event.event_type.as_str())
+                        .push_bind(event_data)
+                        .push_bind(event.finalized)
+                        .push_bind(event.raw.as_ref().map(|raw| raw.topics.clone()))
+                        .push_bind(event.raw.as_ref().map(|raw| raw.data.clone()))
+                        .push_bind(event.spec_version.clone());
+                },
+            );
+            qb.push(
+                r#"
+                ON CONFLICT (chain_id, transaction_hash, log_index) DO NOTHING
+                RETURNING id, transaction_hash, log_index, created_at
+                "#,
+            );
+
+            let rows = qb.build().fetch_all(&mut **tx).await?;
+
+            // Only rows that were actually inserted are returned
+            let mut batch_by_key: HashMap<(&str, u32), &Event> = batch
+                .iter()
+                .map(|e| ((e.transaction_hash.as_str(), e.log_index), e))
+                .collect();
+            for row in &rows {
+                let tx_hash: &str = row.get("transaction_hash");
+                let log_index = row.get::<i32, _>("log_index") as u32;
+                if let Some(event) = batch_by_key.remove(&(tx_hash, log_index)) {
+                    let mut event = event.clone();
+                    event.id = Some(row.get("id"));
+                    event.created_at = Some(row.get("created_at"));
+                    inserted.push(event);
+                }
+            }
+        }
+
+        Ok(inserted)
+    }
+
+    /// Commit the indexed contents of a block range atomically
+    /// Inserts `events`, stores undecodable `unknown_logs` and `headers`, bumps the chain's
+    /// event counter and, if given, moves the sync cursor to `next_block`, all in one
+    /// transaction. Returns the events that were newly inserted (duplicates are skipped).
+    pub async fn commit_block_range(
+        &self,
+        chain_id: u64,
+        events: Vec<Event>,
+        unknown_logs: &[UnknownLog],
+        headers: &[BlockHeader],
+        next_block: Option<u64>,
+    ) -> Result<Vec<Event>> {
+        let mut tx = self.pool.begin().await?;
+        self.check_fence(&mut tx, chain_id).await?;
+
+        let inserted = Self::insert_events(&mut tx, &events).await?;
+
+        if !unknown_logs.is_empty() {
+            let mut qb = sqlx::QueryBuilder::new(
+                r#"
+                INSERT INTO unknown_logs (
+                    chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                    contract_address, registry, topics, data, error
+                )
+                "#,
+            );
+            qb.push_values(unknown_logs, |mut row, log| {
+                row.push_bind(log.chain_id as i64)
+                    .push_bind(log.block_number as i64)
+                    .push_bind(log.block_timestamp)
+                    .push_bind(&log.transaction_hash)
+                    .push_bind(log.log_index as i32)
+                    .push_bind(&log.contract_address)
+                    .push_bind(&log.registry)
+                    .push_bind(&log.topics)
+                    .push_bind(&log.data)
+                    .push_bind(&log.error);
+            });
+            qb.push(" ON CONFLICT (chain_id, transaction_hash, log_index) DO NOTHING");
+            qb.build().execute(&mut *tx).await?;
+        }
+
+        for header in headers {
+            sqlx::query(
+                r#"
+                INSERT INTO block_headers (chain_id, block_number, block_hash, parent_hash, block_timestamp)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (chain_id, block_number)
+                DO UPDATE SET
+                    block_hash = EXCLUDED.block_hash,
+                    parent_hash = EXCLUDED.parent_hash,
+                    block_timestamp = EXCLUDED.block_timestamp
+                "#,
+            )
+            .bind(chain_id as i64)
+            .bind(header.block_number as i64)
+            .bind(&header.block_hash)
+            .bind(&header.parent_hash)
+            .bind(header.block_timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if let Some(newest) = headers.iter().map(|h| h.block_number).max() {
+            sqlx::query("DELETE FROM block_headers WHERE chain_id = $1 AND block_number < $2")
+                .bind(chain_id as i64)
+                .bind(newest as i64 - BLOCK_HEADER_RETENTION)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        match next_block {
+            Some(next_block) => {
+                sqlx::query(
+                    r#"
+                    INSERT INTO chain_sync_state (chain_id, last_synced_block, last_sync_time, total_events_indexed)
+                    VALUES ($1, $2, NOW(), $3)
+                    ON CONFLICT (chain_id)
+                    DO UPDATE SET
+                        last_synced_block = $2,
+                        total_events_indexed = chain_sync_state.total_events_indexed + $3,
+                        last_sync_time = NOW(),
+                        updated_at = NOW()
+                    "#,
+                )
+                .bind(chain_id as i64)
+                .bind(next_block as i64)
+                .bind(inserted.len() as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+            None if !inserted.is_empty() => {
+                sqlx::query(
+                    r#"
+                    UPDATE chain_sync_state
+                    SET total_events_indexed = total_events_indexed + $2,
+                        updated_at = NOW()
+                    WHERE chain_id = $1
+                    "#,
+                )
+                .bind(chain_id as i64)
+                .bind(inserted.len() as i64)
+                .execute(&mut *tx)
+                .await?;
+            }
+            None => {}
+        }
+
+        // Published to sinks (including the WebSocket broadcast) by the outbox dispatcher
+        outbox::record(&mut tx, &inserted, &[]).await?;
+
+        tx.commit().await?;
+
+        if !inserted.is_empty() {
+            metrics::counter!("events_indexed_total", "chain_id" => chain_id.to_string())
+                .increment(inserted.len() as u64);
+        }
+
+        for event in &inserted {
+            let cache_key = format!(
+                "{}:{}:{}",
+                event.chain_id, event.transaction_hash, event.log_index
+            );
+            self.cache_event(cache_key, event.clone());
+        }
+
+        Ok(inserted)
     }
 
     /// Get recent events based on query parameters
@@ -205,7 +411,7 @@ impl Storage {
             r#"
             SELECT
                 id, chain_id, block_number, block_timestamp, transaction_hash, log_index,
-                contract_address, event_type, event_data, created_at
+                contract_address, event_type, event_data, created_at, finalized, spec_version
             FROM events
             WHERE 1=1
             "#,
@@ -232,39 +438,7 @@ impl Storage {
         let rows = qb.build().fetch_all(&self.pool).await?;
 
         // Parse results
-        let events: Vec<Event> = rows
-            .into_iter()
-            .filter_map(|row| {
-                let event_type_str: String = row.get("event_type");
-                let event_type = match event_type_str.as_str() {
-                    "Registered" => EventType::Registered,
-                    "MetadataSet" => EventType::MetadataSet,
-                    "UriUpdated" => EventType::UriUpdated,
-                    "NewFeedback" => EventType::NewFeedback,
-                    "FeedbackRevoked" => EventType::FeedbackRevoked,
-                    "ResponseAppended" => EventType::ResponseAppended,
-                    "ValidationRequest" => EventType::ValidationRequest,
-                    "ValidationResponse" => EventType::ValidationResponse,
-                    _ => return None,
-                };
-
-                let event_data_json: serde_json::Value = row.get("event_data");
-                let event_data = serde_json::from_value(event_data_json).ok()?;
-
-                Some(Event {
-                    id: Some(row.get("id")),
-                    chain_id: row.get::<i64, _>("chain_id") as u64,
-                    block_number: row.get::<i64, _>("block_number") as u64,
-                    block_timestamp: row.get("block_timestamp"),
-                    transaction_hash: row.get("transaction_hash"),
-                    log_index: row.get::<i32, _>("log_index") as u32,
-                    contract_address: row.get("contract_address"),
-                    event_type,
-                    event_data,
-                    created_at: Some(row.get("created_at")),
-                })
-            })
-            .collect();
+        let events: Vec<Event> = rows.iter().filter_map(event_from_row).collect();
 
         Ok(events)
     }
@@ -315,6 +489,7 @@ impl Storage {
     // ===== Multi-Chain Support Methods =====
 
     /// Update the last synced block for a specific chain
+    #[allow(dead_code)]
     pub async fn update_last_synced_block_for_chain(
         &self,
         chain_id: u64,
@@ -339,6 +514,66 @@ impl Storage {
         Ok(())
     }
 
+    /// Move a chain's sync cursor back to `block_number`, the next block to sync
+    /// Stored headers from `block_number` on are replaced by `parent`, the header of the block
+    /// before it, in the same transaction, so the next reorg check starts from the rewound cursor
+    /// instead of rolling events back. Returns false, changing nothing, when the cursor is behind
+    /// `block_number`
+    pub async fn rewind_chain_to_block(
+        &self,
+        chain_id: u64,
+        block_number: u64,
+        parent: Option<&BlockHeader>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        self.check_fence(&mut tx, chain_id).await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE chain_sync_state
+            SET last_synced_block = $2, last_sync_time = NOW(), updated_at = NOW()
+            WHERE chain_id = $1 AND last_synced_block >= $2
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(block_number as i64)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM block_headers WHERE chain_id = $1 AND block_number >= $2")
+            .bind(chain_id as i64)
+            .bind(block_number as i64)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(header) = parent {
+            sqlx::query(
+                r#"
+                INSERT INTO block_headers (chain_id, block_number, block_hash, parent_hash, block_timestamp)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (chain_id, block_number)
+                DO UPDATE SET
+                    block_hash = EXCLUDED.block_hash,
+                    parent_hash = EXCLUDED.parent_hash,
+                    block_timestamp = EXCLUDED.block_timestamp
+                "#,
+            )
+            .bind(chain_id as i64)
+            .bind(header.block_number as i64)
+            .bind(&header.block_hash)
+            .bind(&header.parent_hash)
+            .bind(header.block_timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
     /// Get the last synced block number for a specific chain
     pub async fn get_last_synced_block_for_chain(&self, chain_id: u64) -> Result<u64> {
         let block: Option<i64> = sqlx::query_scalar(
@@ -374,15 +609,153 @@ impl Storage {
         Ok(())
     }
 
-    /// Get all enabled chains from database
-    pub async fn get_enabled_chains(&self) -> Result<Vec<ChainInfo>> {
+    /// Upsert the configured chains into the chains table and disable every other chain
+    /// Chains removed from the configuration keep their row (and indexed data) but stop being
+    /// listed. Each configured chain also gets a sync state row so status updates apply
+    pub async fn sync_configured_chains(&self, chains: &[ChainConfig]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for chain in chains {
+            sqlx::query(
+                r#"
+                INSERT INTO chains (chain_id, name, rpc_url, enabled, identity_registry, reputation_registry, validation_registry)
+                VALUES ($1, $2, $3, true, $4, $5, $6)
+                ON CONFLICT (chain_id)
+                DO UPDATE SET
+                    name = EXCLUDED.name,
+                    rpc_url = EXCLUDED.rpc_url,
+                    enabled = true,
+                    identity_registry = EXCLUDED.identity_registry,
+                    reputation_registry = EXCLUDED.reputation_registry,
+                    validation_registry = EXCLUDED.validation_registry,
+                    updated_at = NOW()
+                "#,
+            )
+            .bind(chain.chain_id as i64)
+            .bind(&chain.name)
+            .bind(chain.redacted_rpc_url())
+            .bind(&chain.contracts.identity_registry)
+            .bind(&chain.contracts.reputation_registry)
+            .bind(&chain.contracts.validation_registry)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to upsert chain {} ({})", chain.name, chain.chain_id))?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO chain_sync_state (chain_id, last_synced_block, status)
+                VALUES ($1, 0, 'active')
+                ON CONFLICT (chain_id) DO NOTHING
+                "#,
+            )
+            .bind(chain.chain_id as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let chain_ids: Vec<i64> = chains.iter().map(|c| c.chain_id as i64).collect();
+        sqlx::query(
+            r#"
+            UPDATE chains
+            SET enabled = false, updated_at = NOW()
+            WHERE enabled = true AND chain_id <> ALL($1)
+            "#,
+        )
+        .bind(&chain_ids)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Try to take the indexing lock of a chain without waiting
+    /// The lock is a session advisory lock keyed by the chain id, held on a dedicated connection
+    /// (outside the pool), so it is released as soon as the holder closes the connection or its
+    /// process dies. Until then this storage's writes to the chain are fenced by it
+    pub async fn try_acquire_chain_lock(
+        &self,
+        chain_id: u64,
+        instance_id: &str,
+    ) -> Result<Option<ChainLock>> {
+        let mut conn = self.pool.acquire().await?.detach();
+
+        // Lock holders are identified by the application_name of their connection
+        let application_name: String = format!("{}{}", CHAIN_LOCK_APPLICATION_PREFIX, instance_id)
+            .chars()
+            .take(63)
+            .collect();
+        sqlx::query("SELECT set_config('application_name', $1, false)")
+            .bind(application_name)
+            .execute(&mut conn)
+            .await?;
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(chain_id as i64)
+            .fetch_one(&mut conn)
+            .await?;
+
+        if !acquired {
+            let _ = conn.close().await;
+            return Ok(None);
+        }
+
+        let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(&mut conn)
+            .await?;
+        self.fences.insert(chain_id, pid);
+
+        Ok(Some(ChainLock {
+            chain_id,
+            pid,
+            conn,
+            fences: self.fences.clone(),
+        }))
+    }
+
+    /// Current holders of the per-chain indexing locks by chain id
+    pub async fn get_chain_lock_holders(&self) -> Result<HashMap<u64, ChainLockHolder>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT (l.classid::bigint << 32) | l.objid::bigint AS chain_id,
+                   a.application_name, a.backend_start
+            FROM pg_locks l
+            JOIN pg_stat_activity a ON a.pid = l.pid
+            WHERE l.locktype = 'advisory'
+              AND l.granted
+              AND l.objsubid = 1
+              AND starts_with(a.application_name, $1)
+              AND l.database = (SELECT oid FROM pg_database WHERE datname = current_database())
+            "#,
+        )
+        .bind(CHAIN_LOCK_APPLICATION_PREFIX)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let application_name: Option<String> = row.get("application_name");
+                let holder = ChainLockHolder {
+                    instance_id: application_name
+                        .as_deref()
+                        .and_then(|name| name.strip_prefix(CHAIN_LOCK_APPLICATION_PREFIX))
+                        .map(str::to_string),
+                    since: row.get("backend_start"),
+                };
+                (row.get::<i64, _>("chain_id") as u64, holder)
+            })
+            .collect())
+    }
+
+    /// Get all chains from database, including disabled ones (with their last known status)
+    pub async fn get_chains(&self) -> Result<Vec<ChainInfo>> {
         let rows = sqlx::query(
             r#"
-            SELECT c.chain_id, c.name, c.rpc_url, c.identity_registry, c.reputation_registry, c.validation_registry,
+            SELECT c.chain_id, c.name, c.rpc_url, c.enabled, c.identity_registry, c.reputation_registry, c.validation_registry,
                    s.last_synced_block, s.status, s.error_message, s.total_events_indexed, s.errors_last_hour, s.last_sync_time
             FROM chains c
             LEFT JOIN chain_sync_state s ON c.chain_id = s.chain_id
-            WHERE c.enabled = true
             ORDER BY c.chain_id
             "#,
         )
@@ -395,6 +768,7 @@ impl Storage {
                 chain_id: row.get::<i64, _>("chain_id") as u64,
                 name: row.get("name"),
                 rpc_url: row.get("rpc_url"),
+                enabled: row.get("enabled"),
                 identity_registry: row.get("identity_registry"),
                 reputation_registry: row.get("reputation_registry"),
                 validation_registry: row.get("validation_registry"),
@@ -417,7 +791,6 @@ impl Storage {
     }
 
     /// Get sync state for a specific chain
-    #[allow(dead_code)]
     pub async fn get_chain_sync_state(&self, chain_id: u64) -> Result<Option<ChainSyncState>> {
         let row = sqlx::query(
             r#"
@@ -462,16 +835,21 @@ impl Storage {
             let event_type: String = row.get("event_type");
             let count: i64 = row.get("count");
 
-            match event_type.as_str() {
-                "Registered" => counts.registered = count as u64,
-                "MetadataSet" => counts.metadata_set = count as u64,
-                "UriUpdated" => counts.uri_updated = count as u64,
-                "NewFeedback" => counts.new_feedback = count as u64,
-                "FeedbackRevoked" => counts.feedback_revoked = count as u64,
-                "ResponseAppended" => counts.response_appended = count as u64,
-                "ValidationRequest" => counts.validation_request = count as u64,
-                "ValidationResponse" => counts.validation_response = count as u64,
-                _ => {}
+            match EventType::parse(&event_type) {
+                EventType::Registered => counts.registered = count as u64,
+                EventType::MetadataSet => counts.metadata_set = count as u64,
+                EventType::UriUpdated => counts.uri_updated = count as u64,
+                EventType::Transfer => counts.transfer = count as u64,
+                EventType::Approval => counts.approval = count as u64,
+                EventType::ApprovalForAll => counts.approval_for_all = count as u64,
+                EventType::NewFeedback => counts.new_feedback = count as u64,
+                EventType::FeedbackRevoked => counts.feedback_revoked = count as u64,
+                EventType::ResponseAppended => counts.response_appended = count as u64,
+                EventType::ValidationRequest => counts.validation_request = count as u64,
+                EventType::ValidationResponse => counts.validation_response = count as u64,
+                EventType::Custom(name) => {
+                    counts.custom.insert(name, count as u64);
+                }
             }
         }
 
@@ -479,7 +857,7 @@ impl Storage {
     }
 
     /// Get event statistics by category
-    /// Returns counts for all categories: all, agents, metadata, validation, feedback
+    /// Returns counts for all categories: all, agents, ownership, metadata, validation, feedback
     /// - None: Stats for all chains
     /// - Some(vec![chain_id]): Stats for specific chain(s)
     pub async fn get_category_stats(&self, chain_ids: Option<Vec<u64>>) -> Result<CategoryStats> {
@@ -518,6 +896,14 @@ impl Storage {
         .fetch_one(&self.pool)
         .await?;
 
+        // Count ownership events (Transfer, Approval, ApprovalForAll)
+        let ownership_count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM events WHERE {} AND event_type IN ('Transfer', 'Approval', 'ApprovalForAll')",
+            chain_filter
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+
         // Count metadata events (MetadataSet, UriUpdated)
         let metadata_count: i64 = sqlx::query_scalar(&format!(
             "SELECT COUNT(*) FROM events WHERE {} AND event_type IN ('MetadataSet', 'UriUpdated')",
@@ -543,6 +929,7 @@ impl Storage {
         Ok(CategoryStats {
             all: all_count,
             agents: agents_count,
+            ownership: ownership_count,
             capabilities: 0, // Not implemented yet
             metadata: metadata_count,
             validation: validation_count,
@@ -550,6 +937,588 @@ impl Storage {
             payments: 0, // Not implemented yet
         })
     }
+
+    /// Mark all events at or below `finalized_block` as finalized
+    /// Returns the number of events upgraded
+    pub async fn mark_events_finalized(&self, chain_id: u64, finalized_block: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE events
+            SET finalized = true
+            WHERE chain_id = $1 AND block_number <= $2 AND finalized = false
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(finalized_block as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ===== Reorg Handling Methods =====
+
+    /// Get the stored header for a specific block, if it was recorded
+    pub async fn get_block_header(
+        &self,
+        chain_id: u64,
+        block_number: u64,
+    ) -> Result<Option<BlockHeader>> {
+        let row = sqlx::query(
+            r#"
+            SELECT block_number, block_hash, parent_hash, block_timestamp
+            FROM block_headers
+            WHERE chain_id = $1 AND block_number = $2
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(block_number as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(block_header_from_row))
+    }
+
+    /// Get stored headers strictly below `below_block`, newest first (for common ancestor search)
+    pub async fn get_block_headers_before(
+        &self,
+        chain_id: u64,
+        below_block: u64,
+    ) -> Result<Vec<BlockHeader>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT block_number, block_hash, parent_hash, block_timestamp
+            FROM block_headers
+            WHERE chain_id = $1 AND block_number < $2
+            ORDER BY block_number DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(below_block as i64)
+        .bind(MAX_REORG_WALKBACK)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(block_header_from_row).collect())
+    }
+
+    /// Roll back a chain to its common ancestor after a reorg
+    /// Deletes events and headers above `ancestor_block` and rewinds the sync cursor in one
+    /// transaction. Returns the removed events, newest first.
+    pub async fn rollback_chain_to_block(
+        &self,
+        chain_id: u64,
+        ancestor_block: u64,
+    ) -> Result<Vec<Event>> {
+        let mut tx = self.pool.begin().await?;
+        self.check_fence(&mut tx, chain_id).await?;
+
+        let rows = sqlx::query(
+            r#"
+            DELETE FROM events
+            WHERE chain_id = $1 AND block_number > $2
+            RETURNING
+                id, chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                contract_address, event_type, event_data, created_at, finalized, spec_version
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(ancestor_block as i64)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM block_headers WHERE chain_id = $1 AND block_number > $2")
+            .bind(chain_id as i64)
+            .bind(ancestor_block as i64)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM unknown_logs WHERE chain_id = $1 AND block_number > $2")
+            .bind(chain_id as i64)
+            .bind(ancestor_block as i64)
+            .execute(&mut *tx)
+            .await?;
+
+        // Blocks above the ancestor are re-synced from the cursor, so they leave the retry queue
+        sqlx::query("DELETE FROM failed_blocks WHERE chain_id = $1 AND from_block > $2")
+            .bind(chain_id as i64)
+            .bind(ancestor_block as i64)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE failed_blocks SET to_block = $2 WHERE chain_id = $1 AND to_block > $2")
+            .bind(chain_id as i64)
+            .bind(ancestor_block as i64)
+            .execute(&mut *tx)
+            .await?;
+
+        // Cursor points at the next block to sync
+        sqlx::query(
+            r#"
+            UPDATE chain_sync_state
+            SET last_synced_block = $2,
+                total_events_indexed = GREATEST(total_events_indexed - $3, 0),
+                last_sync_time = NOW(),
+                updated_at = NOW()
+            WHERE chain_id = $1
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(ancestor_block as i64 + 1)
+        .bind(rows.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut removed: Vec<Event> = rows.iter().filter_map(event_from_row).collect();
+        removed.sort_by_key(|e| std::cmp::Reverse((e.block_number, e.log_index)));
+
+        outbox::record(&mut tx, &[], &removed).await?;
+
+        tx.commit().await?;
+
+        // Drop rolled back events from the cache so they can be re-indexed from the new fork
+        for event in &removed {
+            self.cache.remove(&format!(
+                "{}:{}:{}",
+                event.chain_id, event.transaction_hash, event.log_index
+            ));
+        }
+
+        Ok(removed)
+    }
+
+    // ===== Agent Ownership Methods =====
+
+    /// Current owner of an agent identity NFT, from its latest Transfer (or its Registered
+    /// event when no transfer was indexed). None if the agent is unknown on the chain.
+    /// Agent IDs are only unique per identity registry, so `registry` restricts the lookup to
+    /// the events of one deployment
+    pub async fn get_agent_owner(
+        &self,
+        chain_id: u64,
+        agent_id: &str,
+        registry: Option<&str>,
+    ) -> Result<Option<AgentOwner>> {
+        let row = sqlx::query(
+            r#"
+            SELECT
+                CASE WHEN event_type = 'Transfer'
+                    THEN event_data->>'to'
+                    ELSE event_data->>'owner'
+                END AS owner,
+                contract_address, block_number, block_timestamp, transaction_hash, event_type
+            FROM events
+            WHERE chain_id = $1
+              AND event_type IN ('Registered', 'Transfer')
+              AND event_data->>'agent_id' = $2
+              AND ($3::TEXT IS NULL OR contract_address = $3)
+            ORDER BY block_number DESC, log_index DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(agent_id)
+        .bind(registry.map(str::to_lowercase))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| AgentOwner {
+            chain_id,
+            agent_id: agent_id.to_string(),
+            owner: row.get("owner"),
+            contract_address: row.get("contract_address"),
+            block_number: row.get::<i64, _>("block_number") as u64,
+            block_timestamp: row.get("block_timestamp"),
+            transaction_hash: row.get("transaction_hash"),
+            source_event: EventType::parse(row.get("event_type")),
+        }))
+    }
+
+    // ===== Re-decoding Methods =====
+
+    /// Get stored events with an id above `after_id`, including their raw logs
+    pub async fn get_events_after(
+        &self,
+        chain_id: Option<u64>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<Event>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                id, chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                contract_address, event_type, event_data, created_at, finalized,
+                spec_version, raw_topics, raw_data
+            FROM events
+            WHERE ($1::BIGINT IS NULL OR chain_id = $1) AND id > $2
+            ORDER BY id
+            LIMIT $3
+            "#,
+        )
+        .bind(chain_id.map(|id| id as i64))
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().filter_map(event_from_row).collect())
+    }
+
+    /// Overwrite the decoded type, data and spec version of stored events, keyed by event id
+    pub async fn update_event_data(&self, updates: &[Event]) -> Result<u64> {
+        let updates: Vec<(i64, &Event)> = updates
+            .iter()
+            .filter_map(|event| event.id.map(|id| (id, event)))
+            .collect();
+        if updates.is_empty() {
+            return Ok(0);
+        }
+
+        let ids: Vec<i64> = updates.iter().map(|(id, _)| *id).collect();
+        let event_types: Vec<&str> = updates.iter().map(|(_, e)| e.event_type.as_str()).collect();
+        let event_data = updates
+            .iter()
+            .map(|(_, e)| serde_json::to_value(&e.event_data))
+            .collect::<Result<Vec<_>, _>>()?;
+        let spec_versions: Vec<Option<&str>> = updates
+            .iter()
+            .map(|(_, e)| e.spec_version.as_deref())
+            .collect();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE events AS e
+            SET event_type = u.event_type, event_data = u.event_data, spec_version = u.spec_version
+            FROM UNNEST($1::BIGINT[], $2::TEXT[], $3::JSONB[], $4::TEXT[])
+                AS u(id, event_type, event_data, spec_version)
+            WHERE e.id = u.id
+            "#,
+        )
+        .bind(&ids)
+        .bind(&event_types)
+        .bind(&event_data)
+        .bind(&spec_versions)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // ===== Unknown Log Methods =====
+
+    /// List undecodable registry logs, oldest first
+    pub async fn get_unknown_logs(&self, query: &UnknownLogQuery) -> Result<Vec<UnknownLog>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                   contract_address, registry, topics, data, error, created_at
+            FROM unknown_logs
+            WHERE ($1::BIGINT IS NULL OR chain_id = $1)
+            ORDER BY id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(query.chain_id.map(|id| id as i64))
+        .bind(query.limit.unwrap_or(100).clamp(1, 1000))
+        .bind(query.offset.unwrap_or(0).max(0))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(unknown_log_from_row).collect())
+    }
+
+    /// Count undecodable registry logs (optionally for a single chain)
+    pub async fn count_unknown_logs(&self, chain_id: Option<u64>) -> Result<i64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM unknown_logs WHERE ($1::BIGINT IS NULL OR chain_id = $1)",
+        )
+        .bind(chain_id.map(|id| id as i64))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Get undecodable logs with an id above `after_id` (for walking the table in batches)
+    pub async fn get_unknown_logs_after(
+        &self,
+        chain_id: Option<u64>,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<UnknownLog>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, chain_id, block_number, block_timestamp, transaction_hash, log_index,
+                   contract_address, registry, topics, data, error, created_at
+            FROM unknown_logs
+            WHERE ($1::BIGINT IS NULL OR chain_id = $1) AND id > $2
+            ORDER BY id
+            LIMIT $3
+            "#,
+        )
+        .bind(chain_id.map(|id| id as i64))
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(unknown_log_from_row).collect())
+    }
+
+    /// Replace re-decoded unknown logs with their events in one transaction
+    /// `resolved` pairs an unknown log id with the event decoded from it.
+    /// Returns the events that were newly inserted.
+    pub async fn resolve_unknown_logs(&self, resolved: Vec<(i64, Event)>) -> Result<Vec<Event>> {
+        if resolved.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (ids, events): (Vec<i64>, Vec<Event>) = resolved.into_iter().unzip();
+
+        let mut tx = self.pool.begin().await?;
+
+        let inserted = Self::insert_events(&mut tx, &events).await?;
+
+        let mut inserted_per_chain: HashMap<u64, i64> = HashMap::new();
+        for event in &inserted {
+            *inserted_per_chain.entry(event.chain_id).or_default() += 1;
+        }
+        for (chain_id, count) in &inserted_per_chain {
+            sqlx::query(
+                r#"
+                UPDATE chain_sync_state
+                SET total_events_indexed = total_events_indexed + $2,
+                    updated_at = NOW()
+                WHERE chain_id = $1
+                "#,
+            )
+            .bind(*chain_id as i64)
+            .bind(*count)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("DELETE FROM unknown_logs WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&mut *tx)
+            .await?;
+
+        outbox::record(&mut tx, &inserted, &[]).await?;
+
+        tx.commit().await?;
+
+        for (chain_id, count) in inserted_per_chain {
+            metrics::counter!("events_indexed_total", "chain_id" => chain_id.to_string())
+                .increment(count as u64);
+        }
+
+        Ok(inserted)
+    }
+
+    // ===== Failed Block Retry Methods =====
+
+    /// Queue a block range that failed to sync for a retry after `retry_delay`
+    /// Re-queuing a range that starts at the same block counts as another attempt
+    pub async fn record_failed_block_range(
+        &self,
+        chain_id: u64,
+        from_block: u64,
+        to_block: u64,
+        error: &str,
+        retry_delay: std::time::Duration,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO failed_blocks (chain_id, from_block, to_block, last_error, next_retry_at)
+            VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+            ON CONFLICT (chain_id, from_block) DO UPDATE SET
+                to_block = GREATEST(failed_blocks.to_block, EXCLUDED.to_block),
+                attempts = failed_blocks.attempts + 1,
+                last_error = EXCLUDED.last_error,
+                next_retry_at = EXCLUDED.next_retry_at,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(from_block as i64)
+        .bind(to_block as i64)
+        .bind(error)
+        .bind(retry_delay.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get failed block ranges whose retry is due, oldest blocks first
+    pub async fn get_due_failed_block_ranges(
+        &self,
+        chain_id: u64,
+        limit: i64,
+    ) -> Result<Vec<FailedBlockRange>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT from_block, to_block, attempts, last_error, next_retry_at, created_at
+            FROM failed_blocks
+            WHERE chain_id = $1 AND next_retry_at <= NOW()
+            ORDER BY from_block
+            LIMIT $2
+            "#,
+        )
+        .bind(chain_id as i64)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(failed_block_range_from_row).collect())
+    }
+
+    /// Get all queued failed block ranges for a chain, oldest blocks first
+    pub async fn get_failed_block_ranges(&self, chain_id: u64) -> Result<Vec<FailedBlockRange>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT from_block, to_block, attempts, last_error, next_retry_at, created_at
+            FROM failed_blocks
+            WHERE chain_id = $1
+            ORDER BY from_block
+            "#,
+        )
+        .bind(chain_id as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(failed_block_range_from_row).collect())
+    }
+
+    /// Remove a failed block range from the retry queue once it synced successfully
+    pub async fn delete_failed_block_range(&self, chain_id: u64, from_block: u64) -> Result<()> {
+        sqlx::query("DELETE FROM failed_blocks WHERE chain_id = $1 AND from_block = $2")
+            .bind(chain_id as i64)
+            .bind(from_block as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Parse the raw log columns of an `events` row (None if not selected or not stored)
+fn raw_log_from_row(row: &PgRow) -> Option<RawLog> {
+    let topics: Option<Vec<String>> = row.try_get("raw_topics").ok()?;
+    let data: Option<String> = row.try_get("raw_data").ok()?;
+    Some(RawLog {
+        topics: topics?,
+        data: data?,
+    })
+}
+
+/// Parse an `unknown_logs` row into an UnknownLog
+fn unknown_log_from_row(row: &PgRow) -> UnknownLog {
+    UnknownLog {
+        id: Some(row.get("id")),
+        chain_id: row.get::<i64, _>("chain_id") as u64,
+        block_number: row.get::<i64, _>("block_number") as u64,
+        block_timestamp: row.get("block_timestamp"),
+        transaction_hash: row.get("transaction_hash"),
+        log_index: row.get::<i32, _>("log_index") as u32,
+        contract_address: row.get("contract_address"),
+        registry: row.get("registry"),
+        topics: row.get("topics"),
+        data: row.get("data"),
+        error: row.get("error"),
+        created_at: Some(row.get("created_at")),
+    }
+}
+
+/// Parse a `failed_blocks` row into a FailedBlockRange
+fn failed_block_range_from_row(row: &PgRow) -> FailedBlockRange {
+    FailedBlockRange {
+        from_block: row.get::<i64, _>("from_block") as u64,
+        to_block: row.get::<i64, _>("to_block") as u64,
+        attempts: row.get::<i32, _>("attempts") as u32,
+        last_error: row.get("last_error"),
+        next_retry_at: row.get("next_retry_at"),
+        created_at: row.get("created_at"),
+    }
+}
+
+/// Parse an `events` row into an Event (None for unknown event types or malformed data)
+fn event_from_row(row: &PgRow) -> Option<Event> {
+    let event_type = EventType::parse(row.get("event_type"));
+    let event_data = EventData::from_json(&event_type, row.get("event_data"))?;
+
+    Some(Event {
+        id: Some(row.get("id")),
+        chain_id: row.get::<i64, _>("chain_id") as u64,
+        block_number: row.get::<i64, _>("block_number") as u64,
+        block_timestamp: row.get("block_timestamp"),
+        transaction_hash: row.get("transaction_hash"),
+        log_index: row.get::<i32, _>("log_index") as u32,
+        contract_address: row.get("contract_address"),
+        event_type,
+        event_data,
+        created_at: Some(row.get("created_at")),
+        removed: false,
+        finalized: row.get("finalized"),
+        raw: raw_log_from_row(row),
+        spec_version: row.try_get("spec_version").ok().flatten(),
+    })
+}
+
+/// Parse an event serialized as served by the API (e.g. carried by a notification or the outbox)
+pub(crate) fn event_from_json(mut value: serde_json::Value) -> Option<Event> {
+    // EventData is untagged, so it is decoded by event type like stored events
+    let event_data = value.get_mut("event_data")?.take();
+    value["event_data"] = serde_json::json!({});
+
+    let mut event: Event = serde_json::from_value(value).ok()?;
+    event.event_data = EventData::from_json(&event.event_type, event_data)?;
+    Some(event)
+}
+
+/// Parse a `block_headers` row into a BlockHeader
+fn block_header_from_row(row: &PgRow) -> BlockHeader {
+    BlockHeader {
+        block_number: row.get::<i64, _>("block_number") as u64,
+        block_hash: row.get("block_hash"),
+        parent_hash: row.get("parent_hash"),
+        block_timestamp: row.get("block_timestamp"),
+    }
+}
+
+/// Current owner of an agent and the event it was derived from
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AgentOwner {
+    pub chain_id: u64,
+    pub agent_id: String,
+    /// Owner address (the zero address once the agent was burned)
+    pub owner: String,
+    pub contract_address: String,
+    pub block_number: u64,
+    pub block_timestamp: chrono::DateTime<chrono::Utc>,
+    pub transaction_hash: String,
+    /// Registered or Transfer
+    pub source_event: EventType,
+}
+
+/// Block header record used for reorg detection
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct BlockHeader {
+    pub block_number: u64,
+    pub block_hash: String,
+    pub parent_hash: String,
+    pub block_timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Block range queued for a retry after failing to sync
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FailedBlockRange {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub attempts: u32,
+    pub last_error: String,
+    pub next_retry_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
 /// Event counts by specific event type
@@ -558,11 +1527,17 @@ pub struct EventTypeCount {
     pub registered: u64,
     pub metadata_set: u64,
     pub uri_updated: u64,
+    pub transfer: u64,
+    pub approval: u64,
+    pub approval_for_all: u64,
     pub new_feedback: u64,
     pub feedback_revoked: u64,
     pub response_appended: u64,
     pub validation_request: u64,
     pub validation_response: u64,
+    /// Counts of events decoded from configured JSON ABIs, by event name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, u64>,
 }
 
 /// Statistics for event categories
@@ -570,6 +1545,7 @@ pub struct EventTypeCount {
 pub struct CategoryStats {
     pub all: i64,
     pub agents: i64,
+    pub ownership: i64,
     pub capabilities: i64,
     pub metadata: i64,
     pub validation: i64,
@@ -583,6 +1559,8 @@ pub struct ChainInfo {
     pub chain_id: u64,
     pub name: String,
     pub rpc_url: String,
+    /// False for chains removed from (or disabled in) the configuration
+    pub enabled: bool,
     pub identity_registry: String,
     pub reputation_registry: String,
     pub validation_registry: String,
@@ -594,6 +1572,42 @@ pub struct ChainInfo {
     pub last_sync_time: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Per-chain indexing lock held on a dedicated database connection
+pub struct ChainLock {
+    chain_id: u64,
+    /// Backend pid of `conn`, recorded as the fence of the chain's writes
+    pid: i32,
+    conn: PgConnection,
+    fences: Arc<DashMap<u64, i32>>,
+}
+
+impl ChainLock {
+    /// Whether the lock is still held, i.e. its connection is alive
+    pub async fn is_held(&mut self) -> bool {
+        self.conn.ping().await.is_ok()
+    }
+
+    /// Release the lock and close its connection
+    pub async fn release(mut self) {
+        self.fences
+            .remove_if(&self.chain_id, |_, pid| *pid == self.pid);
+        let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(self.chain_id as i64)
+            .execute(&mut self.conn)
+            .await;
+        let _ = self.conn.close().await;
+    }
+}
+
+/// Instance holding a chain's indexing lock
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ChainLockHolder {
+    /// Instance id of the holder (None for connections not opened by this service)
+    pub instance_id: Option<String>,
+    /// When the lock connection was opened (the lock is taken right after connecting)
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Chain sync state
 #[derive(Debug, Clone, serde::Serialize)]
 #[allow(dead_code)]
@@ -638,6 +1652,10 @@ mod tests {
                 owner: "0x5678".to_string(),
             }),
             created_at: None,
+            removed: false,
+            finalized: false,
+            raw: None,
+            spec_version: None,
         }
     }
 
@@ -799,7 +1817,7 @@ mod tests {
             event3.chain_id, event3.transaction_hash, event3.log_index
         );
 
-        // Manually trigger eviction logic (same as in store_event)
+        // Manually trigger eviction logic (same as in cache_event)
         if cache.len() >= 2 {
             let oldest_key = cache
                 .iter()
@@ -835,6 +1853,7 @@ mod tests {
         let stats = super::CategoryStats {
             all: 100,
             agents: 20,
+            ownership: 0,
             capabilities: 0,
             metadata: 30,
             validation: 25,
@@ -862,6 +1881,7 @@ mod tests {
         let stats = super::CategoryStats {
             all: 50,
             agents: 10,
+            ownership: 4,
             capabilities: 5,
             metadata: 15,
             validation: 10,
@@ -872,6 +1892,7 @@ mod tests {
         let json = serde_json::to_string(&stats).unwrap();
         assert!(json.contains("\"all\":50"));
         assert!(json.contains("\"agents\":10"));
+        assert!(json.contains("\"ownership\":4"));
         assert!(json.contains("\"capabilities\":5"));
         assert!(json.contains("\"metadata\":15"));
         assert!(json.contains("\"validation\":10"));
@@ -885,6 +1906,7 @@ mod tests {
             chain_id: 11155111,
             name: "Ethereum Sepolia".to_string(),
             rpc_url: "https://sepolia.infura.io".to_string(),
+            enabled: true,
             identity_registry: "0x1111111111111111111111111111111111111111".to_string(),
             reputation_registry: "0x2222222222222222222222222222222222222222".to_string(),
             validation_registry: "0x3333333333333333333333333333333333333333".to_string(),
@@ -0,0 +1,184 @@
+use super::outbox::OutboxPosition;
+use super::Storage;
+use crate::models::Event;
+use anyhow::Result;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// NOTIFY channel on which new outbox entries are announced
+pub const EVENTS_CHANNEL: &str = "api_8004_events";
+
+/// Outbox entries are kept at least this long, so listeners can catch up after a reconnect
+pub const CATCH_UP_WINDOW: Duration = Duration::from_secs(600);
+
+/// Outbox entries read at once by a listener
+const READ_BATCH_SIZE: i64 = 500;
+
+/// Delay before listening again after the listener connection failed
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+impl Storage {
+    /// Wake the event listeners of every API process to read the outbox
+    pub async fn announce_outbox(&self) -> Result<()> {
+        sqlx::query("SELECT pg_notify($1, '')")
+            .bind(EVENTS_CHANNEL)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Broadcast outbox entries to local subscribers until `shutdown` is cancelled
+/// Each process reads the outbox past its own position whenever `EVENTS_CHANNEL` is notified (by
+/// writers on commit, and by the outbox's broadcast sink for entries that were held back behind
+/// an older transaction) and after every (re)connection, so missed notifications delay events
+/// instead of losing them, and repeated ones do not duplicate them. Listening is retried until it
+/// succeeds.
+pub async fn forward_events(
+    storage: Storage,
+    event_tx: broadcast::Sender<Event>,
+    shutdown: CancellationToken,
+) {
+    // Starts with the entries still to come once listening for the first time
+    let mut position: Option<OutboxPosition> = None;
+    let mut disconnected_at: Option<Instant> = None;
+
+    while !shutdown.is_cancelled() {
+        match listen(&storage).await {
+            Ok(mut listener) => {
+                info!("Listening for events on channel {}", EVENTS_CHANNEL);
+                if let Some(since) = disconnected_at.take() {
+                    warn_if_outside_catch_up_window(since.elapsed());
+                }
+
+                let result = forward_notifications(
+                    &storage,
+                    &event_tx,
+                    &mut listener,
+                    &mut position,
+                    &shutdown,
+                )
+                .await;
+                match result {
+                    Ok(()) => return,
+                    Err(e) => warn!("Event listener failed, reconnecting: {}", e),
+                }
+            }
+            Err(e) => warn!("Failed to listen for events, retrying: {}", e),
+        }
+
+        disconnected_at.get_or_insert_with(Instant::now);
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = sleep(RECONNECT_DELAY) => {}
+        }
+    }
+}
+
+/// Connect a listener to `EVENTS_CHANNEL`
+async fn listen(storage: &Storage) -> Result<PgListener> {
+    let mut listener = PgListener::connect_with(&storage.pool).await?;
+    listener.listen(EVENTS_CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Entries older than the catch-up window may have been pruned while disconnected
+fn warn_if_outside_catch_up_window(disconnected: Duration) {
+    if disconnected > CATCH_UP_WINDOW {
+        warn!(
+            "Event listener was disconnected for {}s, longer than the {}s catch-up window; \
+             events pruned from the outbox meanwhile were not broadcast",
+            disconnected.as_secs(),
+            CATCH_UP_WINDOW.as_secs()
+        );
+    }
+}
+
+/// Forward outbox entries on every notification until `shutdown` is cancelled
+/// Returns an error once the listener or the database fails
+async fn forward_notifications(
+    storage: &Storage,
+    event_tx: &broadcast::Sender<Event>,
+    listener: &mut PgListener,
+    position: &mut Option<OutboxPosition>,
+    shutdown: &CancellationToken,
+) -> Result<()> {
+    let position = match position {
+        Some(position) => position,
+        None => position.insert(storage.outbox_start_position().await?),
+    };
+
+    loop {
+        // Entries committed while not listening are read along with the notified ones
+        forward_outbox(storage, event_tx, position).await?;
+
+        let notification = tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            notification = listener.try_recv() => notification?,
+        };
+        if notification.is_none() {
+            // The listener reconnected right away; notifications sent meanwhile were lost
+            warn!("Event listener connection was lost, catching up from the outbox");
+        }
+    }
+}
+
+/// Broadcast the outbox entries after `position`, moving it past them
+/// Entries are read past the position only, so each one is broadcast once
+async fn forward_outbox(
+    storage: &Storage,
+    event_tx: &broadcast::Sender<Event>,
+    position: &mut OutboxPosition,
+) -> Result<()> {
+    loop {
+        let entries = storage.read_outbox(position, READ_BATCH_SIZE).await?;
+        let caught_up = entries.len() < READ_BATCH_SIZE as usize;
+        if !entries.is_empty() {
+            debug!("Forwarding {} outbox entries", entries.len());
+        }
+
+        for (entry_position, event) in entries {
+            if let Some(event) = event {
+                let _ = event_tx.send(event);
+            }
+            *position = entry_position;
+        }
+
+        if caught_up {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_forward_events_retries_listening() {
+        // Nothing listens on port 1, so every connection attempt fails
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(100))
+            .connect_lazy("postgresql://postgres@127.0.0.1:1/api_8004_dev")
+            .unwrap();
+        let (event_tx, _) = broadcast::channel(16);
+        let shutdown = CancellationToken::new();
+        let listener = tokio::spawn(forward_events(
+            Storage::new(pool, 1000),
+            event_tx,
+            shutdown.clone(),
+        ));
+
+        // The listener keeps retrying instead of giving up, and stops on shutdown
+        sleep(RECONNECT_DELAY * 2).await;
+        assert!(!listener.is_finished());
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(5), listener)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use super::notify::EVENTS_CHANNEL;
+use super::{event_from_json, Storage};
+use crate::models::Event;
+use anyhow::{anyhow, Result};
+use sqlx::{Executor, PgPool, Postgres, Row, Transaction};
+use tokio::time::Duration;
+use tracing::warn;
+
+/// How long a claimed batch keeps its sink from other instances if it is neither acknowledged nor
+/// released (e.g. the instance died); longer than any delivery (webhooks time out after 10s)
+const OUTBOX_LEASE: Duration = Duration::from_secs(60);
+
+/// Write inserted and rolled back events to the outbox, inside the transaction committing them
+/// Entries are tagged with the writing transaction and only delivered once every older transaction
+/// has ended, so concurrent writers need no lock
+pub(crate) async fn record(
+    tx: &mut Transaction<'_, Postgres>,
+    inserted: &[Event],
+    removed: &[Event],
+) -> Result<()> {
+    if inserted.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    let mut chain_ids = Vec::new();
+    let mut event_ids = Vec::new();
+    let mut removed_flags = Vec::new();
+    let mut payloads = Vec::new();
+    for (event, is_removed) in inserted
+        .iter()
+        .map(|e| (e, false))
+        .chain(removed.iter().map(|e| (e, true)))
+    {
+        let event = Event {
+            removed: is_removed,
+            ..event.clone()
+        };
+        chain_ids.push(event.chain_id as i64);
+        event_ids.push(event.id);
+        removed_flags.push(is_removed);
+        payloads.push(serde_json::to_value(&event)?);
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO event_outbox (chain_id, event_id, removed, payload)
+        SELECT * FROM UNNEST($1::BIGINT[], $2::BIGINT[], $3::BOOLEAN[], $4::JSONB[])
+        "#,
+    )
+    .bind(&chain_ids)
+    .bind(&event_ids)
+    .bind(&removed_flags)
+    .bind(&payloads)
+    .execute(&mut **tx)
+    .await?;
+
+    // Delivered on commit, so API processes read the entries without waiting for the dispatcher
+    sqlx::query("SELECT pg_notify($1, '')")
+        .bind(EVENTS_CHANNEL)
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// Position of an outbox entry: the id of the transaction that wrote it and its own id
+pub type OutboxPosition = (String, i64);
+
+/// Outbox entries after `after` whose transactions ended, in (transaction, id) order
+/// Malformed entries are returned without an event (with a warning)
+async fn read_entries<'e, E>(
+    executor: E,
+    after: &OutboxPosition,
+    limit: i64,
+) -> Result<Vec<(OutboxPosition, Option<Event>)>>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let rows = sqlx::query(
+        r#"
+        SELECT id, xid::TEXT AS xid, payload FROM event_outbox
+        WHERE (xid, id) > ($1::TEXT::XID8, $2)
+          AND xid < pg_snapshot_xmin(pg_current_snapshot())
+        ORDER BY xid, id
+        LIMIT $3
+        "#,
+    )
+    .bind(&after.0)
+    .bind(after.1)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let id: i64 = row.get("id");
+            let event = event_from_json(row.get("payload"));
+            if event.is_none() {
+                warn!("Skipping malformed outbox entry {}", id);
+            }
+            ((row.get("xid"), id), event)
+        })
+        .collect())
+}
+
+/// Undelivered outbox entries claimed for a sink
+/// The claim is a lease on the sink's cursor, taken and committed before delivery; until the batch
+/// is acknowledged or released (or the lease expires), no other instance serves the sink
+pub struct OutboxBatch {
+    pool: PgPool,
+    sink: String,
+    lease_token: String,
+    /// Cursor position when the batch was claimed
+    start: OutboxPosition,
+    /// Number of claimed outbox rows (including malformed ones, which are skipped)
+    claimed: usize,
+    /// Position of the last claimed row
+    last: Option<OutboxPosition>,
+    /// Inserted events, and rolled back ones (`removed` set), in outbox order
+    pub events: Vec<Event>,
+}
+
+impl OutboxBatch {
+    /// Number of claimed outbox entries
+    pub fn len(&self) -> usize {
+        self.claimed
+    }
+
+    /// Whether there was nothing to deliver
+    pub fn is_empty(&self) -> bool {
+        self.claimed == 0
+    }
+
+    /// Mark the batch as delivered, moving the sink's cursor past it and ending the lease
+    /// Fails if the lease expired and another instance took the sink; the batch may then be
+    /// delivered again
+    pub async fn ack(self) -> Result<()> {
+        let (last_xid, last_id) = self.last.clone().unzip();
+        let result = sqlx::query(
+            r#"
+            UPDATE outbox_cursors
+            SET last_xid = COALESCE($5::TEXT::XID8, last_xid),
+                last_outbox_id = COALESCE($6, last_outbox_id),
+                lease_token = NULL,
+                lease_until = NULL,
+                updated_at = NOW()
+            WHERE sink = $1 AND lease_token = $2::UUID
+              AND last_xid = $3::TEXT::XID8 AND last_outbox_id = $4
+            "#,
+        )
+        .bind(&self.sink)
+        .bind(&self.lease_token)
+        .bind(&self.start.0)
+        .bind(self.start.1)
+        .bind(last_xid)
+        .bind(last_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Lease on outbox sink {} expired before the batch was acknowledged",
+                self.sink
+            ));
+        }
+        Ok(())
+    }
+
+    /// Give the sink up without moving its cursor, leaving the entries to be delivered again
+    pub async fn release(self) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE outbox_cursors SET lease_token = NULL, lease_until = NULL
+            WHERE sink = $1 AND lease_token = $2::UUID
+            "#,
+        )
+        .bind(&self.sink)
+        .bind(&self.lease_token)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+impl Storage {
+    /// Claim the next undelivered outbox entries of a sink, in (transaction, id) order
+    /// Only entries of transactions older than every running one are claimed, so no entry can
+    /// commit behind the cursor; a long-running transaction anywhere in the cluster therefore
+    /// stalls every sink (see `outbox_horizon_lag`). New sinks start with the transactions still
+    /// running. None while another instance holds the sink, so each sink is delivered by one
+    /// instance at a time.
+    pub async fn claim_outbox_batch(&self, sink: &str, limit: i64) -> Result<Option<OutboxBatch>> {
+        sqlx::query(
+            r#"
+            INSERT INTO outbox_cursors (sink, last_xid, last_outbox_id)
+            VALUES ($1, pg_snapshot_xmin(pg_current_snapshot()), 0)
+            ON CONFLICT (sink) DO NOTHING
+            "#,
+        )
+        .bind(sink)
+        .execute(&self.pool)
+        .await?;
+
+        let Some(cursor) = sqlx::query(
+            r#"
+            UPDATE outbox_cursors
+            SET lease_token = gen_random_uuid(),
+                lease_until = NOW() + make_interval(secs => $2)
+            WHERE sink = $1 AND (lease_until IS NULL OR lease_until < NOW())
+            RETURNING lease_token::TEXT AS lease_token, last_xid::TEXT AS last_xid, last_outbox_id
+            "#,
+        )
+        .bind(sink)
+        .bind(OUTBOX_LEASE.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let start: OutboxPosition = (cursor.get("last_xid"), cursor.get("last_outbox_id"));
+
+        let entries = read_entries(&self.pool, &start, limit).await?;
+        let claimed = entries.len();
+        let last = entries.last().map(|(position, _)| position.clone());
+        let events = entries.into_iter().filter_map(|(_, event)| event).collect();
+
+        Ok(Some(OutboxBatch {
+            pool: self.pool.clone(),
+            sink: sink.to_string(),
+            lease_token: cursor.get("lease_token"),
+            start,
+            claimed,
+            last,
+            events,
+        }))
+    }
+
+    /// Position from which outbox entries are still to come: entries of transactions that are
+    /// running now come after it, entries of ended ones before
+    pub async fn outbox_start_position(&self) -> Result<OutboxPosition> {
+        let xid: String =
+            sqlx::query_scalar("SELECT pg_snapshot_xmin(pg_current_snapshot())::TEXT")
+                .fetch_one(&self.pool)
+                .await?;
+        Ok((xid, 0))
+    }
+
+    /// Read outbox entries after `after` without claiming them, for consumers keeping their own
+    /// position (the event listeners of API processes)
+    pub async fn read_outbox(
+        &self,
+        after: &OutboxPosition,
+        limit: i64,
+    ) -> Result<Vec<(OutboxPosition, Option<Event>)>> {
+        read_entries(&self.pool, after, limit).await
+    }
+
+    /// Delete outbox entries every sink has acknowledged and that are older than `keep`
+    /// Recent entries are kept for event listeners catching up after a reconnect. Sinks that stop
+    /// being served hold the outbox back until their cursor is deleted; see `outbox_sink_lag`.
+    /// Without any sink, entries of ended transactions are deleted (new sinks start after them).
+    pub async fn prune_outbox(&self, keep: Duration) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            WITH slowest AS (
+                SELECT last_xid, last_outbox_id FROM outbox_cursors
+                ORDER BY last_xid, last_outbox_id
+                LIMIT 1
+            )
+            DELETE FROM event_outbox
+            WHERE created_at < NOW() - make_interval(secs => $1)
+              AND ((xid, id) <= (SELECT last_xid, last_outbox_id FROM slowest)
+                   OR (NOT EXISTS (SELECT 1 FROM slowest)
+                       AND xid < pg_snapshot_xmin(pg_current_snapshot())))
+            "#,
+        )
+        .bind(keep.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Age of the oldest outbox entry that cannot be delivered yet because a transaction older
+    /// than it is still running (zero when none is held back)
+    pub async fn outbox_horizon_lag(&self) -> Result<Duration> {
+        let lag: f64 = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(EXTRACT(EPOCH FROM NOW() - MIN(created_at)), 0)::FLOAT8
+            FROM event_outbox
+            WHERE xid >= pg_snapshot_xmin(pg_current_snapshot())
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Duration::from_secs_f64(lag.max(0.0)))
+    }
+
+    /// Age of the oldest outbox entry each sink has not acknowledged yet (zero when caught up)
+    pub async fn outbox_sink_lag(&self) -> Result<Vec<(String, Duration)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.sink,
+                   COALESCE(EXTRACT(EPOCH FROM NOW() - o.created_at), 0)::FLOAT8 AS lag_seconds
+            FROM outbox_cursors c
+            LEFT JOIN LATERAL (
+                SELECT created_at FROM event_outbox
+                WHERE (xid, id) > (c.last_xid, c.last_outbox_id)
+                ORDER BY xid, id
+                LIMIT 1
+            ) o ON true
+            ORDER BY c.sink
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let lag: f64 = row.get("lag_seconds");
+                (row.get("sink"), Duration::from_secs_f64(lag.max(0.0)))
+            })
+            .collect())
+    }
+}
@@ -1,11 +1,17 @@
+use api_8004_dev::config::ChainConfig;
+use api_8004_dev::indexer::control::IndexerCommand;
+use api_8004_dev::indexer::decoder::{ChainDecoders, DecoderRegistry};
+use api_8004_dev::indexer::reindex;
+use api_8004_dev::indexer::supervisor::ChainStatus;
 use api_8004_dev::models::{
-    Event, EventData, EventType, FeedbackRevokedData, MetadataSetData, NewFeedbackData,
-    RegisteredData, ResponseAppendedData, UriUpdatedData, ValidationRequestData,
-    ValidationResponseData,
+    Event, EventData, EventQuery, EventType, FeedbackRevokedData, MetadataSetData, NewFeedbackData,
+    RegisteredData, ResponseAppendedData, TransferData, UnknownLog, UnknownLogQuery,
+    UriUpdatedData, ValidationRequestData, ValidationResponseData,
 };
-use api_8004_dev::storage::Storage;
+use api_8004_dev::storage::{BlockHeader, Storage};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use std::time::Duration;
 
 /// Integration test configuration
 /// Uses DATABASE_URL environment variable, or falls back to default local connection
@@ -63,6 +69,24 @@ async fn cleanup_chain_data(pool: &PgPool, chain_id: u64) {
         .await
         .expect("Failed to clean up events");
 
+    sqlx::query("DELETE FROM block_headers WHERE chain_id = $1")
+        .bind(chain_id as i64)
+        .execute(pool)
+        .await
+        .expect("Failed to clean up block headers");
+
+    sqlx::query("DELETE FROM failed_blocks WHERE chain_id = $1")
+        .bind(chain_id as i64)
+        .execute(pool)
+        .await
+        .expect("Failed to clean up failed blocks");
+
+    sqlx::query("DELETE FROM unknown_logs WHERE chain_id = $1")
+        .bind(chain_id as i64)
+        .execute(pool)
+        .await
+        .expect("Failed to clean up unknown logs");
+
     sqlx::query("DELETE FROM chain_sync_state WHERE chain_id = $1")
         .bind(chain_id as i64)
         .execute(pool)
@@ -71,6 +95,14 @@ async fn cleanup_chain_data(pool: &PgPool, chain_id: u64) {
 }
 
 /// Helper to create a realistic Registered event
+/// Commit a single event the way the indexer commits block ranges (cursor untouched)
+async fn store_event(storage: &Storage, event: Event) {
+    storage
+        .commit_block_range(event.chain_id, vec![event], &[], &[], None)
+        .await
+        .unwrap();
+}
+
 fn create_registered_event(chain_id: u64, block_number: u64, agent_id: &str) -> Event {
     Event {
         id: None,
@@ -87,6 +119,10 @@ fn create_registered_event(chain_id: u64, block_number: u64, agent_id: &str) ->
             owner: "0x742d35cc6634c0532925a3b844bc9e7595f0beb1".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -108,6 +144,10 @@ fn create_metadata_set_event(chain_id: u64, block_number: u64, agent_id: &str) -
             value: "0x5465737420416765".to_string(), // "Test Age" in hex
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -128,6 +168,10 @@ fn create_uri_updated_event(chain_id: u64, block_number: u64, agent_id: &str) ->
             updated_by: "0x742d35cc6634c0532925a3b844bc9e7595f0beb1".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -150,8 +194,16 @@ fn create_new_feedback_event(chain_id: u64, block_number: u64, agent_id: &str) -
             tag2: "responsive".to_string(),
             feedback_uri: "ipfs://QmFeedbackUri".to_string(),
             feedback_hash: "0xfeedbackhash123456789abcdef".to_string(),
+            feedback_index: None,
+            value: None,
+            value_decimals: None,
+            endpoint: None,
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -172,6 +224,10 @@ fn create_feedback_revoked_event(chain_id: u64, block_number: u64, agent_id: &st
             feedback_index: "0".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -195,6 +251,10 @@ fn create_response_appended_event(chain_id: u64, block_number: u64, agent_id: &s
             response_hash: "0xresponsehash123456789abcdef".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -216,6 +276,10 @@ fn create_validation_request_event(chain_id: u64, block_number: u64, agent_id: &
             request_hash: "0xrequesthash123456789abcdef".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -240,6 +304,10 @@ fn create_validation_response_event(chain_id: u64, block_number: u64, agent_id:
             tag: "verified".to_string(),
         }),
         created_at: None,
+        removed: false,
+        finalized: false,
+        raw: None,
+        spec_version: None,
     }
 }
 
@@ -259,15 +327,9 @@ async fn test_ethereum_sepolia_event_processing_and_storage() {
     let metadata_event = create_metadata_set_event(chain_id, 100, agent_id);
 
     // Store events
-    storage
-        .store_event(registered_event.clone())
-        .await
-        .expect("Failed to store Registered event");
+    store_event(&storage, registered_event.clone()).await;
 
-    storage
-        .store_event(metadata_event.clone())
-        .await
-        .expect("Failed to store MetadataSet event");
+    store_event(&storage, metadata_event.clone()).await;
 
     // Update last synced block
     storage
@@ -283,10 +345,12 @@ async fn test_ethereum_sepolia_event_processing_and_storage() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let events = storage
@@ -346,10 +410,7 @@ async fn test_base_sepolia_event_processing_and_storage() {
     let agent_id = "0x8004000000000000000000000000000000000002";
     let event = create_registered_event(chain_id, 200, agent_id);
 
-    storage
-        .store_event(event.clone())
-        .await
-        .expect("Failed to store event");
+    store_event(&storage, event.clone()).await;
 
     storage
         .update_last_synced_block_for_chain(chain_id, 200)
@@ -363,10 +424,12 @@ async fn test_base_sepolia_event_processing_and_storage() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let events = storage
@@ -393,10 +456,7 @@ async fn test_linea_sepolia_event_processing_and_storage() {
     let agent_id = "0x8004000000000000000000000000000000000003";
     let event = create_registered_event(chain_id, 300, agent_id);
 
-    storage
-        .store_event(event.clone())
-        .await
-        .expect("Failed to store event");
+    store_event(&storage, event.clone()).await;
 
     storage
         .update_last_synced_block_for_chain(chain_id, 300)
@@ -410,10 +470,12 @@ async fn test_linea_sepolia_event_processing_and_storage() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let events = storage
@@ -445,9 +507,9 @@ async fn test_multi_chain_isolation() {
     let linea_event =
         create_registered_event(59141, 100, "0x8004000000000000000000000000000000000003");
 
-    storage.store_event(eth_event).await.unwrap();
-    storage.store_event(base_event).await.unwrap();
-    storage.store_event(linea_event).await.unwrap();
+    store_event(&storage, eth_event).await;
+    store_event(&storage, base_event).await;
+    store_event(&storage, linea_event).await;
 
     // Query each chain separately
     let eth_query = api_8004_dev::models::EventQuery {
@@ -457,10 +519,12 @@ async fn test_multi_chain_isolation() {
         contract: None,
         event_type: None,
         agent_id: None,
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let eth_events = storage.get_recent_events(eth_query.clone()).await.unwrap();
@@ -505,10 +569,7 @@ async fn test_all_event_types_storage_and_retrieval() {
 
     // Store all events
     for event in &events {
-        storage
-            .store_event(event.clone())
-            .await
-            .expect("Failed to store event");
+        store_event(&storage, event.clone()).await;
     }
 
     storage
@@ -524,10 +585,12 @@ async fn test_all_event_types_storage_and_retrieval() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(20),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let retrieved_events = storage
@@ -620,6 +683,10 @@ async fn test_all_event_types_storage_and_retrieval() {
                 assert_eq!(data.response, 1);
                 assert_eq!(data.tag, "verified");
             }
+            EventData::Transfer(_) | EventData::Approval(_) | EventData::ApprovalForAll(_) => {
+                panic!("Unexpected ownership event data")
+            }
+            EventData::Custom(_) => panic!("Unexpected custom event data"),
         }
     }
 
@@ -638,10 +705,7 @@ async fn test_polygon_amoy_event_processing() {
     let agent_id = "0x8004000000000000000000000000000000000004";
     let event = create_registered_event(chain_id, 400, agent_id);
 
-    storage
-        .store_event(event.clone())
-        .await
-        .expect("Failed to store event");
+    store_event(&storage, event.clone()).await;
 
     storage
         .update_last_synced_block_for_chain(chain_id, 400)
@@ -655,10 +719,12 @@ async fn test_polygon_amoy_event_processing() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let events = storage
@@ -685,10 +751,7 @@ async fn test_hedera_testnet_event_processing() {
     let agent_id = "0x8004000000000000000000000000000000000005";
     let event = create_registered_event(chain_id, 500, agent_id);
 
-    storage
-        .store_event(event.clone())
-        .await
-        .expect("Failed to store event");
+    store_event(&storage, event.clone()).await;
 
     storage
         .update_last_synced_block_for_chain(chain_id, 500)
@@ -702,10 +765,12 @@ async fn test_hedera_testnet_event_processing() {
         contract: None,
         event_type: None,
         agent_id: Some(agent_id.to_string()),
+        spec_version: None,
         category: None,
         limit: Some(10),
         offset: Some(0),
         include_stats: false,
+        finalized_only: false,
     };
 
     let events = storage
@@ -722,37 +787,1723 @@ async fn test_hedera_testnet_event_processing() {
 
 #[tokio::test]
 #[ignore]
-async fn test_crash_recovery_block_minus_one() {
+async fn test_crash_recovery_resumes_from_committed_cursor() {
     let (pool, storage) = setup_test_db().await;
     let chain_id = 999999; // Use a unique chain ID to avoid conflicts
 
     // Clean up any existing data for this chain
     cleanup_chain_data(&pool, chain_id).await;
 
-    // Simulate processing blocks 100, 101, 102
-    for block in 100..=102 {
-        let event = create_registered_event(
+    // Commit blocks 100, 101, 102 together with the cursor
+    let events: Vec<Event> = (100..=102)
+        .map(|block| {
+            create_registered_event(
+                chain_id,
+                block,
+                &format!("0x800400000000000000000000000000000000{:04x}", block),
+            )
+        })
+        .collect();
+    let header = BlockHeader {
+        block_number: 102,
+        block_hash: format!("0x{:064x}", 102),
+        parent_hash: format!("0x{:064x}", 101),
+        block_timestamp: chrono::Utc::now(),
+    };
+    let inserted = storage
+        .commit_block_range(
             chain_id,
-            block,
-            &format!("0x800400000000000000000000000000000000{:04x}", block),
-        );
-        storage.store_event(event).await.unwrap();
+            events.clone(),
+            &[],
+            std::slice::from_ref(&header),
+            Some(103),
+        )
+        .await
+        .unwrap();
+    assert_eq!(inserted.len(), 3);
+    assert!(inserted.iter().all(|e| e.id.is_some()));
+
+    // The cursor points at the next block to sync, so recovery resumes there directly
+    let last_synced = storage
+        .get_last_synced_block_for_chain(chain_id)
+        .await
+        .unwrap();
+    assert_eq!(last_synced, 103, "Should resume from the committed cursor");
+    let stored_header = storage.get_block_header(chain_id, 102).await.unwrap();
+    assert_eq!(stored_header.map(|h| h.block_hash), Some(header.block_hash));
+
+    // Re-committing the same range inserts nothing and does not double count
+    let inserted = storage
+        .commit_block_range(chain_id, events, &[], &[], Some(103))
+        .await
+        .unwrap();
+    assert!(inserted.is_empty());
+
+    let sync_state = storage
+        .get_chain_sync_state(chain_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sync_state.total_events_indexed, 3);
+
+    println!("✅ Crash recovery (committed cursor) test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_reorg_rollback_to_common_ancestor() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999998;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Index blocks 100..=104 with one event and one header each
+    for block in 100..=104u64 {
         storage
-            .update_last_synced_block_for_chain(chain_id, block)
+            .commit_block_range(
+                chain_id,
+                vec![create_registered_event(chain_id, block, &block.to_string())],
+                &[],
+                &[BlockHeader {
+                    block_number: block,
+                    block_hash: format!("0x{:064x}", block),
+                    parent_hash: format!("0x{:064x}", block - 1),
+                    block_timestamp: chrono::Utc::now(),
+                }],
+                Some(block + 1),
+            )
             .await
             .unwrap();
     }
 
-    // Verify last synced block
+    // Headers below the orphaned block are returned newest first
+    let headers = storage
+        .get_block_headers_before(chain_id, 104)
+        .await
+        .unwrap();
+    assert_eq!(headers.first().map(|h| h.block_number), Some(103));
+
+    // Blocks 103 and 104 were orphaned, 102 is the common ancestor
+    let removed = storage
+        .rollback_chain_to_block(chain_id, 102)
+        .await
+        .unwrap();
+    assert_eq!(removed.len(), 2);
+    assert_eq!(removed[0].block_number, 104);
+    assert_eq!(removed[1].block_number, 103);
+
+    // Cursor points at the first block to re-index
     let last_synced = storage
         .get_last_synced_block_for_chain(chain_id)
         .await
         .unwrap();
-    assert_eq!(last_synced, 102);
+    assert_eq!(last_synced, 103);
+
+    assert!(storage
+        .get_block_header(chain_id, 103)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(storage
+        .get_block_header(chain_id, 102)
+        .await
+        .unwrap()
+        .is_some());
+
+    // Re-indexing the new fork must not be blocked by the cache
+    store_event(&storage, create_registered_event(chain_id, 103, "103")).await;
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM events WHERE chain_id = $1")
+        .bind(chain_id as i64)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 4);
+
+    println!("✅ Reorg rollback test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_finalized_events_filter() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999997;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    for block in 200..=204u64 {
+        store_event(
+            &storage,
+            create_registered_event(chain_id, block, &block.to_string()),
+        )
+        .await;
+    }
+
+    let query = |finalized_only| api_8004_dev::models::EventQuery {
+        chain_id: Some(chain_id.to_string()),
+        hours: None,
+        blocks: None,
+        contract: None,
+        event_type: None,
+        agent_id: None,
+        spec_version: None,
+        category: None,
+        limit: Some(10),
+        offset: Some(0),
+        include_stats: false,
+        finalized_only,
+    };
+
+    // Nothing is finalized yet
+    let events = storage.get_recent_events(query(true)).await.unwrap();
+    assert!(events.is_empty());
+
+    // Blocks up to 202 become final
+    let upgraded = storage.mark_events_finalized(chain_id, 202).await.unwrap();
+    assert_eq!(upgraded, 3);
+
+    // Already finalized events are not upgraded twice
+    let upgraded = storage.mark_events_finalized(chain_id, 202).await.unwrap();
+    assert_eq!(upgraded, 0);
+
+    let events = storage.get_recent_events(query(true)).await.unwrap();
+    assert_eq!(events.len(), 3);
+    assert!(events.iter().all(|e| e.finalized && e.block_number <= 202));
+
+    let events = storage.get_recent_events(query(false)).await.unwrap();
+    assert_eq!(events.len(), 5);
+
+    println!("✅ Finalized events filter test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_failed_block_retry_queue() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999996;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Queue two ranges; only the first one is due immediately
+    storage
+        .record_failed_block_range(chain_id, 100, 199, "timeout", Duration::ZERO)
+        .await
+        .unwrap();
+    storage
+        .record_failed_block_range(chain_id, 300, 399, "timeout", Duration::from_secs(3600))
+        .await
+        .unwrap();
+
+    let due = storage
+        .get_due_failed_block_ranges(chain_id, 10)
+        .await
+        .unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!((due[0].from_block, due[0].to_block), (100, 199));
+    assert_eq!(due[0].attempts, 1);
+
+    // A failed retry counts as another attempt and is rescheduled
+    storage
+        .record_failed_block_range(chain_id, 100, 199, "rate limited", Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert!(storage
+        .get_due_failed_block_ranges(chain_id, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let queued = storage.get_failed_block_ranges(chain_id).await.unwrap();
+    assert_eq!(queued.len(), 2);
+    assert_eq!(queued[0].attempts, 2);
+    assert_eq!(queued[0].last_error, "rate limited");
+
+    // A reorg below a queued range trims the queue to the common ancestor
+    storage
+        .update_last_synced_block_for_chain(chain_id, 400)
+        .await
+        .unwrap();
+    storage
+        .rollback_chain_to_block(chain_id, 150)
+        .await
+        .unwrap();
+    let queued = storage.get_failed_block_ranges(chain_id).await.unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!((queued[0].from_block, queued[0].to_block), (100, 150));
+
+    // A successful retry removes the range
+    storage
+        .delete_failed_block_range(chain_id, 100)
+        .await
+        .unwrap();
+    assert!(storage
+        .get_failed_block_ranges(chain_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    println!("✅ Failed block retry queue test passed!");
+}
+
+/// Helper to create a raw dead-letter log carrying an encoded IdentityRegistry `Registered` event
+fn create_unknown_log(chain_id: u64, block_number: u64, registry: &str) -> UnknownLog {
+    use alloy::primitives::{Address, U256};
+    use alloy::sol_types::SolEvent;
+    use api_8004_dev::contracts::IdentityRegistry;
+
+    let log_data = IdentityRegistry::Registered {
+        agentId: U256::from(block_number),
+        tokenURI: format!("ipfs://agent-{}", block_number),
+        owner: Address::repeat_byte(0x11),
+    }
+    .encode_log_data();
+
+    UnknownLog {
+        id: None,
+        chain_id,
+        block_number,
+        block_timestamp: chrono::Utc::now(),
+        transaction_hash: format!("0x{:064x}", block_number),
+        log_index: 0,
+        contract_address: "0x8004a6090cd10a7288092483047b097295fb8847".to_string(),
+        registry: registry.to_string(),
+        topics: log_data
+            .topics()
+            .iter()
+            .map(|t| format!("{:?}", t))
+            .collect(),
+        data: format!("0x{}", hex::encode(&log_data.data)),
+        error: "Unknown event".to_string(),
+        created_at: None,
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_unknown_logs_dead_letter_and_redecode() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999995;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Block 300's log decodes once the right decoder is used, 301's never does,
+    // 302's is orphaned by a reorg
+    let unknown_logs = vec![
+        create_unknown_log(chain_id, 300, "identity"),
+        create_unknown_log(chain_id, 301, "reputation"),
+        create_unknown_log(chain_id, 302, "identity"),
+    ];
+    storage
+        .commit_block_range(chain_id, vec![], &unknown_logs, &[], Some(303))
+        .await
+        .unwrap();
+
+    let query = UnknownLogQuery {
+        chain_id: Some(chain_id),
+        ..Default::default()
+    };
+    let stored = storage.get_unknown_logs(&query).await.unwrap();
+    assert_eq!(stored.len(), 3);
+    assert_eq!(stored[0].topics, unknown_logs[0].topics);
+    assert_eq!(stored[0].data, unknown_logs[0].data);
+
+    // Re-committing the same range does not duplicate dead letters
+    storage
+        .commit_block_range(chain_id, vec![], &unknown_logs, &[], Some(303))
+        .await
+        .unwrap();
+    assert_eq!(storage.count_unknown_logs(Some(chain_id)).await.unwrap(), 3);
+
+    storage
+        .rollback_chain_to_block(chain_id, 301)
+        .await
+        .unwrap();
+    assert_eq!(storage.count_unknown_logs(Some(chain_id)).await.unwrap(), 2);
+
+    // Re-decode: only the identity log succeeds and moves into the events table
+    let registry = DecoderRegistry::builtin();
+    let pending = storage
+        .get_unknown_logs_after(Some(chain_id), 0, 100)
+        .await
+        .unwrap();
+    let resolved: Vec<(i64, Event)> = pending
+        .iter()
+        .filter_map(|log| Some((log.id?, registry.redecode_unknown_log(log).ok()?)))
+        .collect();
+    assert_eq!(resolved.len(), 1);
+
+    let inserted = storage.resolve_unknown_logs(resolved).await.unwrap();
+    assert_eq!(inserted.len(), 1);
+    assert_eq!(inserted[0].event_type, EventType::Registered);
+    assert_eq!(inserted[0].block_number, 300);
+
+    let remaining = storage.get_unknown_logs(&query).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].block_number, 301);
+
+    println!("✅ Unknown logs dead-letter test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_redecode_events_from_raw_logs() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999994;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Decode a real log so the event carries its raw topics and data
+    let unknown = create_unknown_log(chain_id, 400, "identity");
+    let mut decoded = DecoderRegistry::builtin()
+        .redecode_unknown_log(&unknown)
+        .unwrap();
+    assert!(decoded.raw.is_some());
+    decoded.finalized = true;
+
+    // An event indexed before raw logs were stored cannot be re-decoded
+    let legacy = create_registered_event(chain_id, 401, "legacy");
+
+    storage
+        .commit_block_range(chain_id, vec![decoded, legacy], &[], &[], Some(402))
+        .await
+        .unwrap();
+
+    // Simulate a decoder bug that stored wrong event data
+    sqlx::query(
+        "UPDATE events SET event_data = jsonb_set(event_data, '{token_uri}', '\"corrupted\"') WHERE chain_id = $1 AND block_number = 400",
+    )
+    .bind(chain_id as i64)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let summary = reindex::redecode_events(&storage, &ChainDecoders::default(), Some(chain_id))
+        .await
+        .unwrap();
+    assert_eq!(summary.scanned, 2);
+    assert_eq!(summary.updated, 1);
+    assert_eq!(summary.missing_raw, 1);
+    assert_eq!(summary.failed, 0);
+
+    let token_uri: String = sqlx::query_scalar(
+        "SELECT event_data->>'token_uri' FROM events WHERE chain_id = $1 AND block_number = 400",
+    )
+    .bind(chain_id as i64)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(token_uri, "ipfs://agent-400");
+
+    // A second pass has nothing left to fix
+    let summary = reindex::redecode_events(&storage, &ChainDecoders::default(), Some(chain_id))
+        .await
+        .unwrap();
+    assert_eq!(summary.updated, 0);
+
+    println!("✅ Re-decode from raw logs test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_custom_abi_events_storage() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999993;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let mut fields = serde_json::Map::new();
+    fields.insert("agent_id".to_string(), serde_json::json!("7"));
+    fields.insert("client".to_string(), serde_json::json!("0xclient"));
+    fields.insert("feedback_index".to_string(), serde_json::json!("1"));
+
+    let mut custom = create_registered_event(chain_id, 500, "7");
+    custom.transaction_hash = format!("0x{:064x}", 501);
+    custom.event_type = EventType::Custom("FeedbackArchived".to_string());
+    custom.event_data = EventData::Custom(fields);
+
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 500, "7"), custom],
+            &[],
+            &[],
+            Some(501),
+        )
+        .await
+        .unwrap();
 
-    // Simulate crash recovery: should resume from block 101 (102 - 1)
-    let resume_from = last_synced.saturating_sub(1);
-    assert_eq!(resume_from, 101, "Should resume from last_synced - 1");
+    // Custom events filter by name and agent_id like built-in events
+    let query = EventQuery {
+        chain_id: Some(chain_id.to_string()),
+        blocks: None,
+        event_type: Some("FeedbackArchived".to_string()),
+        agent_id: Some("7".to_string()),
+        ..Default::default()
+    };
+    let events = storage.get_recent_events(query).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].event_type,
+        EventType::Custom("FeedbackArchived".to_string())
+    );
+    // Fields shared with a built-in event don't turn the data into that event
+    assert!(matches!(events[0].event_data, EventData::Custom(_)));
+
+    let counts = storage.get_event_counts_by_type(chain_id).await.unwrap();
+    assert_eq!(counts.registered, 1);
+    assert_eq!(counts.custom.get("FeedbackArchived"), Some(&1));
+
+    println!("✅ Custom ABI events storage test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_spec_version_storage() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999992;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let mut v1 = create_registered_event(chain_id, 600, "8");
+    v1.spec_version = Some("v1".to_string());
+    let mut v2 = create_registered_event(chain_id, 700, "9");
+    v2.spec_version = Some("v2".to_string());
 
-    println!("✅ Crash recovery (block - 1) test passed!");
+    storage
+        .commit_block_range(chain_id, vec![v1, v2], &[], &[], Some(701))
+        .await
+        .unwrap();
+
+    let query = |spec_version: Option<&str>| EventQuery {
+        chain_id: Some(chain_id.to_string()),
+        blocks: None,
+        spec_version: spec_version.map(str::to_string),
+        ..Default::default()
+    };
+
+    let events = storage.get_recent_events(query(None)).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].spec_version.as_deref(), Some("v2"));
+    assert_eq!(events[1].spec_version.as_deref(), Some("v1"));
+
+    let events = storage.get_recent_events(query(Some("v2"))).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].block_number, 700);
+
+    println!("✅ Spec version storage test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_agent_owner_from_transfers() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999991;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let transfer = |block_number: u64, agent_id: &str, from: &str, to: &str| Event {
+        event_type: EventType::Transfer,
+        transaction_hash: format!("0x{:064x}", block_number + 100),
+        event_data: EventData::Transfer(TransferData {
+            agent_id: agent_id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+        ..create_registered_event(chain_id, block_number, agent_id)
+    };
+    let original_owner = "0x742d35cc6634c0532925a3b844bc9e7595f0beb1";
+    let new_owner = "0x1111111111111111111111111111111111111111";
+
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 800, "5")],
+            &[],
+            &[],
+            Some(801),
+        )
+        .await
+        .unwrap();
+
+    let owner = storage
+        .get_agent_owner(chain_id, "5", None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(owner.owner, original_owner);
+    assert_eq!(owner.source_event, EventType::Registered);
+
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![
+                transfer(810, "5", original_owner, new_owner),
+                transfer(811, "6", new_owner, original_owner),
+            ],
+            &[],
+            &[],
+            Some(812),
+        )
+        .await
+        .unwrap();
+
+    let owner = storage
+        .get_agent_owner(chain_id, "5", None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(owner.owner, new_owner);
+    assert_eq!(owner.block_number, 810);
+    assert_eq!(owner.source_event, EventType::Transfer);
+
+    assert!(storage
+        .get_agent_owner(chain_id, "7", None)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Agent IDs restart in a redeployed identity registry
+    let registry_v2 = "0x2222222222222222222222222222222222222222";
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![Event {
+                contract_address: registry_v2.to_string(),
+                transaction_hash: format!("0x{:064x}", 900),
+                event_data: EventData::Registered(RegisteredData {
+                    agent_id: "5".to_string(),
+                    token_uri: "ipfs://QmV2".to_string(),
+                    owner: original_owner.to_string(),
+                }),
+                ..create_registered_event(chain_id, 900, "5")
+            }],
+            &[],
+            &[],
+            Some(901),
+        )
+        .await
+        .unwrap();
+
+    let v1_registry = create_registered_event(chain_id, 800, "5").contract_address;
+    let owner = storage
+        .get_agent_owner(chain_id, "5", Some(&v1_registry))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(owner.owner, new_owner);
+    assert_eq!(owner.block_number, 810);
+
+    let owner = storage
+        .get_agent_owner(chain_id, "5", Some(registry_v2))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(owner.owner, original_owner);
+    assert_eq!(owner.contract_address, registry_v2);
+    assert!(storage
+        .get_agent_owner(chain_id, "6", Some(registry_v2))
+        .await
+        .unwrap()
+        .is_none());
+
+    println!("✅ Agent owner from transfers test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_chain_status_stopped() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999990;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Committing a range creates the chain's sync state
+    storage
+        .commit_block_range(chain_id, vec![], &[], &[], Some(100))
+        .await
+        .unwrap();
+
+    // The status constraint accepts the status written on graceful shutdown
+    storage
+        .update_chain_status(chain_id, ChainStatus::Stopped, None)
+        .await
+        .unwrap();
+
+    let sync_state = storage
+        .get_chain_sync_state(chain_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sync_state.status, "stopped");
+    assert_eq!(sync_state.last_synced_block, 100);
+
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Chain stopped status test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_chain_rewind_and_pause() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999989;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let events = vec![create_registered_event(chain_id, 100, "1")];
+    storage
+        .commit_block_range(chain_id, events.clone(), &[], &[], Some(101))
+        .await
+        .unwrap();
+
+    // Rewinding moves the cursor and the stored chain tip; stored events stay
+    let parent = BlockHeader {
+        block_number: 49,
+        block_hash: format!("0x{:064x}", 49),
+        parent_hash: format!("0x{:064x}", 48),
+        block_timestamp: chrono::Utc::now(),
+    };
+    assert!(storage
+        .rewind_chain_to_block(chain_id, 50, Some(&parent))
+        .await
+        .unwrap());
+    // The cursor is never moved forward
+    assert!(!storage
+        .rewind_chain_to_block(chain_id, 80, None)
+        .await
+        .unwrap());
+    let stored = storage.get_block_header(chain_id, 49).await.unwrap();
+    assert_eq!(stored.map(|h| h.block_hash), Some(parent.block_hash));
+    storage
+        .update_chain_status(chain_id, ChainStatus::Paused, None)
+        .await
+        .unwrap();
+
+    let sync_state = storage
+        .get_chain_sync_state(chain_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(sync_state.last_synced_block, 50);
+    assert_eq!(sync_state.status, "paused");
+    assert_eq!(sync_state.total_events_indexed, 1);
+
+    // Re-indexing the rewound range does not duplicate events
+    let inserted = storage
+        .commit_block_range(chain_id, events, &[], &[], Some(101))
+        .await
+        .unwrap();
+    assert!(inserted.is_empty());
+
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Chain rewind and pause test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_indexer_command_queue() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999981;
+    let unknown_chain_id = 999980;
+
+    cleanup_chain_data(&pool, chain_id).await;
+    for id in [chain_id, unknown_chain_id] {
+        sqlx::query("DELETE FROM indexer_commands WHERE chain_id = $1")
+            .bind(id as i64)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    sqlx::query("DELETE FROM chains WHERE chain_id = $1")
+        .bind(unknown_chain_id as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // Commands are only queued for enabled chains
+    let first = storage
+        .queue_indexer_command(chain_id, &IndexerCommand::Pause, "admin")
+        .await
+        .unwrap()
+        .unwrap();
+    let second = storage
+        .queue_indexer_command(chain_id, &IndexerCommand::Rewind { block: 42 }, "admin")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(second > first);
+    assert!(storage
+        .queue_indexer_command(unknown_chain_id, &IndexerCommand::Resume, "admin")
+        .await
+        .unwrap()
+        .is_none());
+
+    // The lock holder takes them once, in request order
+    let commands = storage.take_indexer_commands(chain_id).await.unwrap();
+    assert_eq!(
+        commands,
+        vec![IndexerCommand::Pause, IndexerCommand::Rewind { block: 42 }]
+    );
+    assert!(storage
+        .take_indexer_commands(chain_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    sqlx::query("DELETE FROM indexer_commands WHERE chain_id = $1")
+        .bind(chain_id as i64)
+        .execute(&pool)
+        .await
+        .unwrap();
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Indexer command queue test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_sync_configured_chains() {
+    let (pool, storage) = setup_test_db().await;
+
+    let chain = |chain_id: u64, name: &str| -> ChainConfig {
+        serde_yaml::from_str(&format!(
+            r#"
+name: "{name}"
+chain_id: {chain_id}
+enabled: true
+rpc_url: "https://eth-sepolia.g.alchemy.com/v2/AbCdEf0123456789xyzABCDEF0123456"
+contracts:
+  identity_registry: "0x8004a6090Cd10A7288092483047B097295Fb8847"
+  reputation_registry: "0x8004B8FD1A363aa02fDC07635C0c5F94f6Af5B7E"
+  validation_registry: "0x8004CB39f29c09145F24Ad9dDe2A108C1A2cdfC5"
+starting_block: "latest"
+poll_interval_ms: 5000
+"#
+        ))
+        .unwrap()
+    };
+
+    let test_chain_ids = [999988_i64, 999987];
+    for chain_id in test_chain_ids {
+        cleanup_chain_data(&pool, chain_id as u64).await;
+        sqlx::query("DELETE FROM chains WHERE chain_id = $1")
+            .bind(chain_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    // Syncing disables every chain left out of the configuration; the chains of other tests
+    // sharing the database are enabled again right away, so only this test's rows change
+    let other_chain_ids: Vec<i64> =
+        sqlx::query_scalar("SELECT chain_id FROM chains WHERE enabled AND chain_id <> ALL($1)")
+            .bind(&test_chain_ids[..])
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    let sync = |chains: Vec<ChainConfig>| {
+        let (pool, storage, other_chain_ids) = (&pool, &storage, &other_chain_ids);
+        async move {
+            let result = storage.sync_configured_chains(&chains).await;
+            sqlx::query("UPDATE chains SET enabled = true WHERE chain_id = ANY($1)")
+                .bind(other_chain_ids)
+                .execute(pool)
+                .await
+                .unwrap();
+            result.unwrap();
+        }
+    };
+
+    // New chains are inserted with a redacted RPC URL and a sync state row
+    sync(vec![
+        chain(999988, "Sync Test A"),
+        chain(999987, "Sync Test B"),
+    ])
+    .await;
+
+    let chains = storage.get_chains().await.unwrap();
+    let a = chains.iter().find(|c| c.chain_id == 999988).unwrap();
+    assert_eq!(a.name, "Sync Test A");
+    assert_eq!(a.rpc_url, "https://eth-sepolia.g.alchemy.com/v2/***");
+    assert_eq!(a.status.as_deref(), Some("active"));
+
+    storage
+        .update_chain_status(999987, ChainStatus::Syncing, None)
+        .await
+        .unwrap();
+    let state = storage.get_chain_sync_state(999987).await.unwrap().unwrap();
+    assert_eq!(state.status, "syncing");
+
+    // Removed chains are disabled but keep their rows; renamed chains are updated
+    sync(vec![chain(999988, "Sync Test A (renamed)")]).await;
+
+    let chains = storage.get_chains().await.unwrap();
+    let a = chains.iter().find(|c| c.chain_id == 999988).unwrap();
+    assert_eq!(a.name, "Sync Test A (renamed)");
+    assert!(a.enabled);
+
+    // Disabled chains are still listed, with their last status
+    let b = chains.iter().find(|c| c.chain_id == 999987).unwrap();
+    assert!(!b.enabled);
+    assert_eq!(b.status.as_deref(), Some("syncing"));
+
+    for chain_id in [999988, 999987] {
+        cleanup_chain_data(&pool, chain_id).await;
+    }
+    println!("✅ Chains table sync test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_chain_lock_leader_election() {
+    let (_pool, storage) = setup_test_db().await;
+
+    // First instance becomes the leader
+    let lock = storage
+        .try_acquire_chain_lock(999986, "instance-a")
+        .await
+        .unwrap()
+        .expect("Lock should be free");
+
+    // A second instance stands by while the lock is held
+    assert!(storage
+        .try_acquire_chain_lock(999986, "instance-b")
+        .await
+        .unwrap()
+        .is_none());
+
+    let holders = storage.get_chain_lock_holders().await.unwrap();
+    let holder = holders.get(&999986).expect("Lock holder should be listed");
+    assert_eq!(holder.instance_id.as_deref(), Some("instance-a"));
+    assert!(holder.since.is_some());
+
+    // Releasing the lock lets the standby take over
+    lock.release().await;
+    assert!(!storage
+        .get_chain_lock_holders()
+        .await
+        .unwrap()
+        .contains_key(&999986));
+
+    let mut lock = storage
+        .try_acquire_chain_lock(999986, "instance-b")
+        .await
+        .unwrap()
+        .expect("Lock should be free after release");
+    assert!(lock.is_held().await);
+
+    // Dropping the holder (e.g. a crashed process) releases the lock as well
+    drop(lock);
+    let mut takeover = None;
+    for _ in 0..50 {
+        takeover = storage
+            .try_acquire_chain_lock(999986, "instance-a")
+            .await
+            .unwrap();
+        if takeover.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    takeover
+        .expect("Lock should be released when its connection closes")
+        .release()
+        .await;
+
+    println!("✅ Chain lock leader election test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_chain_lock_fences_writes() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999979;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Chain ids differing only above 32 bits get their own lock
+    let wide_chain_id = chain_id + (1 << 32);
+    let lock = storage
+        .try_acquire_chain_lock(chain_id, "instance-a")
+        .await
+        .unwrap()
+        .expect("Lock should be free");
+    let wide_lock = storage
+        .try_acquire_chain_lock(wide_chain_id, "instance-a")
+        .await
+        .unwrap()
+        .expect("Lock should not be shared with another chain");
+    let holders = storage.get_chain_lock_holders().await.unwrap();
+    assert!(holders.contains_key(&chain_id));
+    assert!(holders.contains_key(&wide_chain_id));
+    wide_lock.release().await;
+
+    // Writes go through while the lock is held
+    store_event(&storage, create_registered_event(chain_id, 100, "1")).await;
+
+    // Once the lock connection is gone, writes fail instead of racing the next holder
+    sqlx::query(
+        r#"
+        SELECT pg_terminate_backend(pid) FROM pg_locks
+        WHERE locktype = 'advisory' AND objsubid = 1 AND classid = 0 AND objid::bigint = $1
+        "#,
+    )
+    .bind(chain_id as i64)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut fenced = false;
+    for _ in 0..50 {
+        let result = storage
+            .commit_block_range(
+                chain_id,
+                vec![create_registered_event(chain_id, 101, "2")],
+                &[],
+                &[],
+                None,
+            )
+            .await;
+        if result.is_err() {
+            fenced = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(fenced, "Writes should fail after the lock is lost");
+    assert!(storage.rollback_chain_to_block(chain_id, 0).await.is_err());
+
+    // Releasing the lost lock lifts the fence
+    lock.release().await;
+    assert!(storage.rollback_chain_to_block(chain_id, 0).await.is_ok());
+
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Chain lock fencing test passed!");
+}
+
+/// Start an outbox dispatcher serving only the WebSocket broadcast sink
+/// The sink's cursor is moved to the end of the outbox first, so only new events are announced
+async fn start_broadcast_dispatcher(
+    storage: &Storage,
+    shutdown: &tokio_util::sync::CancellationToken,
+) -> tokio::task::JoinHandle<()> {
+    use api_8004_dev::outbox::{BroadcastSink, EventSink, OutboxDispatcher};
+    use std::sync::Arc;
+
+    let sink = BroadcastSink::new(storage.clone());
+    while let Some(batch) = storage.claim_outbox_batch(sink.name(), 1000).await.unwrap() {
+        let caught_up = batch.len() < 1000;
+        batch.ack().await.unwrap();
+        if caught_up {
+            break;
+        }
+    }
+
+    let sinks: Vec<Arc<dyn EventSink>> = vec![Arc::new(sink)];
+    tokio::spawn(OutboxDispatcher::new(storage.clone(), sinks).run(shutdown.clone()))
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_event_notifications_reach_listeners() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999985;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // An API-only process listens for events announced by the broadcast sink
+    let (event_tx, mut event_rx) = tokio::sync::broadcast::channel(100);
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let listener = tokio::spawn(api_8004_dev::storage::notify::forward_events(
+        storage.clone(),
+        event_tx,
+        shutdown.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // An indexer process commits the events and serves the broadcast sink
+    let indexer_storage = Storage::new(pool.clone(), 1000);
+    let dispatcher = start_broadcast_dispatcher(&indexer_storage, &shutdown).await;
+    let events: Vec<Event> = (100..=101)
+        .map(|block| create_registered_event(chain_id, block, &format!("{}", block)))
+        .collect();
+    let inserted = indexer_storage
+        .commit_block_range(chain_id, events, &[], &[], Some(102))
+        .await
+        .unwrap();
+    assert_eq!(inserted.len(), 2);
+
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let event: Event = tokio::time::timeout(Duration::from_secs(5), event_rx.recv())
+            .await
+            .expect("Announced event should be forwarded")
+            .unwrap();
+        if event.chain_id == chain_id {
+            received.push(event);
+        }
+    }
+    assert_eq!(received[0].id, inserted[0].id);
+    assert_eq!(received[1].block_number, 101);
+    assert!(!received[1].removed);
+
+    // Rolled back events are forwarded as removed
+    indexer_storage
+        .rollback_chain_to_block(chain_id, 100)
+        .await
+        .unwrap();
+    let removed = loop {
+        let event: Event = tokio::time::timeout(Duration::from_secs(5), event_rx.recv())
+            .await
+            .expect("Rolled back event should be forwarded")
+            .unwrap();
+        if event.chain_id == chain_id {
+            break event;
+        }
+    };
+    assert!(removed.removed);
+    assert_eq!(removed.block_number, 101);
+    assert!(
+        matches!(removed.event_data, EventData::Registered(ref data) if data.agent_id == "101")
+    );
+
+    shutdown.cancel();
+    listener.await.unwrap();
+    dispatcher.await.unwrap();
+
+    println!("✅ Event notification test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_event_notifications_forward_each_event_once() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999984;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let (event_tx, mut event_rx) = tokio::sync::broadcast::channel(100);
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let listener = tokio::spawn(api_8004_dev::storage::notify::forward_events(
+        storage.clone(),
+        event_tx,
+        shutdown.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let dispatcher = start_broadcast_dispatcher(&storage, &shutdown).await;
+
+    // Events committed by this process and by another instance both go through the outbox
+    let local = storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 100, "100")],
+            &[],
+            &[],
+            Some(101),
+        )
+        .await
+        .unwrap();
+    let other_instance = Storage::new(pool.clone(), 1000);
+    let remote = other_instance
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 101, "101")],
+            &[],
+            &[],
+            Some(102),
+        )
+        .await
+        .unwrap();
+
+    // Repeated announcements, e.g. of a batch delivered again after a failed ack, add nothing
+    storage.announce_outbox().await.unwrap();
+    other_instance.announce_outbox().await.unwrap();
+
+    let mut received = Vec::new();
+    while let Ok(Ok(event)) =
+        tokio::time::timeout(Duration::from_millis(3000), event_rx.recv()).await
+    {
+        if event.chain_id == chain_id {
+            received.push(event.id);
+        }
+    }
+    assert_eq!(received, vec![local[0].id, remote[0].id]);
+
+    shutdown.cancel();
+    listener.await.unwrap();
+    dispatcher.await.unwrap();
+
+    println!("✅ Event forwarding deduplication test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_event_listener_catches_up_after_reconnect() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999976;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    let (event_tx, mut event_rx) = tokio::sync::broadcast::channel(100);
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let listener = tokio::spawn(api_8004_dev::storage::notify::forward_events(
+        storage.clone(),
+        event_tx,
+        shutdown.clone(),
+    ));
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Events are committed without being announced, then the listener loses its connection
+    let inserted = storage
+        .commit_block_range(
+            chain_id,
+            vec![
+                create_registered_event(chain_id, 100, "100"),
+                create_registered_event(chain_id, 101, "101"),
+            ],
+            &[],
+            &[],
+            Some(102),
+        )
+        .await
+        .unwrap();
+    let terminated: Vec<bool> = sqlx::query_scalar(
+        "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+         WHERE query LIKE 'LISTEN%' AND pid <> pg_backend_pid()",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert!(!terminated.is_empty());
+
+    // The listener reads the outbox once it has reconnected
+    let mut received = Vec::new();
+    while let Ok(Ok(event)) =
+        tokio::time::timeout(Duration::from_millis(3000), event_rx.recv()).await
+    {
+        if event.chain_id == chain_id {
+            received.push(event.id);
+        }
+        if received.len() == 2 {
+            storage.announce_outbox().await.unwrap();
+        }
+    }
+    assert_eq!(received, vec![inserted[0].id, inserted[1].id]);
+
+    shutdown.cancel();
+    listener.await.unwrap();
+
+    println!("✅ Event listener reconnect test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_event_outbox_cursor() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999983;
+    let sink = "test:outbox-cursor";
+
+    cleanup_chain_data(&pool, chain_id).await;
+    sqlx::query("DELETE FROM outbox_cursors WHERE sink = $1")
+        .bind(sink)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // New sinks start at the end of the outbox
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(batch.is_empty());
+    batch.ack().await.unwrap();
+
+    let events: Vec<Event> = (100..=102)
+        .map(|block| create_registered_event(chain_id, block, &format!("{}", block)))
+        .collect();
+    storage
+        .commit_block_range(chain_id, events, &[], &[], Some(103))
+        .await
+        .unwrap();
+    storage
+        .rollback_chain_to_block(chain_id, 101)
+        .await
+        .unwrap();
+
+    let outbox_events = |batch: &api_8004_dev::storage::outbox::OutboxBatch| -> Vec<(u64, bool)> {
+        batch
+            .events
+            .iter()
+            .filter(|e| e.chain_id == chain_id)
+            .map(|e| (e.block_number, e.removed))
+            .collect()
+    };
+
+    // Inserted and rolled back events come in commit order
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        outbox_events(&batch),
+        vec![(100, false), (101, false), (102, false), (102, true)]
+    );
+
+    // While claimed, the sink is not served by another instance
+    let other_instance = Storage::new(pool.clone(), 1000);
+    assert!(other_instance
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .is_none());
+
+    // Released batches are delivered again
+    batch.release().await.unwrap();
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(outbox_events(&batch).len(), 4);
+
+    // Once its lease expired, the sink is taken over and the late acknowledgement is refused
+    sqlx::query(
+        "UPDATE outbox_cursors SET lease_until = NOW() - INTERVAL '1 second' WHERE sink = $1",
+    )
+    .bind(sink)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let taken_over = other_instance
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(batch.ack().await.is_err());
+    assert_eq!(outbox_events(&taken_over).len(), 4);
+    taken_over.ack().await.unwrap();
+
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(outbox_events(&batch).is_empty());
+    batch.ack().await.unwrap();
+
+    // An entry written by a transaction that is still running holds back newer entries, so it
+    // cannot commit behind the cursor
+    let mut slow_tx = pool.begin().await.unwrap();
+    sqlx::query("INSERT INTO event_outbox (chain_id, removed, payload) VALUES ($1, false, $2)")
+        .bind(chain_id as i64)
+        .bind(serde_json::to_value(create_registered_event(chain_id, 200, "200")).unwrap())
+        .execute(&mut *slow_tx)
+        .await
+        .unwrap();
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 201, "201")],
+            &[],
+            &[],
+            None,
+        )
+        .await
+        .unwrap();
+
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(outbox_events(&batch).is_empty());
+    batch.ack().await.unwrap();
+
+    // The held back entry is reported through the horizon lag
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(storage.outbox_horizon_lag().await.unwrap() >= Duration::from_millis(200));
+
+    slow_tx.commit().await.unwrap();
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(outbox_events(&batch), vec![(200, false), (201, false)]);
+    batch.ack().await.unwrap();
+
+    println!("✅ Event outbox cursor test passed!");
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_outbox_prune_keeps_undelivered_entries() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999978;
+    let sink = "test:prune";
+
+    cleanup_chain_data(&pool, chain_id).await;
+    // Only this test's sink may hold the outbox back (other tests re-create their cursors)
+    sqlx::query("DELETE FROM outbox_cursors")
+        .execute(&pool)
+        .await
+        .unwrap();
+    storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap()
+        .ack()
+        .await
+        .unwrap();
+
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 100, "100")],
+            &[],
+            &[],
+            Some(101),
+        )
+        .await
+        .unwrap();
+    let outbox_entries = || async {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM event_outbox WHERE chain_id = $1")
+            .bind(chain_id as i64)
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    };
+
+    // Entries the sink has not acknowledged are kept, however old, and show up as lag
+    sqlx::query(
+        "UPDATE event_outbox SET created_at = NOW() - INTERVAL '30 days' WHERE chain_id = $1",
+    )
+    .bind(chain_id as i64)
+    .execute(&pool)
+    .await
+    .unwrap();
+    storage.prune_outbox(Duration::ZERO).await.unwrap();
+    assert_eq!(outbox_entries().await, 1);
+
+    let lag = storage.outbox_sink_lag().await.unwrap();
+    assert_eq!(lag.len(), 1);
+    assert_eq!(lag[0].0, sink);
+    assert!(lag[0].1 >= Duration::from_secs(29 * 24 * 3600));
+
+    // Once acknowledged, they are pruned and the sink has no lag
+    let batch = storage
+        .claim_outbox_batch(sink, 1000)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!batch.is_empty());
+    batch.ack().await.unwrap();
+    storage.prune_outbox(Duration::ZERO).await.unwrap();
+    assert_eq!(outbox_entries().await, 0);
+    assert_eq!(
+        storage.outbox_sink_lag().await.unwrap(),
+        vec![(sink.to_string(), Duration::ZERO)]
+    );
+
+    sqlx::query("DELETE FROM outbox_cursors WHERE sink = $1")
+        .bind(sink)
+        .execute(&pool)
+        .await
+        .unwrap();
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Outbox pruning test passed!");
+}
+
+/// Sink recording delivered events, failing its first delivery
+struct FlakySink {
+    chain_id: u64,
+    attempts: std::sync::atomic::AtomicU32,
+    delivered: tokio::sync::Mutex<Vec<u64>>,
+}
+
+#[axum::async_trait]
+impl api_8004_dev::outbox::EventSink for FlakySink {
+    fn name(&self) -> &str {
+        "test:flaky"
+    }
+
+    async fn deliver(&self, events: &[Event]) -> anyhow::Result<()> {
+        if self
+            .attempts
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            == 0
+        {
+            return Err(anyhow::anyhow!("Sink unavailable"));
+        }
+        let mut delivered = self.delivered.lock().await;
+        delivered.extend(
+            events
+                .iter()
+                .filter(|e| e.chain_id == self.chain_id)
+                .map(|e| e.block_number),
+        );
+        Ok(())
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_outbox_dispatcher_retries_failed_deliveries() {
+    use api_8004_dev::outbox::{EventSink, OutboxDispatcher};
+    use std::sync::Arc;
+
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999982;
+
+    cleanup_chain_data(&pool, chain_id).await;
+    sqlx::query("DELETE FROM outbox_cursors WHERE sink = 'test:flaky'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    storage
+        .claim_outbox_batch("test:flaky", 1000)
+        .await
+        .unwrap()
+        .unwrap()
+        .ack()
+        .await
+        .unwrap();
+
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![
+                create_registered_event(chain_id, 100, "100"),
+                create_registered_event(chain_id, 101, "101"),
+            ],
+            &[],
+            &[],
+            Some(102),
+        )
+        .await
+        .unwrap();
+
+    let sink = Arc::new(FlakySink {
+        chain_id,
+        attempts: Default::default(),
+        delivered: Default::default(),
+    });
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let dispatcher = tokio::spawn(
+        OutboxDispatcher::new(storage.clone(), vec![sink.clone() as Arc<dyn EventSink>])
+            .run(shutdown.clone()),
+    );
+
+    // The first delivery fails; the batch is delivered again after the retry delay
+    for _ in 0..50 {
+        if !sink.delivered.lock().await.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    shutdown.cancel();
+    dispatcher.await.unwrap();
+
+    assert_eq!(*sink.delivered.lock().await, vec![100, 101]);
+    assert!(sink.attempts.load(std::sync::atomic::Ordering::SeqCst) >= 2);
+
+    println!("✅ Outbox dispatcher retry test passed!");
+}
+
+/// Hash of block `number` on the chain served by `mock_chain_rpc`
+fn mock_block_hash(number: u64) -> String {
+    format!("0x{:064x}", 0xb10c_0000_u64 + number)
+}
+
+/// JSON-RPC server for a chain without registry logs, whose head is `head`
+async fn mock_chain_rpc(chain_id: u64, head: u64) -> String {
+    use axum::{routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    let block = move |number: u64| {
+        json!({
+            "hash": mock_block_hash(number),
+            "parentHash": mock_block_hash(number.saturating_sub(1)),
+            "sha3Uncles": format!("0x{:064x}", 0),
+            "miner": format!("0x{:040x}", 0),
+            "stateRoot": format!("0x{:064x}", 0),
+            "transactionsRoot": format!("0x{:064x}", 0),
+            "receiptsRoot": format!("0x{:064x}", 0),
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "difficulty": "0x0",
+            "number": format!("0x{:x}", number),
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "timestamp": format!("0x{:x}", 1_700_000_000 + number * 12),
+            "extraData": "0x",
+            "mixHash": format!("0x{:064x}", 0),
+            "nonce": "0x0000000000000000",
+            "baseFeePerGas": "0x7",
+            "uncles": [],
+            "transactions": [],
+        })
+    };
+    let app = Router::new().route(
+        "/",
+        post(move |Json(request): Json<Value>| async move {
+            let result = match request["method"].as_str() {
+                Some("eth_chainId") => json!(format!("0x{:x}", chain_id)),
+                Some("eth_blockNumber") => json!(format!("0x{:x}", head)),
+                Some("eth_getBlockByNumber") => {
+                    let number = request["params"][0]
+                        .as_str()
+                        .and_then(|tag| u64::from_str_radix(tag.trim_start_matches("0x"), 16).ok())
+                        .unwrap_or(head);
+                    block(number)
+                }
+                Some("eth_getLogs") => json!([]),
+                _ => Value::Null,
+            };
+            Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    url
+}
+
+/// Indexer of `chain_id` polling the mock chain at `rpc_url`
+async fn mock_chain_indexer(
+    chain_id: u64,
+    rpc_url: &str,
+    storage: &Storage,
+) -> api_8004_dev::indexer::Indexer {
+    use api_8004_dev::config::{GlobalConfig, IndexerConfig};
+    use api_8004_dev::rpc::ProviderManager;
+    use std::sync::Arc;
+
+    let chain: ChainConfig = serde_yaml::from_str(&format!(
+        r#"
+name: "Mock Chain {chain_id}"
+chain_id: {chain_id}
+enabled: true
+rpc_url: "{rpc_url}"
+contracts:
+  identity_registry: "0x8004a6090Cd10A7288092483047B097295Fb8847"
+  reputation_registry: "0x8004B8FD1A363aa02fDC07635C0c5F94f6Af5B7E"
+  validation_registry: "0x8004CB39f29c09145F24Ad9dDe2A108C1A2cdfC5"
+starting_block: "100"
+poll_interval_ms: 10
+confirmations: 0
+"#
+    ))
+    .unwrap();
+    let global: GlobalConfig = serde_yaml::from_str("{}").unwrap();
+    let config = IndexerConfig::from_chain_config(&chain, &global).unwrap();
+    let provider_manager = Arc::new(
+        ProviderManager::new(
+            config.rpc_providers.clone(),
+            config.provider_strategy,
+            chain_id,
+            config.name.clone(),
+        )
+        .unwrap(),
+    );
+
+    api_8004_dev::indexer::Indexer::new(
+        config,
+        provider_manager,
+        storage.clone(),
+        api_8004_dev::stats::StatsTracker::new(),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_rewind_resyncs_without_rolling_back() {
+    let (pool, storage) = setup_test_db().await;
+    let chain_id = 999977;
+    let rpc_url = mock_chain_rpc(chain_id, 110).await;
+
+    cleanup_chain_data(&pool, chain_id).await;
+
+    // Synced up to block 110; headers are only kept at range ends and blocks with events
+    let header = |block_number: u64| BlockHeader {
+        block_number,
+        block_hash: mock_block_hash(block_number),
+        parent_hash: mock_block_hash(block_number - 1),
+        block_timestamp: chrono::Utc::now(),
+    };
+    storage
+        .commit_block_range(
+            chain_id,
+            vec![create_registered_event(chain_id, 105, "105")],
+            &[],
+            &[header(100), header(105), header(110)],
+            Some(111),
+        )
+        .await
+        .unwrap();
+
+    let indexer = mock_chain_indexer(chain_id, &rpc_url, &storage).await;
+    assert!(indexer.rewind(103).await.unwrap());
+    assert!(!indexer.rewind(120).await.unwrap());
+    assert_eq!(
+        storage
+            .get_last_synced_block_for_chain(chain_id)
+            .await
+            .unwrap(),
+        103
+    );
+    assert!(storage
+        .get_block_header(chain_id, 105)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        storage
+            .get_block_header(chain_id, 102)
+            .await
+            .unwrap()
+            .map(|h| h.block_hash),
+        Some(mock_block_hash(102))
+    );
+
+    // Re-syncing from the rewound cursor up to the head is not mistaken for a reorg
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let run = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { indexer.start(shutdown).await }
+    });
+    for _ in 0..100 {
+        if storage
+            .get_last_synced_block_for_chain(chain_id)
+            .await
+            .unwrap()
+            == 110
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    shutdown.cancel();
+    run.await.unwrap().unwrap();
+
+    assert_eq!(
+        storage
+            .get_last_synced_block_for_chain(chain_id)
+            .await
+            .unwrap(),
+        110
+    );
+    // A rollback would have deleted the event, as the mock chain has no logs to re-index
+    let stored: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM events WHERE chain_id = $1 AND block_number = 105",
+    )
+    .bind(chain_id as i64)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, 1);
+
+    cleanup_chain_data(&pool, chain_id).await;
+    println!("✅ Rewind re-sync test passed!");
 }